@@ -1,18 +1,28 @@
 use std::slice;
+use std::fmt;
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::path::Path;
 use rayon::prelude::*;
 use std::mem::{self, size_of};
 use ash::vk::{self, VertexInputAttributeDescription, ShaderStageFlags, RenderPassBeginInfoBuilder, VertexInputBindingDescription};
 use nalgebra::base::Matrix4;
 use nalgebra::Isometry3;
-use nokden::{Handle, Storage, offset_of, AssetPath};
-use nokden::graphics::{Shader, GraphicsSystem};
+use nokden::{Handle, Storage, StorageKeys, offset_of, AssetPath, Framework, SystemEvents, ConsoleCommand};
+use nokden::vfs::Vfs;
+use nokden::serialize::{Serialize, Deserialize, Serializer, Deserializer, SerializeError};
+use nokden::save::SaveSection;
+use nokden::graphics::{Shader, GraphicsSystem, GraphicsError};
 
 /// Renders a non-animated mesh at a specific location.
 pub struct MeshSystem
 {
     shader: Shader,
     pub assets: Storage<MeshAsset>,
-    pub instances: Storage<MeshInstance>
+    pub instances: Storage<MeshInstance>,
+
+    /// Tick of the instances at the last fixed step, see [MeshSystem::store_previous].
+    stored: u64
 }
 
 impl MeshSystem
@@ -77,7 +87,8 @@ impl MeshSystem
                 vert_in_asmb_info
             ),
             assets: Storage::new(),
-            instances: Storage::new()
+            instances: Storage::new(),
+            stored: 0
         }
     }
 
@@ -85,7 +96,8 @@ impl MeshSystem
     (
         &self,
         graphics: &GraphicsSystem,
-        world_camera: &Matrix4<f32>
+        world_camera: &Matrix4<f32>,
+        alpha: f32
     )
     {
         unsafe
@@ -93,10 +105,14 @@ impl MeshSystem
             let dv = &graphics.device;
             dv.logical.cmd_bind_pipeline(dv.draw_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.shader.pipeline[0]);
 
-            for instance in self.instances.all()
+            for instance in self.instances.iter()
             {
                 let instance = instance.read().unwrap();
-                let mesh_asset = self.assets.read(&instance.mesh);
+                let mesh_asset = match self.assets.read(&instance.mesh)
+                {
+                    Some(mesh_asset) => mesh_asset,
+                    None => continue
+                };
 
                 dv.logical.cmd_bind_vertex_buffers(dv.draw_command_buffer, 0, &[mesh_asset.vertex_buffer], &[0]);
                 dv.logical.cmd_bind_index_buffer(dv.draw_command_buffer, mesh_asset.index_buffer, 0, vk::IndexType::UINT32); // TODO Needs to use UINT16.
@@ -106,7 +122,12 @@ impl MeshSystem
                 let c_u8: *const u8 = c_u32 as *const _;
                 let bytes_camera: &[u8] = slice::from_raw_parts(c_u8, mem::size_of::<Matrix4<f32>>());
 
-                let mvp = instance.transform.to_homogeneous();
+                // Instances that did not move since the last fixed step keep their matrix.
+                let mvp = match instance.previous == instance.transform
+                {
+                    true => instance.model,
+                    false => instance.previous.lerp_slerp(&instance.transform, alpha).to_homogeneous()
+                };
                 let c_u32: *const Matrix4<f32> = &mvp;
                 let c_u8: *const u8 = c_u32 as *const _;
                 let bytes_model_position: &[u8] = slice::from_raw_parts(c_u8, mem::size_of::<Matrix4<f32>>());
@@ -118,25 +139,76 @@ impl MeshSystem
         }
     }
 
+    /// Frees the assets no instance or other handle points to anymore, with their buffers.
+    /// Call it outside of a frame, the buffers of the previous frame are done by then.
+    pub fn release_unused
+    (
+        &mut self
+    )
+    {
+        // Released instances drop their asset handles, so instances go first.
+        self.instances.release_dropped();
+        self.assets.release_dropped();
+    }
+
+    /// Called before every fixed step, rendering interpolates from the transforms kept.
+    /// Only instances written since the last step are visited, the others already hold their transform as the previous one.
+    /// Writes through the locks of [Storage::iter] need [Storage::mark_changed] to be seen.
+    pub fn store_previous
+    (
+        &mut self
+    )
+    {
+        for instance in self.instances.changed_since(self.stored)
+        {
+            let mut instance = instance.write().unwrap();
+            instance.previous = instance.transform;
+            instance.model = instance.transform.to_homogeneous();
+        }
+        self.stored = self.instances.tick();
+    }
+
     pub fn load_asset_obj
     (
         &mut self,
         asset_path: AssetPath,
+        vfs: &Vfs,
         graphics: &GraphicsSystem,
     )
-    -> Handle<MeshAsset>
+    -> Result<Handle<MeshAsset>, MeshError>
+    {
+        let (indexes, input) = Self::read_obj(&asset_path, vfs)?;
+        Ok(self.assets.add(MeshAsset::new(graphics, asset_path.0.display().to_string(), indexes, input)?))
+    }
+
+    /// Indices and vertices of every model of an OBJ file, colored by the diffuse color of its material.
+    pub fn read_obj
+    (
+        asset_path: &AssetPath,
+        vfs: &Vfs
+    )
+    -> Result<(Vec<u32>, Vec<VertexInput>), MeshError>
     {        
         const VERTEX_PER_FACE: u8 = 3;
-        let (models, textures) = tobj::load_obj(&asset_path.0, false).unwrap();
+        let path = asset_path.0.display().to_string();
+        let bytes = asset_path.read(vfs).map_err(|err| MeshError::Load(path.clone(), err.to_string()))?;
+
+        // The loader only knows the material library failed, not why.
+        let material_error = RefCell::new(None);
+        let load_materials = |name: &Path| match asset_path.sibling(name).read(vfs)
+        {
+            Ok(materials) => tobj::load_mtl_buf(&mut Cursor::new(materials)),
+            Err(err) =>
+            {
+                *material_error.borrow_mut() = Some(err.to_string());
+                Err(tobj::LoadError::OpenFileFailed)
+            }
+        };
+        let (models, textures) = tobj::load_obj_buf(&mut Cursor::new(bytes), false, load_materials)
+            .map_err(|err| MeshError::Load(path.clone(), material_error.take().unwrap_or_else(|| err.to_string())))?;
         if models.is_empty() || textures.is_empty()
         {
-            panic!
-            (
-                "Some data is empty: Models {}, Textures {}, for file {}.",
-                models.len(),
-                textures.len(),
-                &asset_path.0.display()
-            );
+            Err(MeshError::Empty { path, models: models.len(), textures: textures.len() })
         }
         else
         {
@@ -145,6 +217,12 @@ impl MeshSystem
             let mut positions = Vec::new();
             for model in models
             {
+                let diffuse = match model.mesh.material_id.and_then(|material_id| textures.get(material_id))
+                {
+                    Some(texture) => texture.diffuse,
+                    None => return Err(MeshError::NoMaterial(path))
+                };
+
                 for surface_index in &model.mesh.indices
                 {
                     indexes.push(*surface_index + positions.len() as u32);
@@ -164,9 +242,9 @@ impl MeshSystem
                     colors.push
                     (
                         [
-                            textures[model.mesh.material_id.unwrap()].diffuse[0],
-                            textures[model.mesh.material_id.unwrap()].diffuse[1],
-                            textures[model.mesh.material_id.unwrap()].diffuse[2],
+                            diffuse[0],
+                            diffuse[1],
+                            diffuse[2],
                             1.0
                         ]
                     );
@@ -200,28 +278,125 @@ impl MeshSystem
                 }
             }
     
-            if ((input.len() - 1) as u32) < highest
+            if input.len() as u32 <= highest
             {
-                panic!
-                (
-                    "The highest vertex index value is not allowed to be higher than the count of inputs minus one. Max Input Index: {}, Highest Detected: {}",
-                    input.len() - 1,
-                    highest
-                );
+                return Err(MeshError::IndexOutOfRange { path, inputs: input.len(), highest });
             }
             
-            self.assets.add(MeshAsset::new(&graphics, indexes.clone(), input))
+            Ok((indexes, input))
         }        
     }
 }
 
+impl SystemEvents for MeshSystem
+{
+    fn name
+    (
+        &self
+    )
+    -> &'static str
+    {
+        "meshes"
+    }
+
+    fn commands
+    (
+        &self
+    )
+    -> Vec<ConsoleCommand>
+    {
+        Vec::new()
+    }
+
+    fn console
+    (
+        &mut self,
+        _framework: &mut Framework,
+        _command: &ConsoleCommand
+    )
+    {
+    }
+
+    /// Only instances are saved with the path of each asset, assets are loaded again on start and matched by path for the handles of the instances.
+    fn save_load
+    (
+        &mut self,
+        _framework: &mut Framework,
+        section: SaveSection
+    )
+    -> Result<(), SerializeError>
+    {
+        match section
+        {
+            SaveSection::Save(serializer) =>
+            {
+                serializer.field("assets", &StorageKeys::new(&self.assets, |asset| asset.path.clone()));
+                serializer.field("instances", &self.instances);
+            }
+            SaveSection::Load(deserializer, staged) =>
+            {
+                let assets: StorageKeys<String> = deserializer.field("assets")?;
+                deserializer.bind_storage_by_key(&self.assets, &assets, |asset| asset.path.clone());
+                let instances: Storage<MeshInstance> = deserializer.field("instances")?;
+                staged.stage(instances);
+            }
+            SaveSection::Commit(staged) =>
+            {
+                if let Some(instances) = staged.take()
+                {
+                    self.instances = instances;
+                    self.stored = 0;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum MeshError
+{
+    /// The file could not be read or parsed, with the path and the reason.
+    Load(String, String),
+    Empty { path: String, models: usize, textures: usize },
+    NoMaterial(String),
+
+    /// A face points past the last vertex.
+    IndexOutOfRange { path: String, inputs: usize, highest: u32 },
+    Graphics(GraphicsError)
+}
+
+impl fmt::Display for MeshError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            MeshError::Load(path, err) => write!(f, "Mesh {} not loaded: {}", path, err),
+            MeshError::Empty { path, models, textures } => write!(f, "Mesh {} has some data empty: Models {}, Textures {}.", path, models, textures),
+            MeshError::NoMaterial(path) => write!(f, "Mesh {} has a model without a known material.", path),
+            MeshError::IndexOutOfRange { path, inputs, highest } => write!(f, "Mesh {} has vertex index {} with only {} vertices.", path, highest, inputs),
+            MeshError::Graphics(err) => write!(f, "Mesh buffers not created: {}", err)
+        }
+    }
+}
+
+/// Owns its buffers, they are destroyed when the asset storage releases the asset.
 pub struct MeshAsset
 {
+    /// Asset path it was loaded from, names it in save games.
+    pub path: String,
+    device: ash::Device,
     index_count: u32,
     index_buffer: vk::Buffer,
-    index_memory: vk::DeviceMemory, // TODO Perhaps has no use after new function.
+    index_memory: vk::DeviceMemory,
     vertex_buffer: vk::Buffer,
-    vertex_memory: vk::DeviceMemory // TODO Perhaps has no use after new function.
+    vertex_memory: vk::DeviceMemory
 }
 
 impl MeshAsset
@@ -229,32 +404,122 @@ impl MeshAsset
     pub fn new
     (
         graphics: &GraphicsSystem,
+        path: String,
         indices: Vec<u32>,
         vertices: Vec<VertexInput>,
     )
-    -> MeshAsset
+    -> Result<MeshAsset, MeshError>
     {
-        let (index_buffer, index_memory) = graphics.bind_buffer_memory(&indices, vk::BufferUsageFlags::INDEX_BUFFER);
-        let (vertex_buffer, vertex_memory) = graphics.bind_buffer_memory(&vertices, vk::BufferUsageFlags::VERTEX_BUFFER);
+        let device = graphics.device.logical.clone();
+        let (index_buffer, index_memory) = graphics.bind_buffer_memory(&indices, vk::BufferUsageFlags::INDEX_BUFFER).map_err(MeshError::Graphics)?;
+        let (vertex_buffer, vertex_memory) = match graphics.bind_buffer_memory(&vertices, vk::BufferUsageFlags::VERTEX_BUFFER)
+        {
+            Ok(bound) => bound,
+            Err(err) =>
+            {
+                unsafe
+                {
+                    device.destroy_buffer(index_buffer, None);
+                    device.free_memory(index_memory, None);
+                }
+                return Err(MeshError::Graphics(err));
+            }
+        };
 
         //let accelleration_geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::builder().
             
 
-        MeshAsset
+        Ok(MeshAsset
         {
+            path,
+            device,
             index_count: indices.len() as u32,
             index_buffer,
             index_memory,
             vertex_buffer,
             vertex_memory
-        }
+        })
     }    
 }
 
+impl Drop for MeshAsset
+{
+    fn drop
+    (
+        &mut self
+    )
+    {
+        unsafe
+        {
+            self.device.destroy_buffer(self.index_buffer, None);
+            self.device.free_memory(self.index_memory, None);
+            self.device.destroy_buffer(self.vertex_buffer, None);
+            self.device.free_memory(self.vertex_memory, None);
+        }
+    }
+}
+
 pub struct MeshInstance
 {
     pub transform: Isometry3<f32>,
-    pub mesh: Handle<MeshAsset>
+
+    /// Transform before the last fixed step.
+    pub previous: Isometry3<f32>,
+    pub mesh: Handle<MeshAsset>,
+
+    /// Matrix of the transform, drawn while the instance does not move.
+    model: Matrix4<f32>
+}
+
+impl MeshInstance
+{
+    pub fn new
+    (
+        transform: Isometry3<f32>,
+        mesh: Handle<MeshAsset>
+    )
+    -> MeshInstance
+    {
+        MeshInstance
+        {
+            transform,
+            previous: transform,
+            mesh,
+            model: transform.to_homogeneous()
+        }
+    }
+}
+
+/// Loading needs the asset storage bound with [Deserializer::bind_storage_by_key] first.
+impl Serialize for MeshInstance
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("transform", &self.transform);
+        serializer.field("mesh", &self.mesh);
+        serializer.end();
+    }
+}
+
+impl Deserialize for MeshInstance
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<MeshInstance, SerializeError>
+    {
+        deserializer.begin()?;
+        let transform = deserializer.field("transform")?;
+        let instance = MeshInstance::new(transform, deserializer.field("mesh")?);
+        deserializer.end()?;
+        Ok(instance)
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -262,4 +527,87 @@ pub struct VertexInput
 {
     pub position: [f32; 3],
     pub color: [f32; 4],
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::path::PathBuf;
+    use nokden::vfs::MountSource;
+
+    /// A mounted directory with the given files.
+    fn vfs
+    (
+        name: &str,
+        files: &[(&str, &str)]
+    )
+    -> Vfs
+    {
+        let directory = std::env::temp_dir().join(format!("enamorf-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        for (file, text) in files
+        {
+            std::fs::write(directory.join(file), text).unwrap();
+        }
+
+        let mut vfs = Vfs::new();
+        vfs.mount("test", MountSource::Directory(directory));
+        vfs
+    }
+
+    fn read
+    (
+        vfs: &Vfs,
+        file: &str
+    )
+    -> Result<(Vec<u32>, Vec<VertexInput>), MeshError>
+    {
+        MeshSystem::read_obj(&AssetPath(PathBuf::from(file)), vfs)
+    }
+
+    #[test]
+    fn read_obj
+    ()
+    {
+        const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+        let vfs = vfs
+        (
+            "read-obj",
+            &[
+                ("red.mtl", "newmtl red\nKd 1 0 0\n"),
+                ("triangle.obj", &format!("mtllib red.mtl\nusemtl red\n{}", TRIANGLE)),
+                ("no_library.obj", TRIANGLE),
+                ("missing_library.obj", &format!("mtllib missing.mtl\nusemtl red\n{}", TRIANGLE)),
+                ("no_material.obj", &format!("mtllib red.mtl\n{}", TRIANGLE))
+            ]
+        );
+
+        let (indexes, input) = read(&vfs, "triangle.obj").unwrap();
+        assert_eq!(indexes, [0, 1, 2]);
+        assert_eq!(input.len(), 3);
+        assert_eq!(input[1].position, [1.0, 0.0, 0.0]);
+        assert!(input.iter().all(|vertex| vertex.color == [1.0, 0.0, 0.0, 1.0]));
+
+        assert!(matches!(read(&vfs, "missing.obj"), Err(MeshError::Load(path, _)) if path == "missing.obj"));
+        assert!(matches!(read(&vfs, "no_library.obj"), Err(MeshError::Empty { models: 1, textures: 0, .. })));
+        assert!(matches!(read(&vfs, "no_material.obj"), Err(MeshError::NoMaterial(_))));
+
+        // The reason the material library failed is kept.
+        let err = read(&vfs, "missing_library.obj").err().unwrap();
+        let reason = vfs.read(Path::new("missing.mtl")).err().unwrap().to_string();
+        assert_eq!(err.to_string(), format!("Mesh missing_library.obj not loaded: {}", reason));
+    }
+
+    #[test]
+    fn display
+    ()
+    {
+        let path = String::from("cube.obj");
+        assert_eq!(MeshError::Empty { path: path.clone(), models: 1, textures: 0 }.to_string(), "Mesh cube.obj has some data empty: Models 1, Textures 0.");
+        assert_eq!(MeshError::NoMaterial(path.clone()).to_string(), "Mesh cube.obj has a model without a known material.");
+        assert_eq!(MeshError::IndexOutOfRange { path, inputs: 3, highest: 7 }.to_string(), "Mesh cube.obj has vertex index 7 with only 3 vertices.");
+        assert_eq!(MeshError::Graphics(GraphicsError::NoMemoryType).to_string(), "Mesh buffers not created: The device has no fitting memory type.");
+    }
 }
\ No newline at end of file
@@ -5,6 +5,8 @@ use nokden::input::{InputSystem, Mapping, MethodKM};
 use nokden::graphics::{GraphicsSystem};
 use enamorf::mesh::{MeshSystem, MeshInstance};
 use nokden::*;
+use nokden::serialize::SerializeError;
+use nokden::save::{SaveSection, QUICKSAVE_SLOT};
 
 const CAMERA_SPEED: f32 = 10.0;
 
@@ -22,7 +24,8 @@ pub struct GameSystem
 {
     state: GameState,
 
-    rotate_neticas: Handle<MeshInstance>,
+    /// Without meshes when headless.
+    rotate_neticas: Option<Handle<MeshInstance>>,
     tiles: Vec<Handle<MeshInstance>>,
 
     input_camera_forward: Handle<Mapping>,
@@ -44,64 +47,16 @@ impl GameSystem
     pub(crate) fn new
     (        
         input: &mut InputSystem,
-        graphics: &mut GraphicsSystem,
-        meshes: &mut MeshSystem,
+        graphics: Option<&mut GraphicsSystem>,
+        meshes: Option<&mut MeshSystem>,
         framework: &mut Framework
     )    
     -> GameSystem
     {
-        graphics.world_camera.transform = Isometry3::look_at_rh
-        (
-            &Point3::new(0.0, 15.0, -10.0),
-            &Point3::origin(),
-            &Vector3::y()
-        );        
-
-        let rotate_neticas =
-        {
-            let mesh_asset = meshes.load_asset_obj(framework.asset_path(Path::new("neticas.obj")), &graphics);
-            meshes.instances.add
-            (
-                MeshInstance
-                {
-                    transform: Isometry3::identity(),
-                    mesh: mesh_asset
-                }
-            )
-        };
-
-        let tiles = 
+        let (rotate_neticas, tiles) = match (graphics, meshes)
         {
-            let mesh_asset = meshes.load_asset_obj(framework.asset_path(Path::new("tile_test.obj")), &graphics);
-            let mut tiles: Vec<Handle<MeshInstance>> = Vec::new();
-            let tile_count = MAP_SIZE as u64 * MAP_SIZE as u64;
-            for index in 0..tile_count
-            {
-                let position = index.to_2D_square(MAP_SIZE as u64);
-                let map_center = (MAP_SIZE / 2) as f32 * TILE_METERS + TILE_METERS / 2.0;
-     
-                tiles.push
-                (
-                    meshes.instances.add
-                    (
-                        MeshInstance
-                        {
-                            transform: Isometry3::new
-                            (
-                                Vector3::new
-                                (
-                                    position[0] as f32 * TILE_METERS - map_center,
-                                    0.0,
-                                    position[1] as f32 * TILE_METERS - map_center,
-                                ),
-                                Vector3::zeros()
-                            ),
-                            mesh: meshes.assets.duplicate(&mesh_asset)
-                        }
-                    )
-                );
-            }            
-            tiles 
+            (Some(graphics), Some(meshes)) => Self::scene(graphics, meshes, framework),
+            _ => (None, Vec::new())
         };
 
         GameSystem
@@ -123,12 +78,81 @@ impl GameSystem
         }
     }
 
+    /// Camera and meshes of the test map, meshes failing to load are reported and left out.
+    fn scene
+    (
+        graphics: &mut GraphicsSystem,
+        meshes: &mut MeshSystem,
+        framework: &mut Framework
+    )
+    -> (Option<Handle<MeshInstance>>, Vec<Handle<MeshInstance>>)
+    {
+        graphics.world_camera.transform = Isometry3::look_at_rh
+        (
+            &Point3::new(0.0, 15.0, -10.0),
+            &Point3::origin(),
+            &Vector3::y()
+        );        
+
+        let rotate_neticas = match meshes.load_asset_obj(framework.asset_path(Path::new("neticas.obj")), framework.vfs(), graphics)
+        {
+            Ok(mesh_asset) => Some(meshes.instances.add(MeshInstance::new(Isometry3::identity(), mesh_asset))),
+            Err(err) =>
+            {
+                error!("mesh", "{}", err);
+                None
+            }
+        };
+
+        let tiles = match meshes.load_asset_obj(framework.asset_path(Path::new("tile_test.obj")), framework.vfs(), graphics)
+        {
+            Ok(mesh_asset) =>
+            {
+                let mut tiles: Vec<Handle<MeshInstance>> = Vec::new();
+                let tile_count = MAP_SIZE as u64 * MAP_SIZE as u64;
+                for index in 0..tile_count
+                {
+                    let position = index.to_2D_square(MAP_SIZE as u64);
+                    let map_center = (MAP_SIZE / 2) as f32 * TILE_METERS + TILE_METERS / 2.0;
+         
+                    tiles.push
+                    (
+                        meshes.instances.add
+                        (
+                            MeshInstance::new
+                            (
+                                Isometry3::new
+                                (
+                                    Vector3::new
+                                    (
+                                        position[0] as f32 * TILE_METERS - map_center,
+                                        0.0,
+                                        position[1] as f32 * TILE_METERS - map_center,
+                                    ),
+                                    Vector3::zeros()
+                                ),
+                                meshes.assets.duplicate(&mesh_asset)
+                            )
+                        )
+                    );
+                }            
+                tiles
+            }
+            Err(err) =>
+            {
+                error!("mesh", "{}", err);
+                Vec::new()
+            }
+        };
+
+        (rotate_neticas, tiles)
+    }
+
     pub(crate) fn update
     (
         &mut self,
         input: &mut InputSystem,
-        meshes: &mut MeshSystem,
-        graphics: &mut GraphicsSystem,
+        graphics: Option<&mut GraphicsSystem>,
         framework: &mut Framework,
     )
     {
@@ -139,7 +163,7 @@ impl GameSystem
 
         if input.check_once(&self.input_print_mapping)
         {
-            input.print_mappings();
+            input.print_mappings(framework.console());
         }
 
         if input.check_once(&self.input_default_mapping)
@@ -149,12 +173,12 @@ impl GameSystem
 
         if input.check_once(&self.input_load)
         {
-            framework.save_load(SaveLoad::Load);
+            framework.save_load(SaveLoad::Load(QUICKSAVE_SLOT.to_string()));
         }
 
         if input.check_once(&self.input_save)
         {
-            framework.save_load(SaveLoad::Save);
+            framework.save_load(SaveLoad::Save(QUICKSAVE_SLOT.to_string()));
         }
 
         match self.state
@@ -192,38 +216,93 @@ impl GameSystem
                 let value = nodes.storage.read(node).matrix * 4.0;
             }
         );*/       
+    }
 
-        meshes.instances.write(&self.rotate_neticas).transform.delta_rotate
-        (
-            Point3::new(0.0, 30.0, 0.0),
-            Vector3::new(0.0, 90.0, 0.0),
-            framework.delta()
-        );
+    /// Simulation at the fixed rate, independent of the frame rate.
+    pub(crate) fn update_fixed
+    (
+        &mut self,
+        meshes: Option<&mut MeshSystem>,
+        framework: &mut Framework
+    )
+    {
+        if let Some(mut rotate_neticas) = meshes.zip(self.rotate_neticas.as_ref()).and_then(|(meshes, rotate_neticas)| meshes.instances.write(rotate_neticas))
+        {
+            rotate_neticas.transform.delta_rotate
+            (
+                Point3::new(0.0, 30.0, 0.0),
+                Vector3::new(0.0, 90.0, 0.0),
+                framework.fixed_delta()
+            );
+        }
     }
 }
 
 impl SystemEvents for GameSystem
 {
+    fn name
+    (
+        &self
+    )
+    -> &'static str
+    {
+        "game"
+    }
+
+    fn commands
+    (
+        &self
+    )
+    -> Vec<ConsoleCommand>
+    {
+        vec![ConsoleCommand::new("test", vec![ConsoleParameterKind::Bool]).describe("Prints its parameter.")]
+    }
+
     fn console
     (
         &mut self,
-        framework: &mut Framework
+        framework: &mut Framework,
+        command: &ConsoleCommand
     )
     {
-        let test = ConsoleCommand::new("test", vec![ConsoleCommandParameter::Bool]);
-
-        if framework.command_event() == &test
+        if command.keyword() == "test"
         {
-            println!("Test command with parameter {}.", framework.command_event().get_bool(0).unwrap());
+            framework.console().print(format!("Test command with parameter {}.", command.get::<bool>(0).unwrap()));
         }
     }
 
+    /// Needs the meshes section loaded first, the handles point into its instances.
+    /// Nothing is replaced unless everything loaded.
     fn save_load
     (
         &mut self,
-        framework: &mut Framework
+        _framework: &mut Framework,
+        section: SaveSection
     )
+    -> Result<(), SerializeError>
     {
-        println!("Saved.")    
+        match section
+        {
+            SaveSection::Save(serializer) =>
+            {
+                serializer.field("rotate_neticas", &self.rotate_neticas);
+                serializer.field("tiles", &self.tiles);
+            }
+            SaveSection::Load(deserializer, staged) =>
+            {
+                let rotate_neticas: Option<Handle<MeshInstance>> = deserializer.field("rotate_neticas")?;
+                let tiles: Vec<Handle<MeshInstance>> = deserializer.field("tiles")?;
+                staged.stage((rotate_neticas, tiles));
+            }
+            SaveSection::Commit(staged) =>
+            {
+                if let Some((rotate_neticas, tiles)) = staged.take()
+                {
+                    self.rotate_neticas = rotate_neticas;
+                    self.tiles = tiles;
+                }
+            }
+        }
+        Ok(())
     }
 }
\ No newline at end of file
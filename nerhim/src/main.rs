@@ -4,7 +4,7 @@ use winit::event::{DeviceEvent, WindowEvent};
 use nokden::input::{InputSystem};
 use enamorf::mesh::{MeshSystem};
 use nokden::graphics::{GraphicsSystem};
-use nokden::{ApplicationEvents, Framework, SystemEvents, SaveLoad};
+use nokden::{ApplicationEvents, Framework, StartupError};
 use nokden::widgets::{ConsoleWidget, ConsoleState};
 use game::{GameSystem};
 
@@ -13,16 +13,21 @@ fn main()
     nokden::run::<Application>();
 }
 
-pub struct Application
+nokden::find_traits!
 {
-    game: game::GameSystem,
-    input: InputSystem,
-    meshes: MeshSystem,
-    graphics: GraphicsSystem,
+    /// Meshes come before game, the game holds handles to mesh instances.
+    /// Meshes and graphics are left out when headless.
+    pub struct Application
+    {
+        meshes: Option<MeshSystem>,
+        input: InputSystem,
+        game: game::GameSystem,
+        graphics: Option<GraphicsSystem>,
 
-    //player: PlayerRig,
+        //player: PlayerRig,
 
-    console: ConsoleWidget   
+        console: ConsoleWidget
+    }
 }
 
 impl ApplicationEvents
@@ -60,23 +65,27 @@ for Application
     (
         framework: &mut Framework
     )
-    -> Application
+    -> Result<Application, StartupError>
     {
         let mut input = InputSystem::new();
-        let mut graphics = GraphicsSystem::new(&framework.window());
-        let mut meshes = MeshSystem::new(&graphics);
+        let mut graphics = match framework.is_headless()
+        {
+            true => None,
+            false => Some(GraphicsSystem::new(framework)?)
+        };
+        let mut meshes = graphics.as_ref().map(MeshSystem::new);
 
-        let game = GameSystem::new(&mut input, &mut graphics, &mut meshes, framework);
+        let game = GameSystem::new(&mut input, graphics.as_mut(), meshes.as_mut(), framework);
         let console = ConsoleWidget::new(&mut input);                    
 
-        Application
+        Ok(Application
         {
             game,
             input,
             graphics,
             console,
             meshes
-        }
+        })
     }
 
     fn update
@@ -85,7 +94,8 @@ for Application
         framework: &mut Framework        
     )
     {
-        self.game.update(&mut self.input, &mut self.meshes, &mut self.graphics, framework);
+        self.input.update_binds(framework);
+        self.game.update(&mut self.input, self.graphics.as_mut(), framework);
         match self.console.update(&mut self.input, framework)
         {
             ConsoleState::Opened => (),
@@ -93,53 +103,42 @@ for Application
         }        
     }
 
-    fn update_engine
-    (
-        &mut self,
-        device_event: &Vec<DeviceEvent>,
-        window_event: &Vec<WindowEvent>
-    )
-    {
-        for event in device_event
-        {
-            self.input.register_device_events(&event)
-        }        
-
-        let world_vp = self.graphics.world_camera.projection.as_matrix() * self.graphics.world_camera.transform.to_homogeneous();
-        let frame_index = self.graphics.frame_start();
-        self.meshes.update(&self.graphics, &world_vp);
-        self.graphics.frame_end(frame_index);
-    }    
-
-    fn console // TODO Needs macro to run all.
+    fn update_fixed
     (
         &mut self,
         framework: &mut Framework
     )
     {
-        self.game.console(framework);
-        self.input.console(framework);
+        if let Some(meshes) = &mut self.meshes
+        {
+            meshes.store_previous();
+        }
+        self.game.update_fixed(self.meshes.as_mut(), framework);
     }
 
-    fn save_load // TODO Needs macro to run all.
+    fn update_engine
     (
         &mut self,
-        framework: &mut Framework
+        device_event: &[DeviceEvent],
+        window_event: &[WindowEvent],
+        alpha: f32
     )
     {
-        match framework.save_load_event()
+        for event in device_event
         {
-            SaveLoad::Load => 
-            {
-                framework.save_load(SaveLoad::Idle);
-                println!("Load");
-            } 
-            SaveLoad::Save => 
+            if let Err(err) = self.input.register_device_events(event)
             {
-                framework.save_load(SaveLoad::Idle);
-                println!("Saved.")                
+                nokden::debug!("input", "{}", err);
             }
-            SaveLoad::Idle => ()
         }        
-    }   
+
+        if let (Some(graphics), Some(meshes)) = (&mut self.graphics, &mut self.meshes)
+        {
+            meshes.release_unused();
+            let world_vp = graphics.world_camera.projection.as_matrix() * graphics.world_camera.transform.to_homogeneous();
+            let frame_index = graphics.frame_start();
+            meshes.update(graphics, &world_vp, alpha);
+            graphics.frame_end(frame_index);
+        }
+    }
 }
\ No newline at end of file
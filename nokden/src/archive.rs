@@ -0,0 +1,575 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use crate::serialize::{Serialize, Deserialize, Serializer, Deserializer, SerializeError, Format};
+
+pub const ARCHIVE_EXTENSION: &str = "nka";
+
+/// Counted when the layout of the header, the index or the compression changes.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const ARCHIVE_MAGIC: &[u8] = b"NOKA";
+
+/// Magic, version and the length of the index.
+const HEADER_SIZE: u64 = 4 + 4 + 8;
+
+/// Matches shorter than this are written as literals, see [compress].
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = MIN_MATCH + 0x7F;
+const MAX_LITERALS: usize = 0x80;
+const MAX_OFFSET: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 14;
+
+/// Assets packed into one file, `name.nka`, built by the `pack` binary.
+/// The file is a header, an index in the binary [Serializer] format and then the blobs,
+/// each compressed on its own and checked against the [hash] of its content when read.
+pub struct Archive
+{
+    path: PathBuf,
+    entries: HashMap<String, Entry>
+}
+
+/// Where an asset is in the archive, offsets count from the end of the index.
+struct Entry
+{
+    asset: String,
+    offset: u64,
+    packed: u64,
+    size: u64,
+    compressed: bool,
+    hash: u64
+}
+
+#[derive(Debug)]
+pub enum ArchiveError
+{
+    Io(PathBuf, io::Error),
+    NotArchive(PathBuf),
+    Version
+    {
+        found: u32,
+        expected: u32
+    },
+    Index(SerializeError),
+    Missing(String),
+
+    /// The blob does not decompress to the size in the index, or the index points outside of the file.
+    Corrupt(String),
+    Hash
+    {
+        asset: String,
+        expected: u64,
+        found: u64
+    }
+}
+
+impl fmt::Display for ArchiveError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ArchiveError::Io(path, err) => write!(f, "{}: {}", path.to_string_lossy(), err),
+            ArchiveError::NotArchive(path) => write!(f, "{} is not a nokden archive.", path.to_string_lossy()),
+            ArchiveError::Version { found, expected } => write!(f, "Archive version {} is not supported, expected {}.", found, expected),
+            ArchiveError::Index(err) => write!(f, "Archive index not read: {}", err),
+            ArchiveError::Missing(asset) => write!(f, "Asset {} is not in the archive.", asset),
+            ArchiveError::Corrupt(asset) => write!(f, "Asset {} in the archive is corrupt.", asset),
+            ArchiveError::Hash { asset, expected, found } => write!(f, "Asset {} in the archive has hash {:016x}, expected {:016x}.", asset, found, expected)
+        }
+    }
+}
+
+impl Serialize for Entry
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("asset", &self.asset);
+        serializer.field("offset", &self.offset);
+        serializer.field("packed", &self.packed);
+        serializer.field("size", &self.size);
+        serializer.field("compressed", &self.compressed);
+        serializer.field("hash", &self.hash);
+        serializer.end();
+    }
+}
+
+impl Deserialize for Entry
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Entry, SerializeError>
+    {
+        deserializer.begin()?;
+        let entry = Entry
+        {
+            asset: deserializer.field("asset")?,
+            offset: deserializer.field("offset")?,
+            packed: deserializer.field("packed")?,
+            size: deserializer.field("size")?,
+            compressed: deserializer.field("compressed")?,
+            hash: deserializer.field("hash")?
+        };
+        deserializer.end()?;
+        Ok(entry)
+    }
+}
+
+/// What [Archive::pack] wrote.
+pub struct PackStats
+{
+    pub assets: usize,
+    pub size: u64,
+    pub packed: u64
+}
+
+impl Archive
+{
+    /// Reads the index only, blobs are read by [Archive::read].
+    pub fn open
+    (
+        path: &Path
+    )
+    -> Result<Archive, ArchiveError>
+    {
+        let io = |err| ArchiveError::Io(path.to_path_buf(), err);
+        let mut file = File::open(path).map_err(io)?;
+
+        let mut header = [0; HEADER_SIZE as usize];
+        file.read_exact(&mut header).map_err(|_| ArchiveError::NotArchive(path.to_path_buf()))?;
+        if &header[0..4] != ARCHIVE_MAGIC
+        {
+            return Err(ArchiveError::NotArchive(path.to_path_buf()));
+        }
+
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != ARCHIVE_VERSION
+        {
+            return Err(ArchiveError::Version { found: version, expected: ARCHIVE_VERSION });
+        }
+
+        let index_size = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let mut index = Vec::new();
+        file.take(index_size).read_to_end(&mut index).map_err(io)?;
+        let entries: Vec<Entry> = Deserializer::new(index).and_then(|mut deserializer| deserializer.field("entries")).map_err(ArchiveError::Index)?;
+
+        // Offsets in the index are relative to the end of it, sizes from a damaged file may not add up.
+        let data_start = HEADER_SIZE.checked_add(index_size).ok_or_else(|| ArchiveError::Corrupt("index".to_string()))?;
+        let entries = entries.into_iter()
+            .map(|entry| match data_start.checked_add(entry.offset)
+            {
+                Some(offset) => Ok((entry.asset.clone(), Entry { offset, ..entry })),
+                None => Err(ArchiveError::Corrupt(entry.asset))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok
+        (
+            Archive
+            {
+                path: path.to_path_buf(),
+                entries
+            }
+        )
+    }
+
+    pub fn path
+    (
+        &self
+    )
+    -> &Path
+    {
+        &self.path
+    }
+
+    pub fn len
+    (
+        &self
+    )
+    -> usize
+    {
+        self.entries.len()
+    }
+
+    pub fn is_empty
+    (
+        &self
+    )
+    -> bool
+    {
+        self.entries.is_empty()
+    }
+
+    pub fn contains
+    (
+        &self,
+        asset: &Path
+    )
+    -> bool
+    {
+        asset_name(asset).is_some_and(|name| self.entries.contains_key(&name))
+    }
+
+    /// Decompressed and checked against the hash from the index.
+    pub fn read
+    (
+        &self,
+        asset: &Path
+    )
+    -> Result<Vec<u8>, ArchiveError>
+    {
+        let name = asset_name(asset).unwrap_or_else(|| asset.to_string_lossy().to_string());
+        let entry = self.entries.get(&name).ok_or_else(|| ArchiveError::Missing(name.clone()))?;
+
+        let io = |err| ArchiveError::Io(self.path.clone(), err);
+        let mut file = File::open(&self.path).map_err(io)?;
+        file.seek(SeekFrom::Start(entry.offset)).map_err(io)?;
+        let mut packed = Vec::new();
+        file.take(entry.packed).read_to_end(&mut packed).map_err(io)?;
+
+        let bytes = match entry.compressed
+        {
+            true => decompress(&packed, entry.size as usize),
+            false => Some(packed)
+        };
+        let bytes = bytes.filter(|bytes| bytes.len() as u64 == entry.size).ok_or_else(|| ArchiveError::Corrupt(name.clone()))?;
+
+        let found = hash(&bytes);
+        if found != entry.hash
+        {
+            return Err(ArchiveError::Hash { asset: name, expected: entry.hash, found });
+        }
+        Ok(bytes)
+    }
+
+    /// Packs every file below a directory, named by their path relative to it.
+    pub fn pack
+    (
+        directory: &Path,
+        archive: &Path
+    )
+    -> Result<PackStats, ArchiveError>
+    {
+        let mut files = Vec::new();
+        collect_files(directory, &mut files).map_err(|err| ArchiveError::Io(directory.to_path_buf(), err))?;
+        files.sort();
+
+        let mut entries = Vec::new();
+        let mut blobs = Vec::new();
+        let mut stats = PackStats { assets: 0, size: 0, packed: 0 };
+        for file in files
+        {
+            let bytes = fs::read(&file).map_err(|err| ArchiveError::Io(file.clone(), err))?;
+            let asset = match file.strip_prefix(directory).ok().and_then(asset_name)
+            {
+                Some(asset) => asset,
+                None => continue
+            };
+
+            let compressed = compress(&bytes);
+            let (blob, compressed) = match compressed.len() < bytes.len()
+            {
+                true => (compressed, true),
+                false => (bytes.clone(), false)
+            };
+
+            entries.push
+            (
+                Entry
+                {
+                    asset,
+                    offset: blobs.len() as u64,
+                    packed: blob.len() as u64,
+                    size: bytes.len() as u64,
+                    compressed,
+                    hash: hash(&bytes)
+                }
+            );
+            stats.assets += 1;
+            stats.size += bytes.len() as u64;
+            stats.packed += blob.len() as u64;
+            blobs.extend_from_slice(&blob);
+        }
+
+        let mut serializer = Serializer::new(Format::Binary);
+        serializer.field("entries", &entries);
+        let index = serializer.finish();
+
+        let io = |err| ArchiveError::Io(archive.to_path_buf(), err);
+        let mut file = File::create(archive).map_err(io)?;
+        file.write_all(ARCHIVE_MAGIC).map_err(io)?;
+        file.write_all(&ARCHIVE_VERSION.to_le_bytes()).map_err(io)?;
+        file.write_all(&(index.len() as u64).to_le_bytes()).map_err(io)?;
+        file.write_all(&index).map_err(io)?;
+        file.write_all(&blobs).map_err(io)?;
+        Ok(stats)
+    }
+}
+
+/// Assets are named by their relative path with `/` between folders on every platform.
+/// [None] for paths leaving the archive, absolute ones or with `..`.
+pub fn asset_name
+(
+    asset: &Path
+)
+-> Option<String>
+{
+    let mut parts = Vec::new();
+    for component in asset.components()
+    {
+        match component
+        {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => (),
+            _ => return None
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn collect_files
+(
+    directory: &Path,
+    files: &mut Vec<PathBuf>
+)
+-> io::Result<()>
+{
+    for entry in fs::read_dir(directory)?
+    {
+        let path = entry?.path();
+        match path.is_dir()
+        {
+            true => collect_files(&path, files)?,
+            false => files.push(path)
+        }
+    }
+    Ok(())
+}
+
+/// FNV-1a, catches damaged or changed blobs, not made to stand against tampering.
+pub fn hash
+(
+    bytes: &[u8]
+)
+-> u64
+{
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET, |hash, byte| (hash ^ *byte as u64).wrapping_mul(PRIME))
+}
+
+/// Byte oriented LZ77, each token byte starts either
+/// a run of up to 128 literals (high bit clear, count minus one),
+/// or a match of [MIN_MATCH] to [MAX_MATCH] bytes (high bit set, length minus [MIN_MATCH]) followed by a little endian u16 distance back.
+fn compress
+(
+    bytes: &[u8]
+)
+-> Vec<u8>
+{
+    let mut packed = Vec::with_capacity(bytes.len() / 2);
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    let mut literals = 0;
+    let mut position = 0;
+
+    let flush = |packed: &mut Vec<u8>, literals: usize, end: usize|
+    {
+        for chunk in bytes[end - literals..end].chunks(MAX_LITERALS)
+        {
+            packed.push((chunk.len() - 1) as u8);
+            packed.extend_from_slice(chunk);
+        }
+    };
+
+    while position + MIN_MATCH <= bytes.len()
+    {
+        let key = u32::from_le_bytes(bytes[position..position + MIN_MATCH].try_into().unwrap());
+        let slot = (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+        let candidate = table[slot];
+        table[slot] = position;
+
+        let length = match candidate
+        {
+            usize::MAX => 0,
+            candidate if position - candidate > MAX_OFFSET => 0,
+            candidate => bytes[candidate..].iter().zip(&bytes[position..]).take(MAX_MATCH).take_while(|(a, b)| a == b).count()
+        };
+
+        if length >= MIN_MATCH
+        {
+            flush(&mut packed, literals, position);
+            literals = 0;
+            packed.push(0x80 | (length - MIN_MATCH) as u8);
+            packed.extend_from_slice(&((position - candidate) as u16).to_le_bytes());
+            position += length;
+        }
+        else
+        {
+            literals += 1;
+            position += 1;
+        }
+    }
+
+    flush(&mut packed, literals + bytes.len() - position, bytes.len());
+    packed
+}
+
+/// [None] if the data does not fit the format or makes more than the size.
+fn decompress
+(
+    packed: &[u8],
+    size: usize
+)
+-> Option<Vec<u8>>
+{
+    // No token makes more than [MAX_MATCH] bytes, a larger size is a corrupt index and not reserved.
+    let mut bytes = Vec::with_capacity(size.min(packed.len().saturating_mul(MAX_MATCH)));
+    let mut position = 0;
+    while position < packed.len()
+    {
+        let token = packed[position] as usize;
+        position += 1;
+
+        if token & 0x80 == 0
+        {
+            let literals = packed.get(position..position + token + 1)?;
+            bytes.extend_from_slice(literals);
+            position += literals.len();
+        }
+        else
+        {
+            let distance = u16::from_le_bytes(packed.get(position..position + 2)?.try_into().unwrap()) as usize;
+            position += 2;
+            if distance == 0 || distance > bytes.len()
+            {
+                return None;
+            }
+
+            // Byte by byte, the match may overlap what it writes.
+            let start = bytes.len() - distance;
+            for index in 0..(token & 0x7F) + MIN_MATCH
+            {
+                bytes.push(bytes[start + index]);
+            }
+        }
+
+        if bytes.len() > size
+        {
+            return None;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// Noise does not compress and is stored as is.
+    fn noise
+    (
+        length: usize
+    )
+    -> Vec<u8>
+    {
+        let mut state: u32 = 12345;
+        (0..length).map(|_| { state = state.wrapping_mul(1103515245).wrapping_add(12345); (state >> 16) as u8 }).collect()
+    }
+
+    fn packed
+    (
+        name: &str
+    )
+    -> (PathBuf, PathBuf)
+    {
+        let directory = crate::test_directory(name);
+        let import = directory.join("import");
+        fs::create_dir_all(import.join("meshes")).unwrap();
+        fs::write(import.join("meshes").join("tile.obj"), "v 0 0 0\n".repeat(100)).unwrap();
+        fs::write(import.join("noise.bin"), noise(1000)).unwrap();
+
+        let archive = directory.join("import.nka");
+        let stats = Archive::pack(&import, &archive).unwrap();
+        assert_eq!(stats.assets, 2);
+        assert!(stats.packed < stats.size);
+        (import, archive)
+    }
+
+    #[test]
+    fn pack_and_read
+    ()
+    {
+        let (import, archive) = packed("archive-read");
+        let archive = Archive::open(&archive).unwrap();
+        assert_eq!(archive.len(), 2);
+        assert!(archive.contains(Path::new("meshes/tile.obj")));
+        assert!(!archive.contains(Path::new("../meshes/tile.obj")));
+        assert_eq!(archive.read(Path::new("meshes/tile.obj")).unwrap(), fs::read(import.join("meshes").join("tile.obj")).unwrap());
+        assert_eq!(archive.read(Path::new("noise.bin")).unwrap(), noise(1000));
+        assert!(matches!(archive.read(Path::new("missing")), Err(ArchiveError::Missing(_))));
+    }
+
+    #[test]
+    fn hash_mismatch
+    ()
+    {
+        // The stored noise is the last blob, its last byte is the last of the file.
+        let (_, path) = packed("archive-hash");
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert!(matches!(archive.read(Path::new("noise.bin")), Err(ArchiveError::Hash { .. })));
+        assert!(archive.read(Path::new("meshes/tile.obj")).is_ok());
+    }
+
+    #[test]
+    fn corrupt
+    ()
+    {
+        let (_, path) = packed("archive-corrupt");
+        let bytes = fs::read(&path).unwrap();
+        let index_size = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+
+        // An index size that overflows past the end of the index, with the index still readable.
+        let mut overflowing = bytes[..8].to_vec();
+        overflowing.extend_from_slice(&u64::MAX.to_le_bytes());
+        overflowing.extend_from_slice(&bytes[16..16 + index_size]);
+        fs::write(&path, overflowing).unwrap();
+        assert!(matches!(Archive::open(&path), Err(ArchiveError::Corrupt(_))));
+
+        fs::write(&path, &bytes[..10]).unwrap();
+        assert!(matches!(Archive::open(&path), Err(ArchiveError::NotArchive(_))));
+    }
+
+    #[test]
+    fn compression
+    ()
+    {
+        let bytes = b"abcabcabcabcabcabcabcabc and some more abcabcabc".repeat(20);
+        let packed = compress(&bytes);
+        assert!(packed.len() < bytes.len());
+        assert_eq!(decompress(&packed, bytes.len()).unwrap(), bytes);
+        assert!(decompress(&packed, bytes.len() - 1).is_none());
+
+        // A size from a corrupt index is not reserved up front.
+        assert_eq!(decompress(&[0, 7], usize::MAX).unwrap(), vec![7]);
+        assert!(decompress(&[0x80, 1, 0], 100).is_none());
+    }
+}
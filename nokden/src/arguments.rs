@@ -0,0 +1,225 @@
+use std::fmt;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Options:
+    --asset-path <directory>    Mounts another asset directory or archive over the installed and source ones.
+    --mod <directory>           Mounts a mod directory or archive over everything before, can be repeated.
+    --user-path <directory>     Keeps the settings and autoexec of the user here instead.
+    --width <pixels>            Window width, not written to the settings.
+    --height <pixels>           Window height, not written to the settings.
+    --exec <command>            Issues a console command after start, can be repeated.
+    --headless                  Runs without a window or graphics, updating on a timer.
+    --frames <count>            Quits after this many frames.
+    --help                      Prints this.
+Everything else is left to the application.";
+
+/// Options of the command line that the framework handles itself.
+/// Values can be given as `--width 800` or `--width=800`.
+#[derive(Default, Debug)]
+pub struct Arguments
+{
+    pub asset_path: Option<PathBuf>,
+    pub mods: Vec<PathBuf>,
+    pub user_path: Option<PathBuf>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub exec: Vec<String>,
+    pub headless: bool,
+    pub frames: Option<u32>,
+    pub help: bool,
+
+    /// Arguments not known to the framework, in order.
+    pub leftovers: Vec<String>
+}
+
+impl Arguments
+{
+    /// Arguments of the process without the executable.
+    pub fn from_env
+    ()
+    -> Result<Arguments, ArgumentsError>
+    {
+        Self::parse(std::env::args_os().skip(1))
+    }
+
+    /// Everything needs to be UTF-8, except paths given apart from their flag.
+    pub fn parse
+    (
+        arguments: impl IntoIterator<Item = impl Into<OsString>>
+    )
+    -> Result<Arguments, ArgumentsError>
+    {
+        let mut parsed = Arguments::default();
+        let mut arguments = arguments.into_iter().map(Into::into);
+
+        while let Some(argument) = arguments.next()
+        {
+            let argument = Self::text(argument)?;
+            let (flag, inline_value) = match argument.split_once('=')
+            {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (argument.clone(), None)
+            };
+
+            let mut value = ||
+            {
+                inline_value.clone().map(OsString::from).or_else(|| arguments.next()).ok_or_else(|| ArgumentsError::MissingValue(flag.clone()))
+            };
+
+            match flag.as_str()
+            {
+                "--asset-path" => parsed.asset_path = Some(PathBuf::from(value()?)),
+                "--mod" => parsed.mods.push(PathBuf::from(value()?)),
+                "--user-path" => parsed.user_path = Some(PathBuf::from(value()?)),
+                "--width" => parsed.width = Some(Self::number(&flag, value()?)?),
+                "--height" => parsed.height = Some(Self::number(&flag, value()?)?),
+                "--exec" => parsed.exec.push(Self::text(value()?)?),
+                "--frames" => parsed.frames = Some(Self::number(&flag, value()?)?),
+                "--headless" => parsed.headless = Self::switch(&flag, &inline_value)?,
+                "--help" => parsed.help = Self::switch(&flag, &inline_value)?,
+                _ => parsed.leftovers.push(argument)
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn text
+    (
+        argument: OsString
+    )
+    -> Result<String, ArgumentsError>
+    {
+        argument.into_string().map_err(|argument| ArgumentsError::NotUnicode(argument.to_string_lossy().into_owned()))
+    }
+
+    /// Flags without a value are set by being there, `--headless=no` is a mistake.
+    fn switch
+    (
+        flag: &str,
+        inline_value: &Option<String>
+    )
+    -> Result<bool, ArgumentsError>
+    {
+        match inline_value
+        {
+            Some(value) => Err(ArgumentsError::UnexpectedValue { flag: flag.to_string(), value: value.clone() }),
+            None => Ok(true)
+        }
+    }
+
+    fn number
+    (
+        flag: &str,
+        value: OsString
+    )
+    -> Result<u32, ArgumentsError>
+    {
+        let value = Self::text(value)?;
+        value.parse().map_err(|_| ArgumentsError::InvalidValue { flag: flag.to_string(), value })
+    }
+}
+
+#[derive(Debug)]
+pub enum ArgumentsError
+{
+    MissingValue(String),
+    InvalidValue
+    {
+        flag: String,
+        value: String
+    },
+    UnexpectedValue
+    {
+        flag: String,
+        value: String
+    },
+    /// The argument with the invalid parts replaced.
+    NotUnicode(String)
+}
+
+impl fmt::Display for ArgumentsError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ArgumentsError::MissingValue(flag) => write!(f, "{} needs a value.", flag),
+            ArgumentsError::InvalidValue { flag, value } => write!(f, "{} needs a number but got '{}'.", flag, value),
+            ArgumentsError::UnexpectedValue { flag, value } => write!(f, "{} takes no value but got '{}'.", flag, value),
+            ArgumentsError::NotUnicode(argument) => write!(f, "'{}' is not valid UTF-8.", argument)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn parse
+    (
+        arguments: &[&str]
+    )
+    -> Result<Arguments, ArgumentsError>
+    {
+        Arguments::parse(arguments.iter())
+    }
+
+    #[test]
+    fn values
+    ()
+    {
+        let arguments = parse(&["--width", "800", "--height=600", "--mod", "a", "--mod=b", "--user-path", "user", "--exec", "set x 1", "--headless", "--frames", "3"]).unwrap();
+        assert_eq!(arguments.width, Some(800));
+        assert_eq!(arguments.height, Some(600));
+        assert_eq!(arguments.mods, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(arguments.user_path, Some(PathBuf::from("user")));
+        assert_eq!(arguments.exec, vec!["set x 1".to_string()]);
+        assert!(arguments.headless);
+        assert_eq!(arguments.frames, Some(3));
+        assert!(!arguments.help);
+    }
+
+    #[test]
+    fn leftovers
+    ()
+    {
+        let arguments = parse(&["game", "--unknown", "-x=1", "--help"]).unwrap();
+        assert!(arguments.help);
+        assert_eq!(arguments.leftovers, vec!["game".to_string(), "--unknown".to_string(), "-x=1".to_string()]);
+    }
+
+    #[test]
+    fn errors
+    ()
+    {
+        assert!(matches!(parse(&["--width"]), Err(ArgumentsError::MissingValue(_))));
+        assert!(matches!(parse(&["--frames", "many"]), Err(ArgumentsError::InvalidValue { .. })));
+        assert!(matches!(parse(&["--height=-1"]), Err(ArgumentsError::InvalidValue { .. })));
+        assert!(matches!(parse(&["--headless=no"]), Err(ArgumentsError::UnexpectedValue { .. })));
+        assert!(matches!(parse(&["--help=x"]), Err(ArgumentsError::UnexpectedValue { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn not_unicode
+    ()
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        let path = OsString::from_vec(b"assets\xff".to_vec());
+        let arguments = Arguments::parse([OsString::from("--asset-path"), path.clone()]).unwrap();
+        assert_eq!(arguments.asset_path, Some(PathBuf::from(path.clone())));
+
+        assert!(matches!(Arguments::parse([OsString::from("--exec"), path.clone()]), Err(ArgumentsError::NotUnicode(_))));
+        assert!(matches!(Arguments::parse([path]), Err(ArgumentsError::NotUnicode(_))));
+    }
+}
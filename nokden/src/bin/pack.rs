@@ -0,0 +1,52 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use nokden::archive::{Archive, ARCHIVE_EXTENSION};
+use nokden::vfs::{IMPORT_FOLDER, PROJECT_FOLDER};
+
+const USAGE: &str = "\
+Usage: pack [import folder] [archive]
+Packs every file of the import folder into one archive, checked against content hashes when loaded.
+The folder defaults to @main/import and the archive to the folder name with .nka next to it, @main/import.nka.";
+
+/// Builds a nokden archive, see [Archive::pack].
+fn main
+()
+{
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if arguments.iter().any(|argument| argument == "--help") || arguments.len() > 2
+    {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let directory = arguments.first().map(PathBuf::from).unwrap_or_else(|| PathBuf::from(PROJECT_FOLDER).join(IMPORT_FOLDER));
+    let archive = arguments.get(1).map(PathBuf::from).unwrap_or_else(|| directory.with_extension(ARCHIVE_EXTENSION));
+
+    if !directory.is_dir()
+    {
+        eprintln!("{} is not a folder.", directory.to_string_lossy());
+        println!("{}", USAGE);
+        process::exit(1);
+    }
+
+    match Archive::pack(&directory, &archive)
+    {
+        Ok(stats) =>
+        {
+            println!
+            (
+                "Packed {} assets, {} bytes into {} bytes: {}",
+                stats.assets,
+                stats.size,
+                stats.packed,
+                archive.to_string_lossy()
+            );
+        }
+        Err(err) =>
+        {
+            eprintln!("Archive not packed: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,1466 @@
+use std::fmt;
+use std::collections::{BTreeMap, VecDeque};
+use nalgebra::Vector3;
+use crate::Framework;
+use crate::cvars::Cvars;
+use crate::logger;
+
+/// Older lines are dropped.
+const SCROLLBACK_LINES: usize = 512;
+const HISTORY_LINES: usize = 64;
+
+/// A parsed parameter of an issued command.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConsoleCommandParameter
+{
+    Bool(bool),
+    Integer(i64),
+    Float(f32),
+
+    /// Quoted text or anything that is not one of the other kinds.
+    String(String),
+
+    /// Written as `(1, 2, 3)`.
+    Vector(Vec<f32>)
+}
+
+impl ConsoleCommandParameter
+{
+    pub fn kind
+    (
+        &self
+    )
+    -> ConsoleParameterKind
+    {
+        match self
+        {
+            ConsoleCommandParameter::Bool(_) => ConsoleParameterKind::Bool,
+            ConsoleCommandParameter::Integer(_) => ConsoleParameterKind::Integer,
+            ConsoleCommandParameter::Float(_) => ConsoleParameterKind::Float,
+            ConsoleCommandParameter::String(_) => ConsoleParameterKind::String,
+            ConsoleCommandParameter::Vector(_) => ConsoleParameterKind::Vector
+        }
+    }
+
+    /// Parses a single value written like a command parameter.
+    pub fn from_text
+    (
+        text: &str
+    )
+    -> Result<ConsoleCommandParameter, ConsoleError>
+    {
+        let tokens = ConsoleCommand::tokenize(text)?;
+        match tokens.as_slice()
+        {
+            [token] => ConsoleCommandParameter::parse(token),
+            _ => Err(ConsoleError::NotOneValue(text.to_string()))
+        }
+    }
+
+    fn parse
+    (
+        token: &Token
+    )
+    -> Result<ConsoleCommandParameter, ConsoleError>
+    {
+        if token.quoted
+        {
+            return Ok(ConsoleCommandParameter::String(token.text.clone()));
+        }
+
+        if let Some(inner) = token.text.strip_prefix('(')
+        {
+            let inner = inner.strip_suffix(')').ok_or_else(|| ConsoleError::UnterminatedVector(token.text.clone()))?;
+            let mut vector = Vec::new();
+            for component in inner.split(|character: char| character == ',' || character.is_whitespace()).filter(|component| !component.is_empty())
+            {
+                match component.parse::<f32>()
+                {
+                    Ok(value) => vector.push(value),
+                    Err(_) => return Err(ConsoleError::VectorComponent { vector: token.text.clone(), component: component.to_string() })
+                }
+            }
+            return Ok(ConsoleCommandParameter::Vector(vector));
+        }
+
+        if let Ok(value) = token.text.parse::<bool>()
+        {
+            Ok(ConsoleCommandParameter::Bool(value))
+        }
+        else if let Ok(value) = token.text.parse::<i64>()
+        {
+            Ok(ConsoleCommandParameter::Integer(value))
+        }
+        else if let Ok(value) = token.text.parse::<f32>()
+        {
+            Ok(ConsoleCommandParameter::Float(value))
+        }
+        else
+        {
+            Ok(ConsoleCommandParameter::String(token.text.clone()))
+        }
+    }
+}
+
+/// Written so that parsing gives the same parameter back.
+impl fmt::Display for ConsoleCommandParameter
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ConsoleCommandParameter::Bool(value) => write!(f, "{}", value),
+            ConsoleCommandParameter::Integer(value) => write!(f, "{}", value),
+            ConsoleCommandParameter::Float(value) => write!(f, "{:?}", value),
+            ConsoleCommandParameter::String(value) => write!(f, "\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+            ConsoleCommandParameter::Vector(vector) =>
+            {
+                let components: Vec<String> = vector.iter().map(|component| format!("{:?}", component)).collect();
+                write!(f, "({})", components.join(", "))
+            }
+        }
+    }
+}
+
+macro_rules! console_parameter_from
+{
+    (
+        $($from:ty => $variant:ident($convert:expr)),*
+    )
+    =>
+    {
+        $(
+            impl From<$from> for ConsoleCommandParameter
+            {
+                fn from
+                (
+                    value: $from
+                )
+                -> ConsoleCommandParameter
+                {
+                    ConsoleCommandParameter::$variant($convert(value))
+                }
+            }
+        )*
+    };
+}
+
+console_parameter_from!
+(
+    bool => Bool(|value| value),
+    i32 => Integer(i64::from),
+    u32 => Integer(i64::from),
+    i64 => Integer(|value| value),
+    f32 => Float(|value| value),
+    f64 => Float(|value| value as f32),
+    &str => String(String::from),
+    String => String(|value| value),
+    Vec<f32> => Vector(|value| value)
+);
+
+/// Declared kind of a parameter, used to validate issued commands.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ConsoleParameterKind
+{
+    Bool,
+    Integer,
+
+    /// Also accepts integers.
+    Float,
+
+    /// Also accepts every other kind as written.
+    String,
+    Vector
+}
+
+impl ConsoleParameterKind
+{
+    pub fn accepts
+    (
+        &self,
+        parameter: &ConsoleCommandParameter
+    )
+    -> bool
+    {
+        match (self, parameter)
+        {
+            (ConsoleParameterKind::String, _) => true,
+            (ConsoleParameterKind::Float, ConsoleCommandParameter::Integer(_)) => true,
+            (kind, parameter) => *kind == parameter.kind()
+        }
+    }
+}
+
+impl fmt::Display for ConsoleParameterKind
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ConsoleParameterKind::Bool => write!(f, "bool"),
+            ConsoleParameterKind::Integer => write!(f, "integer"),
+            ConsoleParameterKind::Float => write!(f, "float"),
+            ConsoleParameterKind::String => write!(f, "string"),
+            ConsoleParameterKind::Vector => write!(f, "vector")
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConsoleError
+{
+    Unknown(String),
+    UnterminatedQuote(String),
+    UnterminatedVector(String),
+    VectorComponent
+    {
+        vector: String,
+        component: String
+    },
+    ParameterCount
+    {
+        keyword: String,
+        minimum: usize,
+        maximum: usize,
+        found: usize
+    },
+    ParameterKind
+    {
+        keyword: String,
+        index: usize,
+        expected: ConsoleParameterKind,
+        found: String
+    },
+    NotOneValue(String),
+    AliasIsCommand(String),
+    UnknownCvar(String),
+    CvarKind
+    {
+        name: String,
+        expected: ConsoleParameterKind,
+        found: String
+    },
+    CvarRange
+    {
+        name: String,
+        minimum: f32,
+        maximum: f32,
+        found: String
+    }
+}
+
+impl fmt::Display for ConsoleError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ConsoleError::UnterminatedQuote(line) => write!(f, "Missing closing quote in: {}", line),
+            ConsoleError::UnterminatedVector(vector) => write!(f, "Missing closing bracket in: {}", vector),
+            ConsoleError::VectorComponent { vector, component } => write!(f, "'{}' in {} is not a number.", component, vector),
+            ConsoleError::Unknown(keyword) => write!(f, "Unknown command '{}', 'help' lists all commands.", keyword),
+            ConsoleError::ParameterCount { keyword, minimum, maximum, found } if minimum == maximum => write!(f, "'{}' takes {} parameters but got {}.", keyword, minimum, found),
+            ConsoleError::ParameterCount { keyword, minimum, maximum, found } => write!(f, "'{}' takes {} to {} parameters but got {}.", keyword, minimum, maximum, found),
+            ConsoleError::ParameterKind { keyword, index, expected, found } => write!(f, "Parameter {} of '{}' needs to be a {} but is '{}'.", index + 1, keyword, expected, found),
+            ConsoleError::NotOneValue(text) => write!(f, "Expected a single value but got: {}", text),
+            ConsoleError::AliasIsCommand(name) => write!(f, "'{}' is a command and can not be an alias.", name),
+            ConsoleError::UnknownCvar(name) => write!(f, "Unknown cvar '{}', 'cvars' lists all cvars.", name),
+            ConsoleError::CvarKind { name, expected, found } => write!(f, "'{}' needs to be a {} but is {}.", name, expected, found),
+            ConsoleError::CvarRange { name, minimum, maximum, found } => write!(f, "'{}' needs to be between {} and {} but is {}.", name, minimum, maximum, found)
+        }
+    }
+}
+
+struct Token
+{
+    text: String,
+    quoted: bool
+}
+
+pub struct ConsoleCommand
+{
+    pub(crate) keyword: String,
+
+    /// Parsed parameters of an issued command.
+    parameters: Vec<ConsoleCommandParameter>,
+
+    /// Parameters as typed, used when a string is asked for.
+    texts: Vec<String>,
+
+    /// Declared parameters of a registered command.
+    signature: Vec<ConsoleParameterKind>,
+
+    /// Parameters of the signature after these can be left out.
+    required: usize,
+
+    description: String,
+
+    completion: ConsoleCompletion
+}
+
+/// What tab completes the first parameter of a command with.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ConsoleCompletion
+{
+    Nothing,
+    Commands,
+    Cvars
+}
+
+impl ConsoleCommand
+{
+    /// Declares a command with its parameters, to register with [ConsoleCommands].
+    pub fn new
+    (
+        keyword: &str,
+        signature: Vec<ConsoleParameterKind>
+    )
+    -> ConsoleCommand
+    {
+        ConsoleCommand
+        {
+            keyword: String::from(keyword),
+            parameters: Vec::new(),
+            texts: Vec::new(),
+            required: signature.len(),
+            signature,
+            description: String::new(),
+            completion: ConsoleCompletion::Nothing
+        }
+    }
+
+    /// Adds parameters that can be left out after the required ones.
+    pub fn optional
+    (
+        mut self,
+        signature: Vec<ConsoleParameterKind>
+    )
+    -> ConsoleCommand
+    {
+        self.signature.extend(signature);
+        self
+    }
+
+    pub fn completion
+    (
+        mut self,
+        completion: ConsoleCompletion
+    )
+    -> ConsoleCommand
+    {
+        self.completion = completion;
+        self
+    }
+
+    /// Text shown by help.
+    pub fn describe
+    (
+        mut self,
+        description: &str
+    )
+    -> ConsoleCommand
+    {
+        self.description = String::from(description);
+        self
+    }
+
+    fn cleared
+    ()
+    -> ConsoleCommand
+    {
+        ConsoleCommand::new("", Vec::new())
+    }
+
+    /// Splits a typed line into keyword and parameters.
+    /// Parameters are separated by spaces, "quoted text" and (vectors) can contain spaces.
+    pub fn parse
+    (
+        line: &str
+    )
+    -> Result<ConsoleCommand, ConsoleError>
+    {
+        let mut tokens = Self::tokenize(line)?.into_iter();
+        let keyword = match tokens.next()
+        {
+            Some(keyword) => keyword.text,
+            None => return Ok(ConsoleCommand::cleared())
+        };
+
+        let mut command = ConsoleCommand::new(&keyword, Vec::new());
+        for token in tokens
+        {
+            command.parameters.push(ConsoleCommandParameter::parse(&token)?);
+            command.texts.push(token.text);
+        }
+        Ok(command)
+    }
+
+    pub fn keyword
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.keyword
+    }
+
+    pub fn parameters
+    (
+        &self
+    )
+    -> &[ConsoleCommandParameter]
+    {
+        &self.parameters
+    }
+
+    pub fn signature
+    (
+        &self
+    )
+    -> &[ConsoleParameterKind]
+    {
+        &self.signature
+    }
+
+    pub fn description
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.description
+    }
+
+    /// Keyword with its parameters, optional ones in brackets.
+    /// For instance `save [string]`.
+    pub fn usage
+    (
+        &self
+    )
+    -> String
+    {
+        let mut usage = self.keyword.clone();
+        for (index, kind) in self.signature.iter().enumerate()
+        {
+            match index < self.required
+            {
+                true => usage.push_str(&format!(" <{}>", kind)),
+                false => usage.push_str(&format!(" [{}]", kind))
+            }
+        }
+        usage
+    }
+
+    /// Checks the parameters of this issued command against the signature of the [declared] one.
+    pub fn validate
+    (
+        &self,
+        declared: &ConsoleCommand
+    )
+    -> Result<(), ConsoleError>
+    {
+        if self.parameters.len() < declared.required || self.parameters.len() > declared.signature.len()
+        {
+            return Err
+            (
+                ConsoleError::ParameterCount
+                {
+                    keyword: self.keyword.clone(),
+                    minimum: declared.required,
+                    maximum: declared.signature.len(),
+                    found: self.parameters.len()
+                }
+            );
+        }
+
+        for (index, (kind, parameter)) in declared.signature.iter().zip(self.parameters.iter()).enumerate()
+        {
+            if !kind.accepts(parameter)
+            {
+                return Err
+                (
+                    ConsoleError::ParameterKind
+                    {
+                        keyword: self.keyword.clone(),
+                        index,
+                        expected: *kind,
+                        found: self.texts[index].clone()
+                    }
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a parameter.
+    /// Returns none if there is no paramater or of the wrong type.
+    pub fn get
+    <
+        T: FromConsoleParameter
+    >
+    (
+        &self,
+        index: usize
+    )
+    -> Option<T>
+    {
+        T::from_parameter(self.parameters.get(index)?, self.texts.get(index)?)
+    }
+
+    fn tokenize
+    (
+        line: &str
+    )
+    -> Result<Vec<Token>, ConsoleError>
+    {
+        let mut tokens = Vec::new();
+        let mut characters = line.chars().peekable();
+
+        while let Some(&character) = characters.peek()
+        {
+            if character.is_whitespace()
+            {
+                characters.next();
+            }
+            else if character == '"'
+            {
+                characters.next();
+                let mut text = String::new();
+                loop
+                {
+                    match characters.next()
+                    {
+                        Some('"') => break,
+                        Some('\\') => match characters.next()
+                        {
+                            Some(escaped) => text.push(escaped),
+                            None => return Err(ConsoleError::UnterminatedQuote(line.to_string()))
+                        },
+                        Some(character) => text.push(character),
+                        None => return Err(ConsoleError::UnterminatedQuote(line.to_string()))
+                    }
+                }
+                tokens.push(Token { text, quoted: true });
+            }
+            else
+            {
+                let mut text = String::new();
+                let mut in_vector = false;
+                while let Some(&character) = characters.peek()
+                {
+                    if character.is_whitespace() && !in_vector
+                    {
+                        break;
+                    }
+
+                    match character
+                    {
+                        '(' => in_vector = true,
+                        ')' => in_vector = false,
+                        _ => ()
+                    }
+                    text.push(character);
+                    characters.next();
+                }
+
+                if in_vector
+                {
+                    return Err(ConsoleError::UnterminatedVector(text));
+                }
+                tokens.push(Token { text, quoted: false });
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+/// Who handles a registered command.
+#[derive(Copy, Clone)]
+pub enum ConsoleHandler
+{
+    /// Run by the framework as soon as the command is issued.
+    Framework(fn(&mut Framework, &ConsoleCommand)),
+
+    /// Passed to [crate::SystemEvents::console] of the system with this name before the next update.
+    System(&'static str)
+}
+
+struct RegisteredCommand
+{
+    declared: ConsoleCommand,
+    handler: ConsoleHandler
+}
+
+/// Every command the console knows, by keyword.
+pub struct ConsoleCommands
+{
+    registered: BTreeMap<String, RegisteredCommand>,
+
+    /// Names standing for one or more commands separated by `;`.
+    aliases: BTreeMap<String, String>
+}
+
+impl Default for ConsoleCommands
+{
+    fn default
+    ()
+    -> ConsoleCommands
+    {
+        ConsoleCommands::new()
+    }
+}
+
+impl ConsoleCommands
+{
+    pub fn new
+    ()
+    -> ConsoleCommands
+    {
+        ConsoleCommands
+        {
+            registered: BTreeMap::new(),
+            aliases: BTreeMap::new()
+        }
+    }
+
+    /// Replaces an alias of the same name, registered commands can not be hidden.
+    pub fn alias
+    (
+        &mut self,
+        name: &str,
+        commands: &str
+    )
+    -> Result<(), ConsoleError>
+    {
+        if self.registered.contains_key(name)
+        {
+            return Err(ConsoleError::AliasIsCommand(name.to_string()));
+        }
+
+        self.aliases.insert(name.to_string(), commands.to_string());
+        Ok(())
+    }
+
+    pub fn unalias
+    (
+        &mut self,
+        name: &str
+    )
+    -> bool
+    {
+        self.aliases.remove(name).is_some()
+    }
+
+    pub fn find_alias
+    (
+        &self,
+        name: &str
+    )
+    -> Option<&str>
+    {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Aliases with their commands in alphabetical order.
+    pub fn aliases
+    (
+        &self
+    )
+    -> impl Iterator<Item = (&str, &str)>
+    {
+        self.aliases.iter().map(|(name, commands)| (name.as_str(), commands.as_str()))
+    }
+
+    /// The first registration of a keyword is kept.
+    pub fn register
+    (
+        &mut self,
+        declared: ConsoleCommand,
+        handler: ConsoleHandler
+    )
+    {
+        if self.registered.contains_key(&declared.keyword)
+        {
+            warn!("console", "Console command '{}' is already registered.", declared.keyword);
+            return;
+        }
+
+        self.registered.insert(declared.keyword.clone(), RegisteredCommand { declared, handler });
+    }
+
+    pub fn find
+    (
+        &self,
+        keyword: &str
+    )
+    -> Option<&ConsoleCommand>
+    {
+        self.registered.get(keyword).map(|registered| &registered.declared)
+    }
+
+    /// Registered commands in alphabetical order.
+    pub fn iter
+    (
+        &self
+    )
+    -> impl Iterator<Item = &ConsoleCommand>
+    {
+        self.registered.values().map(|registered| &registered.declared)
+    }
+
+    /// Finds the handler of an issued command and validates its parameters.
+    pub fn resolve
+    (
+        &self,
+        issued: &ConsoleCommand
+    )
+    -> Result<ConsoleHandler, ConsoleError>
+    {
+        match self.registered.get(&issued.keyword)
+        {
+            Some(registered) =>
+            {
+                issued.validate(&registered.declared)?;
+                Ok(registered.handler)
+            }
+            None => Err(ConsoleError::Unknown(issued.keyword.clone()))
+        }
+    }
+
+    /// Lists every command, or the usage of one.
+    pub fn print_help
+    (
+        &self,
+        console: &mut Console,
+        keyword: Option<&str>
+    )
+    {
+        match keyword
+        {
+            Some(keyword) => match self.find(keyword)
+            {
+                Some(declared) =>
+                {
+                    console.print(declared.usage());
+                    console.print(format!("\t{}", declared.description));
+                }
+                None => console.print(ConsoleError::Unknown(keyword.to_string()).to_string())
+            },
+            None =>
+            {
+                console.print("Console commands:");
+                for declared in self.iter()
+                {
+                    console.print(format!("\t{:<24}{}", declared.usage(), declared.description));
+                }
+            }
+        }
+    }
+}
+
+/// State of the console as data, for any widget to show and edit.
+/// Keeps the output of commands, the line being typed and the lines submitted before.
+pub struct Console
+{
+    scrollback: VecDeque<String>,
+    typing: String,
+    history: VecDeque<String>,
+
+    /// Position while going through the history, none while typing a new line.
+    history_index: Option<usize>,
+
+    /// Candidates of the last tab completion.
+    completions: Vec<String>,
+
+    /// Lines printed since the start, including the ones dropped from the scrollback.
+    printed: u64
+}
+
+impl Default for Console
+{
+    fn default
+    ()
+    -> Console
+    {
+        Console::new()
+    }
+}
+
+impl Console
+{
+    pub fn new
+    ()
+    -> Console
+    {
+        Console
+        {
+            scrollback: VecDeque::new(),
+            typing: String::new(),
+            history: VecDeque::new(),
+            history_index: None,
+            completions: Vec::new(),
+            printed: 0
+        }
+    }
+
+    /// Adds a line to the scrollback, it is printed and logged as well.
+    pub fn print
+    (
+        &mut self,
+        line: impl Into<String>
+    )
+    {
+        let line = line.into();
+        logger::echo(&line);
+        self.append(line);
+    }
+
+    /// Adds a line already logged.
+    pub(crate) fn append
+    (
+        &mut self,
+        line: String
+    )
+    {
+        if self.scrollback.len() == SCROLLBACK_LINES
+        {
+            self.scrollback.pop_front();
+        }
+        self.scrollback.push_back(line);
+        self.printed += 1;
+    }
+
+    pub fn printed
+    (
+        &self
+    )
+    -> u64
+    {
+        self.printed
+    }
+
+    /// Lines printed after [printed] lines, as far as the scrollback still has them.
+    pub fn printed_since
+    (
+        &self,
+        printed: u64
+    )
+    -> impl Iterator<Item = &String>
+    {
+        let new = self.printed.saturating_sub(printed).min(self.scrollback.len() as u64) as usize;
+        self.scrollback.iter().skip(self.scrollback.len() - new)
+    }
+
+    /// Oldest line first.
+    pub fn scrollback
+    (
+        &self
+    )
+    -> &VecDeque<String>
+    {
+        &self.scrollback
+    }
+
+    pub fn typing
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.typing
+    }
+
+    pub fn typing_mut
+    (
+        &mut self
+    )
+    -> &mut String
+    {
+        &mut self.typing
+    }
+
+    /// Oldest line first.
+    pub fn history
+    (
+        &self
+    )
+    -> &VecDeque<String>
+    {
+        &self.history
+    }
+
+    pub fn completions
+    (
+        &self
+    )
+    -> &[String]
+    {
+        &self.completions
+    }
+
+    /// Takes the typed line to issue it, it is kept in the history and shown in the scrollback.
+    pub fn submit
+    (
+        &mut self
+    )
+    -> Option<String>
+    {
+        self.history_index = None;
+        self.completions.clear();
+
+        let line = std::mem::take(&mut self.typing);
+        let line = line.trim();
+        if line.is_empty()
+        {
+            return None;
+        }
+
+        // Shown and logged without passwords, the history keeps the line as typed.
+        self.print(format!("> {}", crate::crash::redact(line)));
+
+        if self.history.back().is_none_or(|last| last != line)
+        {
+            if self.history.len() == HISTORY_LINES
+            {
+                self.history.pop_front();
+            }
+            self.history.push_back(line.to_string());
+        }
+
+        Some(line.to_string())
+    }
+
+    /// Replaces the typed line with the one submitted before it.
+    pub fn history_previous
+    (
+        &mut self
+    )
+    {
+        let index = match self.history_index
+        {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1
+        };
+
+        self.history_index = Some(index);
+        self.typing = self.history[index].clone();
+    }
+
+    /// Replaces the typed line with the one submitted after it, past the newest an empty line.
+    pub fn history_next
+    (
+        &mut self
+    )
+    {
+        match self.history_index
+        {
+            Some(index) if index + 1 < self.history.len() =>
+            {
+                self.history_index = Some(index + 1);
+                self.typing = self.history[index + 1].clone();
+            }
+            Some(_) =>
+            {
+                self.history_index = None;
+                self.typing.clear();
+            }
+            None => ()
+        }
+    }
+
+    /// Completes the keyword, or the first parameter of commands that declare a [ConsoleCompletion].
+    /// With several candidates the common start is completed and the candidates are printed.
+    pub fn complete
+    (
+        &mut self,
+        commands: &ConsoleCommands,
+        cvars: &Cvars
+    )
+    {
+        let (head, word, candidates): (String, String, Vec<String>) = match self.typing.split_once(' ')
+        {
+            None =>
+            {
+                let keywords = commands.iter().map(|declared| declared.keyword.clone());
+                let aliases = commands.aliases().map(|(name, _)| name.to_string());
+                (String::new(), self.typing.clone(), keywords.chain(aliases).collect())
+            }
+            Some((keyword, word)) if !word.contains(' ') =>
+            {
+                let candidates = match commands.find(keyword).map(|declared| declared.completion)
+                {
+                    Some(ConsoleCompletion::Commands) => commands.iter().map(|declared| declared.keyword.clone()).collect(),
+                    Some(ConsoleCompletion::Cvars) => cvars.iter().map(|cvar| cvar.name().to_string()).collect(),
+                    _ => Vec::new()
+                };
+                (format!("{} ", keyword), word.to_string(), candidates)
+            }
+            _ => return
+        };
+
+        self.completions = candidates.into_iter().filter(|candidate| candidate.starts_with(&word)).collect();
+
+        match self.completions.as_slice()
+        {
+            [] => (),
+            [completion] => self.typing = format!("{}{} ", head, completion),
+            completions =>
+            {
+                let mut common = completions[0].clone();
+                for completion in &completions[1..]
+                {
+                    while !completion.starts_with(&common)
+                    {
+                        common.pop();
+                    }
+                }
+                self.typing = format!("{}{}", head, common);
+
+                let line = completions.join("  ");
+                self.print(line);
+            }
+        }
+    }
+}
+
+/// Splits a line at every `;` outside of quotes.
+pub fn split_commands
+(
+    line: &str
+)
+-> Vec<String>
+{
+    let mut commands = Vec::new();
+    let mut command = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for character in line.chars()
+    {
+        match character
+        {
+            ';' if !quoted =>
+            {
+                commands.push(std::mem::take(&mut command));
+                continue;
+            }
+            '"' if !escaped => quoted = !quoted,
+            _ => ()
+        }
+        escaped = quoted && character == '\\' && !escaped;
+        command.push(character);
+    }
+    commands.push(command);
+
+    commands.into_iter().map(|command| command.trim().to_string()).filter(|command| !command.is_empty()).collect()
+}
+
+/// Conversion for [ConsoleCommand::get].
+pub trait FromConsoleParameter: Sized
+{
+    fn from_parameter
+    (
+        parameter: &ConsoleCommandParameter,
+        text: &str
+    )
+    -> Option<Self>;
+}
+
+impl FromConsoleParameter for bool
+{
+    fn from_parameter
+    (
+        parameter: &ConsoleCommandParameter,
+        _text: &str
+    )
+    -> Option<bool>
+    {
+        match parameter
+        {
+            ConsoleCommandParameter::Bool(value) => Some(*value),
+            _ => None
+        }
+    }
+}
+
+macro_rules! from_console_integer
+{
+    (
+        $($integer:ty),*
+    )
+    =>
+    {
+        $(
+            impl FromConsoleParameter for $integer
+            {
+                fn from_parameter
+                (
+                    parameter: &ConsoleCommandParameter,
+                    _text: &str
+                )
+                -> Option<$integer>
+                {
+                    match parameter
+                    {
+                        ConsoleCommandParameter::Integer(value) => <$integer>::try_from(*value).ok(),
+                        _ => None
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_console_integer!(i32, i64, u8, u32, u64, usize);
+
+impl FromConsoleParameter for f32
+{
+    fn from_parameter
+    (
+        parameter: &ConsoleCommandParameter,
+        _text: &str
+    )
+    -> Option<f32>
+    {
+        match parameter
+        {
+            ConsoleCommandParameter::Float(value) => Some(*value),
+            ConsoleCommandParameter::Integer(value) => Some(*value as f32),
+            _ => None
+        }
+    }
+}
+
+impl FromConsoleParameter for String
+{
+    fn from_parameter
+    (
+        _parameter: &ConsoleCommandParameter,
+        text: &str
+    )
+    -> Option<String>
+    {
+        Some(text.to_string())
+    }
+}
+
+impl FromConsoleParameter for Vec<f32>
+{
+    fn from_parameter
+    (
+        parameter: &ConsoleCommandParameter,
+        _text: &str
+    )
+    -> Option<Vec<f32>>
+    {
+        match parameter
+        {
+            ConsoleCommandParameter::Vector(vector) => Some(vector.clone()),
+            _ => None
+        }
+    }
+}
+
+impl FromConsoleParameter for Vector3<f32>
+{
+    fn from_parameter
+    (
+        parameter: &ConsoleCommandParameter,
+        _text: &str
+    )
+    -> Option<Vector3<f32>>
+    {
+        match parameter
+        {
+            ConsoleCommandParameter::Vector(vector) if vector.len() == 3 => Some(Vector3::new(vector[0], vector[1], vector[2])),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parse
+    ()
+    {
+        let command = ConsoleCommand::parse("  say \"hello  world\" 5 5.0 -2 true (1, 2 3) word").unwrap();
+        assert_eq!(command.keyword(), "say");
+        assert_eq!
+        (
+            command.parameters(),
+            &[
+                ConsoleCommandParameter::String(String::from("hello  world")),
+                ConsoleCommandParameter::Integer(5),
+                ConsoleCommandParameter::Float(5.0),
+                ConsoleCommandParameter::Integer(-2),
+                ConsoleCommandParameter::Bool(true),
+                ConsoleCommandParameter::Vector(vec![1.0, 2.0, 3.0]),
+                ConsoleCommandParameter::String(String::from("word"))
+            ]
+        );
+
+        // Quoted values stay strings and escapes are taken as written.
+        let command = ConsoleCommand::parse("echo \"5\" \"say \\\"hi\\\"\"").unwrap();
+        assert_eq!(command.parameters()[0], ConsoleCommandParameter::String(String::from("5")));
+        assert_eq!(command.get::<String>(1).as_deref(), Some("say \"hi\""));
+
+        assert_eq!(ConsoleCommand::parse("   ").unwrap().keyword(), "");
+        assert!(matches!(ConsoleCommand::parse("say \"hello"), Err(ConsoleError::UnterminatedQuote(_))));
+        assert!(matches!(ConsoleCommand::parse("move (1, 2"), Err(ConsoleError::UnterminatedVector(_))));
+        assert!(matches!(ConsoleCommand::parse("move (1, x)"), Err(ConsoleError::VectorComponent { .. })));
+
+        // Displayed parameters parse back the same.
+        for parameter in ConsoleCommand::parse("say \"a \\\"b\\\"\" 5 5.0 true (1, 2.5)").unwrap().parameters()
+        {
+            assert_eq!(&ConsoleCommandParameter::from_text(&parameter.to_string()).unwrap(), parameter);
+        }
+    }
+
+    #[test]
+    fn validate
+    ()
+    {
+        let declared = ConsoleCommand::new("teleport", vec![ConsoleParameterKind::Vector])
+            .optional(vec![ConsoleParameterKind::Float, ConsoleParameterKind::String]);
+
+        assert!(ConsoleCommand::parse("teleport (1, 2, 3)").unwrap().validate(&declared).is_ok());
+        assert!(ConsoleCommand::parse("teleport (1, 2, 3) 2 fast").unwrap().validate(&declared).is_ok());
+        assert!(ConsoleCommand::parse("teleport (1, 2, 3) 2.5 true").unwrap().validate(&declared).is_ok());
+
+        assert!(matches!
+        (
+            ConsoleCommand::parse("teleport").unwrap().validate(&declared),
+            Err(ConsoleError::ParameterCount { minimum: 1, maximum: 3, found: 0, .. })
+        ));
+        assert!(matches!
+        (
+            ConsoleCommand::parse("teleport (1) 1 a b").unwrap().validate(&declared),
+            Err(ConsoleError::ParameterCount { found: 4, .. })
+        ));
+        assert!(matches!
+        (
+            ConsoleCommand::parse("teleport (1) fast").unwrap().validate(&declared),
+            Err(ConsoleError::ParameterKind { index: 1, expected: ConsoleParameterKind::Float, .. })
+        ));
+
+        // Floats are not taken where integers are declared.
+        let declared = ConsoleCommand::new("count", vec![ConsoleParameterKind::Integer]);
+        assert!(ConsoleCommand::parse("count 3").unwrap().validate(&declared).is_ok());
+        assert!(matches!
+        (
+            ConsoleCommand::parse("count 3.0").unwrap().validate(&declared),
+            Err(ConsoleError::ParameterKind { index: 0, expected: ConsoleParameterKind::Integer, .. })
+        ));
+    }
+
+    #[test]
+    fn get
+    ()
+    {
+        let command = ConsoleCommand::parse("set 3 -1 2.5 true (1, 2, 3) (1, 2) 007").unwrap();
+
+        assert_eq!(command.get::<i64>(0), Some(3));
+        assert_eq!(command.get::<u32>(0), Some(3));
+        assert_eq!(command.get::<f32>(0), Some(3.0));
+        assert_eq!(command.get::<u32>(1), None);
+        assert_eq!(command.get::<i32>(1), Some(-1));
+        assert_eq!(command.get::<i64>(2), None);
+        assert_eq!(command.get::<f32>(2), Some(2.5));
+        assert_eq!(command.get::<bool>(3), Some(true));
+        assert_eq!(command.get::<bool>(0), None);
+        assert_eq!(command.get::<Vector3<f32>>(4), Some(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(command.get::<Vector3<f32>>(5), None);
+        assert_eq!(command.get::<Vec<f32>>(5), Some(vec![1.0, 2.0]));
+
+        // Strings are given as typed.
+        assert_eq!(command.get::<String>(6).as_deref(), Some("007"));
+        assert_eq!(command.get::<i64>(6), Some(7));
+        assert_eq!(command.get::<String>(7), None);
+    }
+
+    fn handler
+    (
+        _framework: &mut Framework,
+        _command: &ConsoleCommand
+    )
+    {
+    }
+
+    #[test]
+    fn registry
+    ()
+    {
+        let mut commands = ConsoleCommands::new();
+        commands.register(ConsoleCommand::new("quit", Vec::new()).describe("Quits."), ConsoleHandler::Framework(handler));
+        commands.register(ConsoleCommand::new("give", vec![ConsoleParameterKind::String]).optional(vec![ConsoleParameterKind::Integer]), ConsoleHandler::System("inventory"));
+
+        // The first registration is kept.
+        commands.register(ConsoleCommand::new("quit", vec![ConsoleParameterKind::Bool]), ConsoleHandler::System("other"));
+        assert_eq!(commands.find("quit").unwrap().description(), "Quits.");
+        assert!(commands.find("quit").unwrap().signature().is_empty());
+
+        let keywords: Vec<&str> = commands.iter().map(ConsoleCommand::keyword).collect();
+        assert_eq!(keywords, ["give", "quit"]);
+
+        assert!(matches!(commands.resolve(&ConsoleCommand::parse("quit").unwrap()), Ok(ConsoleHandler::Framework(_))));
+        assert!(matches!(commands.resolve(&ConsoleCommand::parse("give sword 2").unwrap()), Ok(ConsoleHandler::System("inventory"))));
+        assert!(matches!(commands.resolve(&ConsoleCommand::parse("give").unwrap()), Err(ConsoleError::ParameterCount { .. })));
+
+        let unknown = commands.resolve(&ConsoleCommand::parse("jump 1").unwrap());
+        assert!(matches!(&unknown, Err(ConsoleError::Unknown(keyword)) if keyword == "jump"));
+        assert_eq!(unknown.err().unwrap().to_string(), "Unknown command 'jump', 'help' lists all commands.");
+
+        // Aliases can not hide commands.
+        assert!(matches!(commands.alias("quit", "give sword"), Err(ConsoleError::AliasIsCommand(_))));
+        assert!(commands.alias("arm", "give sword; give shield").is_ok());
+        assert_eq!(commands.find_alias("arm"), Some("give sword; give shield"));
+        assert!(commands.unalias("arm"));
+        assert!(!commands.unalias("arm"));
+    }
+
+    #[test]
+    fn help
+    ()
+    {
+        let mut commands = ConsoleCommands::new();
+        commands.register(ConsoleCommand::new("quit", Vec::new()).describe("Quits."), ConsoleHandler::Framework(handler));
+        commands.register(ConsoleCommand::new("give", vec![ConsoleParameterKind::String]).optional(vec![ConsoleParameterKind::Integer]).describe("Gives an item."), ConsoleHandler::System("inventory"));
+
+        let mut console = Console::new();
+        commands.print_help(&mut console, None);
+        assert_eq!
+        (
+            console.scrollback(),
+            &[
+                String::from("Console commands:"),
+                format!("\t{:<24}Gives an item.", "give <string> [integer]"),
+                format!("\t{:<24}Quits.", "quit")
+            ]
+        );
+
+        let mut console = Console::new();
+        commands.print_help(&mut console, Some("give"));
+        assert_eq!(console.scrollback(), &[String::from("give <string> [integer]"), String::from("\tGives an item.")]);
+
+        let mut console = Console::new();
+        commands.print_help(&mut console, Some("jump"));
+        assert_eq!(console.scrollback(), &[String::from("Unknown command 'jump', 'help' lists all commands.")]);
+    }
+
+    #[test]
+    fn history
+    ()
+    {
+        let mut console = Console::new();
+        console.history_previous();
+        assert_eq!(console.typing(), "");
+
+        for line in ["first", "second", "second", "  ", "third"]
+        {
+            *console.typing_mut() = line.to_string();
+            console.submit();
+        }
+        assert_eq!(console.history(), &["first", "second", "third"]);
+
+        console.history_previous();
+        assert_eq!(console.typing(), "third");
+        console.history_previous();
+        console.history_previous();
+        console.history_previous();
+        assert_eq!(console.typing(), "first");
+        console.history_next();
+        assert_eq!(console.typing(), "second");
+        console.history_next();
+        console.history_next();
+        assert_eq!(console.typing(), "");
+
+        // Past the newest line nothing changes.
+        *console.typing_mut() = String::from("typed");
+        console.history_next();
+        assert_eq!(console.typing(), "typed");
+
+        // Submitting starts from the newest line again.
+        console.history_previous();
+        console.history_previous();
+        assert_eq!(console.submit().as_deref(), Some("second"));
+        console.history_previous();
+        assert_eq!(console.typing(), "second");
+    }
+
+    #[test]
+    fn complete
+    ()
+    {
+        let mut commands = ConsoleCommands::new();
+        commands.register(ConsoleCommand::new("set", vec![ConsoleParameterKind::String]).completion(ConsoleCompletion::Cvars), ConsoleHandler::Framework(handler));
+        commands.register(ConsoleCommand::new("save", Vec::new()), ConsoleHandler::Framework(handler));
+        commands.register(ConsoleCommand::new("help", Vec::new()).optional(vec![ConsoleParameterKind::String]).completion(ConsoleCompletion::Commands), ConsoleHandler::Framework(handler));
+        commands.alias("sensible", "set speed 1").unwrap();
+
+        let mut cvars = Cvars::new(crate::settings::Settings::empty());
+        cvars.register(crate::cvars::Cvar::new("speed", 1.0f32));
+        cvars.register(crate::cvars::Cvar::new("sensitivity", 1.0f32));
+
+        // Several candidates complete their common start and are printed.
+        let mut console = Console::new();
+        *console.typing_mut() = String::from("s");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "s");
+        assert_eq!(console.completions(), &["save", "set", "sensible"]);
+        assert_eq!(console.scrollback().back().map(String::as_str), Some("save  set  sensible"));
+
+        *console.typing_mut() = String::from("sa");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "save ");
+
+        *console.typing_mut() = String::from("sen");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "sensible ");
+
+        // The first parameter completes with what the command declares.
+        *console.typing_mut() = String::from("set s");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "set s");
+        assert_eq!(console.completions(), &["sensitivity", "speed"]);
+
+        *console.typing_mut() = String::from("set sp");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "set speed ");
+
+        *console.typing_mut() = String::from("help he");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "help help ");
+
+        // Nothing to complete.
+        *console.typing_mut() = String::from("save sl");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "save sl");
+        assert!(console.completions().is_empty());
+
+        *console.typing_mut() = String::from("set speed 2");
+        console.complete(&commands, &cvars);
+        assert_eq!(console.typing(), "set speed 2");
+    }
+}
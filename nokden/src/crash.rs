@@ -0,0 +1,262 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::console::{split_commands, ConsoleCommand, ConsoleCommandParameter};
+use crate::graphics::Info;
+use crate::logger;
+
+/// Commands kept for a crash report, oldest dropped first.
+const COMMANDS: usize = 32;
+
+/// Words with this in them are followed by secrets, like [crate::defaults::RCON_PASSWORD].
+const SECRET: &str = "password";
+
+/// Written instead of a secret.
+const REDACTED: &str = "***";
+
+/// Exit code after a report is written, the same Rust uses for a panic.
+const EXIT_CODE: i32 = 101;
+
+static REPORT: Mutex<Report> = Mutex::new(Report::new());
+
+/// What is known about the running application, gathered up front because the panic hook can not ask.
+struct Report
+{
+    directory: PathBuf,
+    name: String,
+    versions: Vec<String>,
+    graphics: Option<String>,
+    commands: VecDeque<String>
+}
+
+impl Report
+{
+    const fn new
+    ()
+    -> Report
+    {
+        Report
+        {
+            directory: PathBuf::new(),
+            name: String::new(),
+            versions: Vec::new(),
+            graphics: None,
+            commands: VecDeque::new()
+        }
+    }
+
+    fn write
+    (
+        &self,
+        info: &PanicHookInfo,
+        backtrace: &Backtrace
+    )
+    -> String
+    {
+        let mut text = String::new();
+        let _ = writeln!(text, "{} crashed.", self.name);
+        let _ = writeln!(text);
+        let _ = writeln!(text, "Thread {} {}", thread::current().name().unwrap_or("unnamed"), info);
+        let _ = writeln!(text);
+
+        for version in &self.versions
+        {
+            let _ = writeln!(text, "{}", version);
+        }
+        let _ = writeln!(text, "Graphics:\t{}", self.graphics.as_deref().unwrap_or("none"));
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "Last commands:");
+        for command in &self.commands
+        {
+            let _ = writeln!(text, "\t{}", command);
+        }
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "Last log lines:");
+        for line in logger::recent()
+        {
+            let _ = writeln!(text, "{}", line);
+        }
+        let _ = writeln!(text);
+
+        let _ = writeln!(text, "Backtrace:");
+        let _ = writeln!(text, "{}", backtrace);
+        text
+    }
+}
+
+/// Replaces the panic message with a report written to `name.crash.<seconds since 1970>.log` in a directory, then exits.
+/// Only for panics of the thread installing it, other threads panic as they did before.
+pub fn install
+(
+    directory: &Path,
+    name: &str,
+    versions: Vec<String>
+)
+{
+    {
+        let mut report = lock();
+        report.directory = directory.to_path_buf();
+        report.name = name.to_string();
+        report.versions = versions;
+    }
+
+    let main = thread::current().id();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info|
+    {
+        if thread::current().id() != main
+        {
+            previous(info);
+            return;
+        }
+
+        let backtrace = Backtrace::force_capture();
+        let report = lock();
+        let text = report.write(info, &backtrace);
+
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0);
+        let path = report.directory.join(format!("{}.crash.{}.{}", report.name, seconds, logger::LOG_EXTENSION));
+
+        // The logger may be what panicked, so straight to stderr.
+        eprintln!("{}", text);
+        match fs::write(&path, &text)
+        {
+            Ok(()) => eprintln!("Crash report written to {}.", path.to_string_lossy()),
+            Err(err) => eprintln!("Crash report not written to {}: {}", path.to_string_lossy(), err)
+        }
+
+        process::exit(EXIT_CODE);
+    }));
+}
+
+/// Device the report names, set by [crate::graphics::GraphicsSystem::new].
+pub fn set_graphics
+(
+    info: &Info
+)
+{
+    lock().graphics = Some(format!("{} {} ({})", info.api, info.device, info.device_type));
+}
+
+/// Remembers an issued console command for the report, see [redact].
+pub(crate) fn command
+(
+    line: &str
+)
+{
+    let line = redact(line);
+    let mut report = lock();
+    if report.commands.len() == COMMANDS
+    {
+        report.commands.pop_front();
+    }
+    report.commands.push_back(line);
+}
+
+/// Every word following one named like a password is left out, reports and logs get shared.
+/// Commands in quotes, like the ones of aliases and binds, are redacted as well.
+pub(crate) fn redact
+(
+    line: &str
+)
+-> String
+{
+    let commands = split_commands(line);
+    let redacted: Vec<Option<String>> = commands.iter().map(|command| redact_command(command)).collect();
+    if redacted.iter().all(Option::is_none)
+    {
+        return line.to_string();
+    }
+
+    let commands: Vec<String> = commands.into_iter().zip(redacted).map(|(command, redacted)| redacted.unwrap_or(command)).collect();
+    commands.join("; ")
+}
+
+/// [None] if there is nothing to leave out.
+fn redact_command
+(
+    command: &str
+)
+-> Option<String>
+{
+    if !command.to_lowercase().contains(SECRET)
+    {
+        return None;
+    }
+
+    let parsed = match ConsoleCommand::parse(command)
+    {
+        Ok(parsed) => parsed,
+        Err(_) => return Some(REDACTED.to_string())
+    };
+
+    let mut words = vec![parsed.keyword().to_string()];
+    let mut secret = parsed.keyword().to_lowercase().contains(SECRET);
+    for (index, parameter) in parsed.parameters().iter().enumerate()
+    {
+        let text = parsed.get::<String>(index).unwrap_or_default();
+        let word = match parameter
+        {
+            _ if secret => REDACTED.to_string(),
+            ConsoleCommandParameter::String(_) if text.contains([' ', ';']) => ConsoleCommandParameter::from(redact(&text)).to_string(),
+            _ => text.clone()
+        };
+        secret = text.to_lowercase().contains(SECRET);
+        words.push(word);
+    }
+    Some(words.join(" "))
+}
+
+/// Poisoned or not, the report is still wanted.
+fn lock
+()
+-> MutexGuard<'static, Report>
+{
+    REPORT.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn passwords_are_redacted
+    ()
+    {
+        assert_eq!(redact("set rcon_password hunter2"), "set rcon_password ***");
+        assert_eq!(redact("set rcon_password"), "set rcon_password");
+        assert_eq!(redact("set rcon_address 127.0.0.1:1"), "set rcon_address 127.0.0.1:1");
+
+        // Any command, quoted secrets and the commands of aliases and binds.
+        assert_eq!(redact("login Password \"two words\" now"), "login Password *** now");
+        assert_eq!(redact("echo a; set RCON_PASSWORD x"), "echo a; set RCON_PASSWORD ***");
+        assert_eq!(redact("alias secret \"set rcon_password x; echo done\""), "alias secret \"set rcon_password ***; echo done\"");
+        assert_eq!(redact("bind k \"alias a \\\"set rcon_password x\\\"\""), "bind k \"alias a \\\"set rcon_password ***\\\"\"");
+        assert_eq!(redact("set password \"unterminated"), "***");
+        assert_eq!(redact("echo \"a; b\""), "echo \"a; b\"");
+    }
+
+    #[test]
+    fn other_threads
+    ()
+    {
+        // The default hook prints the panic of the other thread, the test goes on.
+        let directory = crate::test_directory("crash");
+        install(&directory, "nokden-crash-test", Vec::new());
+        let panicked = thread::spawn(|| panic!("Panic of another thread, expected by the test.")).join();
+        drop(panic::take_hook());
+
+        assert!(panicked.is_err());
+        assert!(fs::read_dir(&directory).unwrap().next().is_none());
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use crate::console::{Console, ConsoleCommandParameter, ConsoleParameterKind, ConsoleError, FromConsoleParameter};
+use crate::settings::Settings;
+
+/// Console variable.
+/// Typed, named setting with a default, changed with `set <name> <value>`.
+#[derive(Clone, Debug)]
+pub struct Cvar
+{
+    name: String,
+    description: String,
+    kind: ConsoleParameterKind,
+    value: ConsoleCommandParameter,
+    default: ConsoleCommandParameter,
+
+    /// Default as overridden by the settings of the project, what the settings of the user are compared with.
+    base: ConsoleCommandParameter,
+
+    /// Inclusive, only for integers and floats.
+    range: Option<(f32, f32)>
+}
+
+impl Cvar
+{
+    /// The kind of the cvar is the kind of its default.
+    pub fn new
+    (
+        name: &str,
+        default: impl Into<ConsoleCommandParameter>
+    )
+    -> Cvar
+    {
+        let default = default.into();
+
+        Cvar
+        {
+            name: String::from(name),
+            description: String::new(),
+            kind: default.kind(),
+            value: default.clone(),
+            base: default.clone(),
+            default,
+            range: None
+        }
+    }
+
+    pub fn range
+    (
+        mut self,
+        minimum: f32,
+        maximum: f32
+    )
+    -> Cvar
+    {
+        self.range = Some((minimum, maximum));
+        self
+    }
+
+    /// Text shown when listing cvars.
+    pub fn describe
+    (
+        mut self,
+        description: &str
+    )
+    -> Cvar
+    {
+        self.description = String::from(description);
+        self
+    }
+
+    pub fn name
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.name
+    }
+
+    pub fn description
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.description
+    }
+
+    pub fn kind
+    (
+        &self
+    )
+    -> ConsoleParameterKind
+    {
+        self.kind
+    }
+
+    pub fn value
+    (
+        &self
+    )
+    -> &ConsoleCommandParameter
+    {
+        &self.value
+    }
+
+    pub fn default
+    (
+        &self
+    )
+    -> &ConsoleCommandParameter
+    {
+        &self.default
+    }
+
+    /// Returns none if the cvar is of another type.
+    pub fn get
+    <
+        T: FromConsoleParameter
+    >
+    (
+        &self
+    )
+    -> Option<T>
+    {
+        match &self.value
+        {
+            ConsoleCommandParameter::String(text) => T::from_parameter(&self.value, text),
+            value => T::from_parameter(value, &value.to_string())
+        }
+    }
+
+    /// Converts [value] to the kind of the cvar and checks its range.
+    fn check
+    (
+        &self,
+        value: ConsoleCommandParameter
+    )
+    -> Result<ConsoleCommandParameter, ConsoleError>
+    {
+        if !self.kind.accepts(&value)
+        {
+            return Err(ConsoleError::CvarKind { name: self.name.clone(), expected: self.kind, found: value.to_string() });
+        }
+
+        let value = match (self.kind, value)
+        {
+            (ConsoleParameterKind::Float, ConsoleCommandParameter::Integer(value)) => ConsoleCommandParameter::Float(value as f32),
+            (ConsoleParameterKind::String, ConsoleCommandParameter::String(value)) => ConsoleCommandParameter::String(value),
+            (ConsoleParameterKind::String, value) => ConsoleCommandParameter::String(value.to_string()),
+            (_, value) => value
+        };
+
+        let number = match value
+        {
+            ConsoleCommandParameter::Integer(value) => Some(value as f32),
+            ConsoleCommandParameter::Float(value) => Some(value),
+            _ => None
+        };
+
+        match (self.range, number)
+        {
+            // NaN and infinity are parsed as floats but are never a useful value.
+            (_, Some(number)) if !number.is_finite() =>
+            {
+                Err(ConsoleError::CvarKind { name: self.name.clone(), expected: self.kind, found: value.to_string() })
+            }
+            (Some((minimum, maximum)), Some(number)) if !(minimum..=maximum).contains(&number) =>
+            {
+                Err(ConsoleError::CvarRange { name: self.name.clone(), minimum, maximum, found: value.to_string() })
+            }
+            _ => Ok(value)
+        }
+    }
+}
+
+/// Every cvar by name.
+/// Changes are collected until the run loop hands them to the systems.
+pub struct Cvars
+{
+    cvars: BTreeMap<String, Cvar>,
+    changed: Vec<String>,
+    settings: Settings,
+
+    /// Set for this run only, the settings of the user keep what they had for these.
+    transient: BTreeSet<String>
+}
+
+impl Cvars
+{
+    pub fn new
+    (
+        settings: Settings
+    )
+    -> Cvars
+    {
+        Cvars
+        {
+            cvars: BTreeMap::new(),
+            changed: Vec::new(),
+            settings,
+            transient: BTreeSet::new()
+        }
+    }
+
+    /// The first registration of a name is kept.
+    /// Starts with the value from the settings, invalid ones are printed and the default is kept.
+    pub fn register
+    (
+        &mut self,
+        mut cvar: Cvar
+    )
+    {
+        if self.cvars.contains_key(&cvar.name)
+        {
+            warn!("cvars", "Cvar '{}' is already registered.", cvar.name);
+            return;
+        }
+
+        if let Some(value) = self.settings.project_value(&cvar.name)
+        {
+            match cvar.check(value.clone())
+            {
+                Ok(value) =>
+                {
+                    cvar.base = value.clone();
+                    cvar.value = value;
+                }
+                Err(err) => warn!("settings", "Project settings: {}", err)
+            }
+        }
+
+        if let Some(value) = self.settings.user_value(&cvar.name)
+        {
+            match cvar.check(value.clone())
+            {
+                Ok(value) => cvar.value = value,
+                Err(err) => warn!("settings", "User settings: {}", err)
+            }
+        }
+
+        self.cvars.insert(cvar.name.clone(), cvar);
+    }
+
+    pub fn find
+    (
+        &self,
+        name: &str
+    )
+    -> Option<&Cvar>
+    {
+        self.cvars.get(name)
+    }
+
+    /// Returns none if there is no such cvar or it is of another type.
+    pub fn get
+    <
+        T: FromConsoleParameter
+    >
+    (
+        &self,
+        name: &str
+    )
+    -> Option<T>
+    {
+        self.cvars.get(name)?.get()
+    }
+
+    /// Setting the current value again does not count as a change.
+    pub fn set
+    (
+        &mut self,
+        name: &str,
+        value: impl Into<ConsoleCommandParameter>
+    )
+    -> Result<(), ConsoleError>
+    {
+        let cvar = self.cvars.get_mut(name).ok_or_else(|| ConsoleError::UnknownCvar(name.to_string()))?;
+        let value = cvar.check(value.into())?;
+        self.transient.remove(name);
+
+        if cvar.value != value
+        {
+            cvar.value = value;
+            if !self.changed.iter().any(|changed| changed == name)
+            {
+                self.changed.push(name.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [set] but the value is not written to the settings, for instance for options of the command line.
+    pub fn set_transient
+    (
+        &mut self,
+        name: &str,
+        value: impl Into<ConsoleCommandParameter>
+    )
+    -> Result<(), ConsoleError>
+    {
+        self.set(name, value)?;
+        self.transient.insert(name.to_string());
+        Ok(())
+    }
+
+    pub fn reset
+    (
+        &mut self,
+        name: &str
+    )
+    -> Result<(), ConsoleError>
+    {
+        let default = self.cvars.get(name).ok_or_else(|| ConsoleError::UnknownCvar(name.to_string()))?.default.clone();
+        self.set(name, default)
+    }
+
+    /// Cvars in alphabetical order.
+    pub fn iter
+    (
+        &self
+    )
+    -> impl Iterator<Item = &Cvar>
+    {
+        self.cvars.values()
+    }
+
+    pub fn print
+    (
+        &self,
+        console: &mut Console
+    )
+    {
+        console.print("Cvars:");
+        for cvar in self.iter()
+        {
+            console.print(format!("\t{:<24}{:<16}{}", cvar.name, cvar.value.to_string(), cvar.description));
+        }
+    }
+
+    /// Also holds the aliases and binds of the user.
+    pub fn settings
+    (
+        &self
+    )
+    -> &Settings
+    {
+        &self.settings
+    }
+
+    /// Written with the next [Cvars::save_settings].
+    pub(crate) fn settings_mut
+    (
+        &mut self
+    )
+    -> &mut Settings
+    {
+        &mut self.settings
+    }
+
+    /// Writes every cvar that differs from the settings of the project to the settings of the user.
+    pub fn save_settings
+    (
+        &mut self
+    )
+    {
+        for cvar in self.cvars.values().filter(|cvar| !self.transient.contains(&cvar.name))
+        {
+            let value = match cvar.value != cvar.base
+            {
+                true => Some(cvar.value.clone()),
+                false => None
+            };
+            self.settings.set_user_value(&cvar.name, value);
+        }
+
+        if let Err(err) = self.settings.write()
+        {
+            error!("settings", "Settings not written: {}", err);
+        }
+    }
+
+    /// Names of the cvars changed since the last call.
+    pub(crate) fn take_changed
+    (
+        &mut self
+    )
+    -> Vec<String>
+    {
+        mem::take(&mut self.changed)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn cvars
+    ()
+    -> Cvars
+    {
+        let mut cvars = Cvars::new(Settings::empty());
+        cvars.register(Cvar::new("count", 5).range(0.0, 10.0));
+        cvars.register(Cvar::new("speed", 1.0f32).range(0.5, 2.0));
+        cvars.register(Cvar::new("name", "player"));
+        cvars
+    }
+
+    #[test]
+    fn range
+    ()
+    {
+        let mut cvars = cvars();
+        assert!(cvars.set("count", 10).is_ok());
+        assert!(matches!(cvars.set("count", 11), Err(ConsoleError::CvarRange { .. })));
+        assert!(matches!(cvars.set("count", -1), Err(ConsoleError::CvarRange { .. })));
+        assert_eq!(cvars.get::<i64>("count"), Some(10));
+
+        // Integers are taken by float cvars and checked the same.
+        assert!(cvars.set("speed", 2).is_ok());
+        assert_eq!(cvars.get::<f32>("speed"), Some(2.0));
+        assert!(matches!(cvars.set("speed", 0.25f32), Err(ConsoleError::CvarRange { .. })));
+    }
+
+    #[test]
+    fn non_finite
+    ()
+    {
+        let mut cvars = cvars();
+        cvars.register(Cvar::new("scale", 1.0f32));
+
+        // As typed in the console.
+        for text in ["NaN", "inf", "-inf"]
+        {
+            let value = ConsoleCommandParameter::from_text(text).unwrap();
+            assert!(matches!(cvars.set("speed", value.clone()), Err(ConsoleError::CvarKind { .. })));
+            assert!(matches!(cvars.set("scale", value), Err(ConsoleError::CvarKind { .. })));
+        }
+        assert_eq!(cvars.get::<f32>("speed"), Some(1.0));
+        assert_eq!(cvars.get::<f32>("scale"), Some(1.0));
+    }
+
+    #[test]
+    fn kinds
+    ()
+    {
+        let mut cvars = cvars();
+        assert!(matches!(cvars.set("count", 1.5f32), Err(ConsoleError::CvarKind { .. })));
+        assert!(matches!(cvars.set("missing", 1), Err(ConsoleError::UnknownCvar(_))));
+
+        // Strings take anything as text.
+        assert!(cvars.set("name", 7).is_ok());
+        assert_eq!(cvars.get::<String>("name"), Some("7".to_string()));
+    }
+
+    #[test]
+    fn changes
+    ()
+    {
+        let mut cvars = cvars();
+        assert!(cvars.set("count", 5).is_ok());
+        assert!(cvars.take_changed().is_empty());
+        assert!(cvars.set("count", 6).is_ok());
+        assert_eq!(cvars.take_changed(), vec!["count".to_string()]);
+    }
+}
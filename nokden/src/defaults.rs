@@ -1,10 +1,51 @@
-pub const FULLSCREEN: bool = false;
+use crate::cvars::Cvar;
 
-pub const RESOLUTION_WIDTH: u32 = 1280;
-pub const RESOLUTION_HEIGHT: u32 = 720;
+pub const RESOLUTION_WIDTH: &str = "resolution_width";
+pub const RESOLUTION_HEIGHT: &str = "resolution_height";
 
-pub const FOV_Y: f32 = 80.0;
-pub const WORLD_Z_NEAR: f32 = 0.01;
-pub const WORLD_Z_FAR: f32 = 1000.0;
+pub const FOV_Y: &str = "fov_y";
+pub const WORLD_Z_NEAR: &str = "world_z_near";
+pub const WORLD_Z_FAR: &str = "world_z_far";
 
-pub const GUI_Z_FAR: f32 = 100.0;
\ No newline at end of file
+pub const GUI_Z_FAR: &str = "gui_z_far";
+
+pub const FPS_PRINT: &str = "fps_print";
+pub const DELTA_PRINT: &str = "delta_print";
+
+pub const RCON_ENABLED: &str = "rcon_enabled";
+pub const RCON_ADDRESS: &str = "rcon_address";
+pub const RCON_PASSWORD: &str = "rcon_password";
+
+pub const HEADLESS_RATE: &str = "headless_rate";
+
+pub const MAX_FPS: &str = "max_fps";
+pub const PRESENT_MODE: &str = "present_mode";
+
+pub const FIXED_RATE: &str = "fixed_rate";
+pub const FIXED_MAX_STEPS: &str = "fixed_max_steps";
+
+/// Cvars of the framework with their defaults.
+pub fn cvars
+()
+-> Vec<Cvar>
+{
+    vec!
+    [
+        Cvar::new(RESOLUTION_WIDTH, 1280).range(320.0, 7680.0).describe("Applied on restart."),
+        Cvar::new(RESOLUTION_HEIGHT, 720).range(240.0, 4320.0).describe("Applied on restart."),
+        Cvar::new(FOV_Y, 80.0).range(30.0, 150.0).describe("Vertical field of view in degrees."),
+        Cvar::new(WORLD_Z_NEAR, 0.01).range(0.001, 10.0),
+        Cvar::new(WORLD_Z_FAR, 1000.0).range(10.0, 100000.0),
+        Cvar::new(GUI_Z_FAR, 100.0).range(1.0, 10000.0),
+        Cvar::new(FPS_PRINT, false).describe("Prints frames per second every second."),
+        Cvar::new(DELTA_PRINT, false).describe("Prints the frame delta every frame."),
+        Cvar::new(RCON_ENABLED, false).describe("Accepts remote console connections."),
+        Cvar::new(RCON_ADDRESS, "127.0.0.1:27960").describe("Localhost address, or 'unix:' and a path for a Unix socket."),
+        Cvar::new(RCON_PASSWORD, "").describe("Needed to connect, the remote console does not start without one."),
+        Cvar::new(HEADLESS_RATE, 60.0).range(1.0, 1000.0).describe("Updates per second without a window."),
+        Cvar::new(MAX_FPS, 0.0).range(0.0, 1000.0).describe("Frames per second at most, below 1 for no limit."),
+        Cvar::new(PRESENT_MODE, "fifo").describe("One of fifo (vsync), fifo_relaxed, mailbox or immediate, applied on restart."),
+        Cvar::new(FIXED_RATE, 60.0).range(1.0, 1000.0).describe("Fixed updates per second of the simulation."),
+        Cvar::new(FIXED_MAX_STEPS, 8).range(1.0, 100.0).describe("Fixed updates per frame at most, time beyond is dropped.")
+    ]
+}
\ No newline at end of file
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::os::raw::c_char;
 use std::borrow::Cow;
 use std::ffi::CString;
@@ -22,15 +23,70 @@ use nalgebra::base::Matrix4;
 use nalgebra::{ Point3, Isometry3, Vector3 };
 use nalgebra::geometry::Perspective3;
 
-use crate::defaults;
+use crate::{defaults, Framework, SystemEvents, ConsoleCommand, Cvar};
+use crate::serialize::SerializeError;
+use crate::save::SaveSection;
+use crate::logger::{self, Level};
+use crate::crash;
 
 const SHADER_ENTRY_NAME: &str = "main";
 
 pub struct PresentIndex(u32);
 
+#[derive(Debug)]
+pub enum GraphicsError
+{
+    NoWindow,
+    Loading(String),
+    Vulkan
+    {
+        call: &'static str,
+        result: vk::Result
+    },
+    NoDevice,
+    NoDiscreteDevice,
+    NoQueueFamily,
+    NoMemoryType,
+    NoSurfaceFormat,
+    MissingExtensions(Vec<String>)
+}
+
+impl fmt::Display for GraphicsError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            GraphicsError::NoWindow => write!(f, "Graphics need a window, there is none when headless."),
+            GraphicsError::Loading(err) => write!(f, "Vulkan not loaded: {}", err),
+            GraphicsError::Vulkan { call, result } => write!(f, "Vulkan call {} failed: {}", call, result),
+            GraphicsError::NoDevice => write!(f, "No device with Vulkan support found."),
+            GraphicsError::NoDiscreteDevice => write!(f, "No dedicated graphics device found."),
+            GraphicsError::NoQueueFamily => write!(f, "The device has no queue that draws and presents to the window."),
+            GraphicsError::NoMemoryType => write!(f, "The device has no fitting memory type."),
+            GraphicsError::NoSurfaceFormat => write!(f, "The window surface has no format."),
+            GraphicsError::MissingExtensions(missing) => write!(f, "The device does not support the extensions {}.", missing.join(", "))
+        }
+    }
+}
+
+/// For [Result::map_err], names the failed call.
+fn vulkan
+(
+    call: &'static str
+)
+-> impl Fn(vk::Result) -> GraphicsError
+{
+    move |result| GraphicsError::Vulkan { call, result }
+}
+
 pub struct GraphicsSystem 
 {
-    fullscreen: bool,   // TODO Vulkan needs to resize buffers or it will panic.
     resolution_width: u32,
     resolution_height: u32,
 
@@ -54,17 +110,24 @@ pub struct GraphicsSystem
 
 impl GraphicsSystem
 {
+    /// Needs the window, check [Framework::is_headless] first.
     pub fn new
     (
-        window: &Window
+        framework: &Framework
     )
-    -> GraphicsSystem
+    -> Result<GraphicsSystem, GraphicsError>
     {
-        let entry = unsafe { Entry::load().unwrap() };
+        let window = framework.window().ok_or(GraphicsError::NoWindow)?;
+        let cvars = framework.cvars();
+        let resolution_width = cvars.get(defaults::RESOLUTION_WIDTH).unwrap();
+        let resolution_height = cvars.get(defaults::RESOLUTION_HEIGHT).unwrap();
+        let fov_y = cvars.get(defaults::FOV_Y).unwrap();
+
+        let entry = unsafe { Entry::load().map_err(|err| GraphicsError::Loading(err.to_string()))? };
 
         let application_info = vk::ApplicationInfo::builder().api_version(vk::API_VERSION_1_2);
 
-        let mut extensions: Vec<*const c_char> = ash_window::enumerate_required_extensions(window).unwrap().to_vec();
+        let mut extensions: Vec<*const c_char> = ash_window::enumerate_required_extensions(window).map_err(vulkan("enumerate_required_extensions"))?.to_vec();
         extensions.push(ext::DebugUtils::name().as_ptr());
 
         let layers = Self::debug_layers();
@@ -74,7 +137,7 @@ impl GraphicsSystem
             .enabled_extension_names(&extensions)
             .enabled_layer_names(&layers);
 
-        let instance = unsafe { entry.create_instance(&create_info, None).unwrap() };        
+        let instance = unsafe { entry.create_instance(&create_info, None).map_err(vulkan("create_instance"))? };        
 
         let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
             .message_severity
@@ -87,12 +150,12 @@ impl GraphicsSystem
             .pfn_user_callback(Some(Self::messenger_callback));
 
         let debug_utils = ext::DebugUtils::new(&entry, &instance);
-        let debug_utils_msg = unsafe { debug_utils.create_debug_utils_messenger(&debug_info, None).unwrap() };
+        let debug_utils_msg = unsafe { debug_utils.create_debug_utils_messenger(&debug_info, None).map_err(vulkan("create_debug_utils_messenger"))? };
 
         let surface = khr::Surface::new(&entry, &instance);
-        let surface_khr = unsafe { ash_window::create_surface(&entry, &instance, window, None).unwrap() };
+        let surface_khr = unsafe { ash_window::create_surface(&entry, &instance, window, None).map_err(vulkan("create_surface"))? };
         
-        let device = Device::new(&instance, &surface, &surface_khr);
+        let device = Device::new(&instance, &surface, &surface_khr)?;
         Self::check_device_extensions
         (
             &instance,
@@ -105,20 +168,26 @@ impl GraphicsSystem
                 // Needed by VK_KHR_acceleration_structure.
                 khr::DeferredHostOperations::name().to_str().unwrap().to_string(),
             ]
-        );
+        )?;
         
-        let swapchain = Swapchain::new(&instance, &device, &surface, &surface_khr, &window);
+        let present_mode: String = cvars.get(defaults::PRESENT_MODE).unwrap();
+        let swapchain = Swapchain::new(&instance, &device, &surface, &surface_khr, &window, &present_mode)?;
 
-        device.submit_setup(&swapchain);
+        device.submit_setup(&swapchain)?;
 
-        GraphicsSystem
+        let graphics = GraphicsSystem
         {
-            fullscreen: defaults::FULLSCREEN,
-            resolution_width: defaults::RESOLUTION_WIDTH,
-            resolution_height: defaults::RESOLUTION_HEIGHT,
-            fov_y: defaults::FOV_Y,
-            world_camera: WorldViewProjection::perspective(),           
-            gui_camera: GUIProjection::orthographic(),
+            resolution_width,
+            resolution_height,
+            fov_y,
+            world_camera: WorldViewProjection::perspective
+            (
+                resolution_width as f32 / resolution_height as f32,
+                fov_y,
+                cvars.get(defaults::WORLD_Z_NEAR).unwrap(),
+                cvars.get(defaults::WORLD_Z_FAR).unwrap()
+            ),
+            gui_camera: GUIProjection::orthographic(resolution_width as f32, resolution_height as f32, cvars.get(defaults::GUI_Z_FAR).unwrap()),
             instance,
             surface,
             surface_khr,
@@ -126,7 +195,9 @@ impl GraphicsSystem
             debug_utils,
             device,
             swapchain
-        }
+        };
+        crash::set_graphics(&graphics.info());
+        Ok(graphics)
     }    
 
     pub fn bind_buffer_memory
@@ -138,7 +209,7 @@ impl GraphicsSystem
         data: &Vec<T>,
         flags: vk::BufferUsageFlags,
     )
-    -> (vk::Buffer, vk::DeviceMemory)
+    -> Result<(vk::Buffer, vk::DeviceMemory), GraphicsError>
     {
         unsafe
         {
@@ -147,7 +218,7 @@ impl GraphicsSystem
                 .usage(flags)
                 .sharing_mode(vk::SharingMode::EXCLUSIVE);
             
-            let index_buffer = self.device.logical.create_buffer(&buffer_info, None).unwrap();
+            let index_buffer = self.device.logical.create_buffer(&buffer_info, None).map_err(vulkan("create_buffer"))?;
 
             let memory_req = self.device.logical.get_buffer_memory_requirements(index_buffer);
             let memory_type_index = self.device.find_memorytype_index
@@ -155,7 +226,7 @@ impl GraphicsSystem
                 &memory_req,
                 vk::MemoryPropertyFlags::HOST_VISIBLE |
                 vk::MemoryPropertyFlags::HOST_COHERENT
-            ).unwrap();
+            ).ok_or(GraphicsError::NoMemoryType)?;
 
             let allocate_info = vk::MemoryAllocateInfo
             {
@@ -163,16 +234,16 @@ impl GraphicsSystem
                 memory_type_index,
                 ..Default::default()
             };
-            let index_memory = self.device.logical.allocate_memory(&allocate_info, None).unwrap();
+            let index_memory = self.device.logical.allocate_memory(&allocate_info, None).map_err(vulkan("allocate_memory"))?;
 
-            let index_ptr = self.device.logical.map_memory(index_memory, 0, memory_req.size, vk::MemoryMapFlags::empty()).unwrap();
+            let index_ptr = self.device.logical.map_memory(index_memory, 0, memory_req.size, vk::MemoryMapFlags::empty()).map_err(vulkan("map_memory"))?;
             let mut index_slice = Align::new(index_ptr, align_of::<T>() as u64, memory_req.size);
             index_slice.copy_from_slice(&data);
             self.device.logical.unmap_memory(index_memory);
 
-            self.device.logical.bind_buffer_memory(index_buffer, index_memory, 0).unwrap();
+            self.device.logical.bind_buffer_memory(index_buffer, index_memory, 0).map_err(vulkan("bind_buffer_memory"))?;
 
-            (index_buffer, index_memory)
+            Ok((index_buffer, index_memory))
         }
     }
 
@@ -182,9 +253,10 @@ impl GraphicsSystem
         physical: &vk::PhysicalDevice,
         required_extensions: &[String]
     )
+    -> Result<(), GraphicsError>
     {   
-        let mut all_supported = true; 
-        let extensions = unsafe { instance.enumerate_device_extension_properties(*physical).unwrap() };
+        let mut missing: Vec<String> = Vec::new();
+        let extensions = unsafe { instance.enumerate_device_extension_properties(*physical).map_err(vulkan("enumerate_device_extension_properties"))? };
         let mut supported: Vec<String> = Vec::new();
         
         for required in required_extensions
@@ -208,19 +280,21 @@ impl GraphicsSystem
             )
             {
                 supported.push(format!("MISSING - {}", required));
-                all_supported = false;
+                missing.push(required.clone());
             }
         }
 
-        println!("Used device extensions:");
+        let level = if missing.is_empty() { Level::Debug } else { Level::Error };
+        logger::log(level, "graphics", format_args!("Used device extensions:"));
         for extension in supported
         {
-            println!("\t{}", extension);
+            logger::log(level, "graphics", format_args!("\t{}", extension));
         }
 
-        if !all_supported
+        match missing.is_empty()
         {
-            panic!("System requires all extensions to be supported.");
+            true => Ok(()),
+            false => Err(GraphicsError::MissingExtensions(missing))
         }
     }
 
@@ -231,11 +305,11 @@ impl GraphicsSystem
     {    
         let extensions = unsafe { self.instance.enumerate_device_extension_properties(self.device.physical).unwrap() };
         
-        println!("List device extensions:");
+        debug!("graphics", "List device extensions:");
         for extension in extensions
         {
             //let string = unsafe { CStr::from_ptr(extension.extension_name.as_ptr()).to_str().unwrap() };
-            println!("\t{}", unsafe { CStr::from_ptr(extension.extension_name.as_ptr()).to_str().unwrap() });
+            debug!("graphics", "\t{}", unsafe { CStr::from_ptr(extension.extension_name.as_ptr()).to_str().unwrap() });
         }
     }
 
@@ -334,16 +408,17 @@ impl GraphicsSystem
         }
     }
 
+    /// Vulkan validation messages go to the log under the tag "vulkan".
     unsafe extern "system" fn messenger_callback
     (
-        _severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+        severity: vk::DebugUtilsMessageSeverityFlagsEXT,
         _type: vk::DebugUtilsMessageTypeFlagsEXT,
         p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
         _user_data: *mut std::os::raw::c_void,
     ) -> vk::Bool32
     {
         let callback_data = *p_callback_data;
-        let message_id_number: i32 = callback_data.message_id_number as i32;
+        let message_id_number: i32 = callback_data.message_id_number;
 
         let message = if callback_data.p_message.is_null()
         {
@@ -354,9 +429,14 @@ impl GraphicsSystem
             CStr::from_ptr(callback_data.p_message).to_string_lossy()
         };
 
-        println!();
-        println!("ID: {}", &message_id_number.to_string());
-        println!("{}", message);
+        let level = match severity
+        {
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => Level::Error,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => Level::Warn,
+            vk::DebugUtilsMessageSeverityFlagsEXT::INFO => Level::Debug,
+            _ => Level::Trace
+        };
+        logger::log(level, "vulkan", format_args!("ID {}: {}", message_id_number, message));
 
         vk::FALSE
     }
@@ -420,6 +500,76 @@ impl GraphicsSystem
     }
 }
 
+impl SystemEvents for GraphicsSystem
+{
+    fn name
+    (
+        &self
+    )
+    -> &'static str
+    {
+        "graphics"
+    }
+
+    fn commands
+    (
+        &self
+    )
+    -> Vec<ConsoleCommand>
+    {
+        Vec::new()
+    }
+
+    fn console
+    (
+        &mut self,
+        _framework: &mut Framework,
+        _command: &ConsoleCommand
+    )
+    {
+    }
+
+    /// Projections are rebuilt, resolution only applies on restart.
+    fn cvar_changed
+    (
+        &mut self,
+        framework: &mut Framework,
+        cvar: &Cvar
+    )
+    {
+        let cvars = framework.cvars();
+
+        match cvar.name()
+        {
+            defaults::FOV_Y =>
+            {
+                self.fov_y = cvar.get().unwrap();
+                self.world_camera.projection.set_fovy(self.fov_y.to_radians());
+            }
+            defaults::WORLD_Z_NEAR | defaults::WORLD_Z_FAR =>
+            {
+                self.world_camera.projection.set_znear_and_zfar(cvars.get(defaults::WORLD_Z_NEAR).unwrap(), cvars.get(defaults::WORLD_Z_FAR).unwrap());
+            }
+            defaults::GUI_Z_FAR =>
+            {
+                self.gui_camera = GUIProjection::orthographic(self.resolution_width as f32, self.resolution_height as f32, cvar.get().unwrap());
+            }
+            _ => ()
+        }
+    }
+
+    fn save_load
+    (
+        &mut self,
+        _framework: &mut Framework,
+        _section: SaveSection
+    )
+    -> Result<(), SerializeError>
+    {
+        Ok(())
+    }
+}
+
 pub struct WorldViewProjection
 {
     pub projection: Perspective3<f32>,
@@ -428,19 +578,19 @@ pub struct WorldViewProjection
 
 impl WorldViewProjection
 {
+    /// [fov_y] is in degrees.
     fn perspective
-    ()    
+    (
+        aspect: f32,
+        fov_y: f32,
+        z_near: f32,
+        z_far: f32
+    )
     -> WorldViewProjection
     {
         WorldViewProjection
         {
-            projection: Perspective3::new
-            (
-                defaults::RESOLUTION_WIDTH as f32 / defaults::RESOLUTION_HEIGHT as f32,
-                defaults::FOV_Y,
-                defaults::WORLD_Z_NEAR,
-                defaults::WORLD_Z_FAR
-            ),
+            projection: Perspective3::new(aspect, fov_y.to_radians(), z_near, z_far),
             transform: Isometry3::look_at_rh
             (
                 &Point3::new(0.0, 0.0, -5.0),
@@ -459,17 +609,18 @@ pub struct GUIProjection
 impl GUIProjection
 {
     fn orthographic
-    ()
+    (
+        width: f32,
+        height: f32,
+        far: f32
+    )
     -> GUIProjection
     {
-        let width = defaults::RESOLUTION_WIDTH as f32;
-        let height = defaults::RESOLUTION_HEIGHT as f32;
 
         let left = -(width / 2.0);
         let right = width / 2.0;
         let top = -(height / 2.0);
         let bottom = height / 2.0;
-        let far = defaults::GUI_Z_FAR;
 
         let left_right = 2.0 / (right - left);
         let top_bottom = 2.0 / (bottom - top);
@@ -518,7 +669,6 @@ pub struct Device
     physical: vk::PhysicalDevice,
 
     memory_props: vk::PhysicalDeviceMemoryProperties,
-    queue_family: u32,
     queue_present: Queue,
 
     pool: vk::CommandPool,
@@ -537,7 +687,7 @@ impl Device
         surface_ld: &khr::Surface,
         surface: &vk::SurfaceKHR
     )
-    -> Device
+    -> Result<Device, GraphicsError>
     {
         unsafe
         {
@@ -549,11 +699,11 @@ impl Device
 
             let physical =
             {
-                let devices: Vec<vk::PhysicalDevice> = instance.enumerate_physical_devices().unwrap();
+                let devices: Vec<vk::PhysicalDevice> = instance.enumerate_physical_devices().map_err(vulkan("enumerate_physical_devices"))?;
 
                 match devices.len()
                 {
-                    device_count if device_count == 0 => panic!("No device with Vulkan support found."),
+                    device_count if device_count == 0 => return Err(GraphicsError::NoDevice),
                     _ => *devices
                         .iter()
                         .find
@@ -567,7 +717,7 @@ impl Device
                                 }
                             }
                         )
-                        .unwrap()
+                        .ok_or(GraphicsError::NoDiscreteDevice)?
                 }
             };
 
@@ -581,7 +731,7 @@ impl Device
                     (
                         |(i, info)|
                         {
-                            match info.queue_flags.contains(vk::QueueFlags::GRAPHICS) && surface_ld.get_physical_device_surface_support(physical, i as u32, *surface).unwrap()
+                            match info.queue_flags.contains(vk::QueueFlags::GRAPHICS) && surface_ld.get_physical_device_surface_support(physical, i as u32, *surface).unwrap_or(false)
                             {
                                 true => Some(i),
                                 false => None
@@ -589,7 +739,7 @@ impl Device
                         }
                     )
                     .next()
-                    .unwrap() as u32
+                    .ok_or(GraphicsError::NoQueueFamily)? as u32
             };
 
             let logical =
@@ -601,7 +751,7 @@ impl Device
                 let queue_info = [vk::DeviceQueueCreateInfo::builder().queue_family_index(queue_family).queue_priorities(&[0.5]).build()];
                 let device_create_info = vk::DeviceCreateInfo::builder().queue_create_infos(&queue_info).enabled_extension_names(&extensions).enabled_features(&features);
 
-                instance.create_device(physical, &device_create_info, None).unwrap()
+                instance.create_device(physical, &device_create_info, None).map_err(vulkan("create_device"))?
             };
 
             let queue_present = logical.get_device_queue(queue_family, 0);
@@ -610,26 +760,25 @@ impl Device
             let pool_create_info = vk::CommandPoolCreateInfo::builder()
                 .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
                 .queue_family_index(queue_family);
-            let pool = logical.create_command_pool(&pool_create_info, None).unwrap();
+            let pool = logical.create_command_pool(&pool_create_info, None).map_err(vulkan("create_command_pool"))?;
 
             let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
                 .command_buffer_count(2)
                 .command_pool(pool)
                 .level(vk::CommandBufferLevel::PRIMARY);
-            let command_buffers = logical.allocate_command_buffers(&command_buffer_allocate_info).unwrap();
+            let command_buffers = logical.allocate_command_buffers(&command_buffer_allocate_info).map_err(vulkan("allocate_command_buffers"))?;
             let setup_command_buffer = command_buffers[0];
             let draw_command_buffer = command_buffers[1];
 
             let semaphore_create_info = vk::SemaphoreCreateInfo::default();
-            let present_semaphore = logical.create_semaphore(&semaphore_create_info, None).unwrap();
-            let rendering_semaphore = logical.create_semaphore(&semaphore_create_info, None).unwrap();
+            let present_semaphore = logical.create_semaphore(&semaphore_create_info, None).map_err(vulkan("create_semaphore"))?;
+            let rendering_semaphore = logical.create_semaphore(&semaphore_create_info, None).map_err(vulkan("create_semaphore"))?;
 
-            Device
+            Ok(Device
             {
                 clear_values: clear_values.to_vec(),
                 logical,
                 physical,
-                queue_family,
                 queue_present,
                 memory_props,
                 present_semaphore,
@@ -637,7 +786,7 @@ impl Device
                 pool,
                 draw_command_buffer,
                 setup_command_buffer
-            }
+            })
         }
     }
 
@@ -646,13 +795,14 @@ impl Device
         &self,
         swapchain: &Swapchain
     )
+    -> Result<(), GraphicsError>
     {
         unsafe
             {
-                self.logical.reset_command_buffer(self.setup_command_buffer, vk::CommandBufferResetFlags::RELEASE_RESOURCES).unwrap();
+                self.logical.reset_command_buffer(self.setup_command_buffer, vk::CommandBufferResetFlags::RELEASE_RESOURCES).map_err(vulkan("reset_command_buffer"))?;
                 let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
 
-                self.logical.begin_command_buffer(self.setup_command_buffer, &command_buffer_begin_info).unwrap();
+                self.logical.begin_command_buffer(self.setup_command_buffer, &command_buffer_begin_info).map_err(vulkan("begin_command_buffer"))?;
 
                 let image_subres_range = vk::ImageSubresourceRange::builder()
                     .aspect_mask(vk::ImageAspectFlags::DEPTH)
@@ -676,20 +826,21 @@ impl Device
                     &[],
                     &[layout_transition_barriers.build()]);
 
-                self.logical.end_command_buffer(self.setup_command_buffer).unwrap();
+                self.logical.end_command_buffer(self.setup_command_buffer).map_err(vulkan("end_command_buffer"))?;
 
-                let submit_fence = self.logical.create_fence(&vk::FenceCreateInfo::default(), None).unwrap();
+                let submit_fence = self.logical.create_fence(&vk::FenceCreateInfo::default(), None).map_err(vulkan("create_fence"))?;
                 let command_buffers = vec![self.setup_command_buffer];
                 let submit_info = vk::SubmitInfo::builder()
                     .wait_semaphores(&[])
                     .wait_dst_stage_mask(&[])
                     .command_buffers(&command_buffers)
                     .signal_semaphores(&[]);
-                self.logical.queue_submit(self.queue_present, &[submit_info.build()], submit_fence).unwrap();
+                self.logical.queue_submit(self.queue_present, &[submit_info.build()], submit_fence).map_err(vulkan("queue_submit"))?;
 
-                self.logical.wait_for_fences(&[submit_fence], true, u64::MAX).unwrap();
+                self.logical.wait_for_fences(&[submit_fence], true, u64::MAX).map_err(vulkan("wait_for_fences"))?;
                 self.logical.destroy_fence(submit_fence, None);
             }
+        Ok(())
     }
 
     pub fn find_memorytype_index
@@ -769,12 +920,13 @@ impl Swapchain
         device: &Device,
         surface_ld: &khr::Surface,
         surface: &vk::SurfaceKHR,
-        window: &Window
+        window: &Window,
+        present_mode: &str
     )
-    -> Swapchain
+    -> Result<Swapchain, GraphicsError>
     {
-        let caps = unsafe { surface_ld.get_physical_device_surface_capabilities(device.physical, *surface).unwrap() };
-        let format = Self::format(&device.physical, &surface_ld, &surface);
+        let caps = unsafe { surface_ld.get_physical_device_surface_capabilities(device.physical, *surface).map_err(vulkan("get_physical_device_surface_capabilities"))? };
+        let format = Self::format(&device.physical, &surface_ld, &surface)?;
         let image_count = Self::image_count(&caps);
         let resolution = Self::resolution(&caps, window.inner_size().width, window.inner_size().height);
         let transform = Self::transform(&caps);
@@ -791,7 +943,7 @@ impl Swapchain
 
         let scissors = [vk::Rect2D { offset: vk::Offset2D { x: 0, y: 0 }, extent: resolution}];
 
-        let present_mode = Self::present_mode(&device.physical, &surface_ld, &surface);
+        let present_mode = Self::present_mode(&device.physical, &surface_ld, &surface, present_mode);
 
         let swapchain_create_info = vk::SwapchainCreateInfoKHR::builder()
             .surface(*surface)
@@ -808,10 +960,10 @@ impl Swapchain
             .image_array_layers(1);
 
         let loader = khr::Swapchain::new(instance, &device.logical);
-        let swapchain = unsafe { loader.create_swapchain(&swapchain_create_info, None).unwrap() };
+        let swapchain = unsafe { loader.create_swapchain(&swapchain_create_info, None).map_err(vulkan("create_swapchain"))? };
 
-        let (present_images, present_image_views) = Self::present_images(&device, &loader, &swapchain, format);
-        let (depth_image, depth_image_view, depth_image_memory) = Self::depth_images(&device, &window);
+        let (present_images, present_image_views) = Self::present_images(&device, &loader, &swapchain, format)?;
+        let (depth_image, depth_image_view, depth_image_memory) = Self::depth_images(&device, &window)?;
 
         let renderpass =
         {
@@ -859,7 +1011,7 @@ impl Swapchain
                 .subpasses(&subpasses)
                 .dependencies(&dependencies);
 
-            unsafe { device.logical.create_render_pass(&renderpass_create_info, None).unwrap() }
+            unsafe { device.logical.create_render_pass(&renderpass_create_info, None).map_err(vulkan("create_render_pass"))? }
         };
 
         let framebuffers: Vec<vk::Framebuffer> = present_image_views
@@ -874,11 +1026,11 @@ impl Swapchain
                     .height(resolution.height)
                     .layers(1);
 
-                unsafe { device.logical.create_framebuffer(&frame_buffer_create_info, None).unwrap() }
+                unsafe { device.logical.create_framebuffer(&frame_buffer_create_info, None).map_err(vulkan("create_framebuffer")) }
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Swapchain
+        Ok(Swapchain
         {
             renderpass,
             framebuffers,
@@ -896,7 +1048,7 @@ impl Swapchain
             depth_image,
             depth_image_view,
             depth_image_memory
-        }
+        })
     }
 
     fn present_images
@@ -906,9 +1058,9 @@ impl Swapchain
         swapchain: &SwapchainKHR,
         surface_format: vk::SurfaceFormatKHR
     )
-    -> (Vec<vk::Image>, Vec<vk::ImageView>)
+    -> Result<(Vec<vk::Image>, Vec<vk::ImageView>), GraphicsError>
     {
-        let images = unsafe { swapchain_ld.get_swapchain_images(*swapchain).unwrap() };
+        let images = unsafe { swapchain_ld.get_swapchain_images(*swapchain).map_err(vulkan("get_swapchain_images"))? };
         let image_views: Vec<vk::ImageView> = images
             .iter()
             .map
@@ -941,12 +1093,12 @@ impl Swapchain
                         )
                         .image(x);
 
-                    unsafe { device.logical.create_image_view(&create_view_info, None).unwrap() }
+                    unsafe { device.logical.create_image_view(&create_view_info, None).map_err(vulkan("create_image_view")) }
                 }
             )
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        (images, image_views)
+        Ok((images, image_views))
     }
 
     fn depth_images
@@ -954,7 +1106,7 @@ impl Swapchain
         device: &Device,
         window: &Window
     )
-    -> (vk::Image, vk::ImageView, vk::DeviceMemory)
+    -> Result<(vk::Image, vk::ImageView, vk::DeviceMemory), GraphicsError>
     {
         let depth_image_create_info = vk::ImageCreateInfo::builder()
             .image_type(vk::ImageType::TYPE_2D)
@@ -967,13 +1119,13 @@ impl Swapchain
             .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
             .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-        let depth_image = unsafe { device.logical.create_image(&depth_image_create_info, None).unwrap() };
+        let depth_image = unsafe { device.logical.create_image(&depth_image_create_info, None).map_err(vulkan("create_image"))? };
         let depth_image_memory_req = unsafe { device.logical.get_image_memory_requirements(depth_image) };
-        let depth_image_memory_index = device.find_memorytype_index(&depth_image_memory_req, vk::MemoryPropertyFlags::DEVICE_LOCAL).unwrap();
+        let depth_image_memory_index = device.find_memorytype_index(&depth_image_memory_req, vk::MemoryPropertyFlags::DEVICE_LOCAL).ok_or(GraphicsError::NoMemoryType)?;
 
         let depth_image_allocate_info = vk::MemoryAllocateInfo::builder().allocation_size(depth_image_memory_req.size).memory_type_index(depth_image_memory_index);
-        let depth_image_memory = unsafe { device.logical.allocate_memory(&depth_image_allocate_info, None).unwrap() };
-        unsafe { device.logical.bind_image_memory(depth_image, depth_image_memory, 0).unwrap() };
+        let depth_image_memory = unsafe { device.logical.allocate_memory(&depth_image_allocate_info, None).map_err(vulkan("allocate_memory"))? };
+        unsafe { device.logical.bind_image_memory(depth_image, depth_image_memory, 0).map_err(vulkan("bind_image_memory"))? };
 
         let depth_image_view_info = vk::ImageViewCreateInfo::builder()
             .subresource_range
@@ -988,9 +1140,9 @@ impl Swapchain
             .format(depth_image_create_info.format)
             .view_type(vk::ImageViewType::TYPE_2D);
 
-        let depth_image_view = unsafe{ device.logical.create_image_view(&depth_image_view_info, None).unwrap() };
+        let depth_image_view = unsafe{ device.logical.create_image_view(&depth_image_view_info, None).map_err(vulkan("create_image_view"))? };
 
-        (depth_image, depth_image_view, depth_image_memory)
+        Ok((depth_image, depth_image_view, depth_image_memory))
     }
 
     fn image_count
@@ -1029,11 +1181,11 @@ impl Swapchain
         surface_ld: &khr::Surface,
         surface: &vk::SurfaceKHR
     )
-    -> vk::SurfaceFormatKHR
+    -> Result<vk::SurfaceFormatKHR, GraphicsError>
     {
         unsafe
         {
-            let surface_formats = surface_ld.get_physical_device_surface_formats(*device_phys, *surface).unwrap();
+            let surface_formats = surface_ld.get_physical_device_surface_formats(*device_phys, *surface).map_err(vulkan("get_physical_device_surface_formats"))?;
             surface_formats
                 .iter()
                 .map(|x| match x.format
@@ -1046,7 +1198,7 @@ impl Swapchain
                     _ => *x,
                 })
                 .next()
-                .unwrap()
+                .ok_or(GraphicsError::NoSurfaceFormat)
         }
     }
 
@@ -1063,20 +1215,39 @@ impl Swapchain
         }
     }
 
+    /// Falls back to FIFO, which every device supports, when the named mode is unknown or not supported.
     fn present_mode
     (
         device_phys: &vk::PhysicalDevice,
         surface_ld: &khr::Surface,
-        surface: &SurfaceKHR
+        surface: &SurfaceKHR,
+        name: &str
     )
     -> PresentModeKHR
     {
-        let present_modes = unsafe { surface_ld.get_physical_device_surface_present_modes(*device_phys, *surface).unwrap() };
-        present_modes
-            .iter()
-            .cloned()
-            .find(|&mode| mode == vk::PresentModeKHR::IMMEDIATE)
-            .unwrap_or(vk::PresentModeKHR::FIFO)
+        let wanted = match name
+        {
+            "fifo" => vk::PresentModeKHR::FIFO,
+            "fifo_relaxed" => vk::PresentModeKHR::FIFO_RELAXED,
+            "mailbox" => vk::PresentModeKHR::MAILBOX,
+            "immediate" => vk::PresentModeKHR::IMMEDIATE,
+            _ =>
+            {
+                warn!("graphics", "Present mode '{}' is unknown, using fifo.", name);
+                return vk::PresentModeKHR::FIFO;
+            }
+        };
+
+        let present_modes = unsafe { surface_ld.get_physical_device_surface_present_modes(*device_phys, *surface).unwrap_or_default() };
+        match present_modes.contains(&wanted)
+        {
+            true => wanted,
+            false =>
+            {
+                warn!("graphics", "Present mode '{}' is not supported, using fifo.", name);
+                vk::PresentModeKHR::FIFO
+            }
+        }
     }
 }
 
@@ -1252,4 +1423,24 @@ impl Shader
             device.logical.destroy_pipeline_layout(self.pipeline_layout, None);
         }
     }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn display
+    ()
+    {
+        let err = vk::Result::ERROR_OUT_OF_DEVICE_MEMORY;
+        assert_eq!(vulkan("allocate_memory")(err).to_string(), format!("Vulkan call allocate_memory failed: {}", err));
+        assert_eq!(GraphicsError::Loading(String::from("no library")).to_string(), "Vulkan not loaded: no library");
+        assert_eq!
+        (
+            GraphicsError::MissingExtensions(vec![String::from("VK_KHR_a"), String::from("VK_KHR_b")]).to_string(),
+            "The device does not support the extensions VK_KHR_a, VK_KHR_b."
+        );
+    }
 }
\ No newline at end of file
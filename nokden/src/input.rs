@@ -1,5 +1,8 @@
+use std::fmt;
 use winit::event::{DeviceEvent, VirtualKeyCode, ElementState};
-use crate::{CPS, Handle, Storage, enum_str, SystemEvents, ConsoleCommand, Framework, ConsoleCommandParameter};
+use crate::{CPS, Handle, Storage, StorageEntries, enum_str, SystemEvents, ConsoleCommand, Framework, ConsoleParameterKind, ConsoleCommandParameter, Console};
+use crate::serialize::{Serialize, Deserialize, Serializer, Deserializer, SerializeError};
+use crate::save::SaveSection;
 
 pub struct InputSystem
 {
@@ -11,7 +14,14 @@ pub struct InputSystem
     /// Signals Per Second.
     pub sps: CPS,
 
-    pub mappings: Storage<Mapping>
+    pub mappings: Storage<Mapping>,
+
+    /// Console commands issued by keys.
+    binds: Vec<Bind>,
+
+    /// Kept so the GUI mappings are not released.
+    submit: Handle<Mapping>,
+    typing_cancel: Handle<Mapping>
 }
 
 impl InputSystem
@@ -30,7 +40,10 @@ impl InputSystem
             signals_km: [Signal::Inactive; MAX_SIGNAL_SLOTS_KM],
             signals_mouse_cursor: [0.0, 0.0],
             sps: CPS::new("Input Signals Per Second"),
-            mappings
+            mappings,
+            binds: Vec::new(),
+            submit,
+            typing_cancel
         }
     }
 
@@ -43,6 +56,26 @@ impl InputSystem
         self.mode = mode;
     }
 
+    /// Mapping confirming what is typed in the GUI.
+    pub fn submit
+    (
+        &self
+    )
+    -> &Handle<Mapping>
+    {
+        &self.submit
+    }
+
+    /// Mapping leaving typing in the GUI.
+    pub fn typing_cancel
+    (
+        &self
+    )
+    -> &Handle<Mapping>
+    {
+        &self.typing_cancel
+    }
+
     pub fn add_mapping // TODO Needs to be removed.
     (
         &mut self,
@@ -163,7 +196,11 @@ impl InputSystem
     )
     -> bool
     {        
-        let mapping = self.mappings.read(mapping);
+        let mapping = match self.mappings.read(mapping)
+        {
+            Some(mapping) => mapping,
+            None => return false
+        };
         match mapping.custom
         {
             Some(custom_mapping) => Self::check_once_signal(&mut self.signals_km[custom_mapping as usize]),
@@ -219,13 +256,16 @@ impl InputSystem
         {            
             Mode::TypingNLS => 
             {                
+                // Keys the console widget handles itself do not type.
+                let console_keys = [MethodKM::F1, MethodKM::F2, MethodKM::Enter, MethodKM::TAB, MethodKM::ArrowUp, MethodKM::ArrowDown].map(|key| key as usize);
                 for (slot, signal) in self.signals_km.iter_mut().enumerate()
                 {
-                    if slot == MethodKM::F1 as usize {()}
-                    else if slot == MethodKM::F2 as usize {()}
-                    else if slot == MethodKM::Enter as usize {()}
-                    else if slot == MethodKM::TAB as usize {()}
-                    else if slot == MethodKM::Backspace as usize && Self::check_once_signal(signal)
+                    if console_keys.contains(&slot)
+                    {
+                        continue;
+                    }
+
+                    if slot == MethodKM::Backspace as usize && Self::check_once_signal(signal)
                     {
                         typing.pop();
                     }
@@ -299,11 +339,13 @@ impl InputSystem
         } 
     }
 
+    /// Keys and buttons without a [MethodKM] are an error, the event is left out.
     pub fn register_device_events
     (
         &mut self,
         input: &DeviceEvent
     )
+    -> Result<(), InputError>
     {
         match input // TODO Seems that the polling rate is 30hz only. That's a big problem for 60+ FPS games.
         {
@@ -322,6 +364,8 @@ impl InputSystem
                             VirtualKeyCode::Return => self.register_signal_km(MethodKM::Enter as usize, &keyboard.state),
                             VirtualKeyCode::LShift => self.register_signal_km(MethodKM::ShiftLeft as usize, &keyboard.state),
                             VirtualKeyCode::RShift => self.register_signal_km(MethodKM::ShiftRight as usize, &keyboard.state),
+                            VirtualKeyCode::Up => self.register_signal_km(MethodKM::ArrowUp as usize, &keyboard.state),
+                            VirtualKeyCode::Down => self.register_signal_km(MethodKM::ArrowDown as usize, &keyboard.state),
 
                             VirtualKeyCode::F1 => self.register_signal_km(MethodKM::F1 as usize, &keyboard.state),
                             VirtualKeyCode::F2 => self.register_signal_km(MethodKM::F2 as usize, &keyboard.state),
@@ -374,7 +418,7 @@ impl InputSystem
                             VirtualKeyCode::Y => self.register_signal_km(MethodKM::Y as usize, &keyboard.state),
                             VirtualKeyCode::Z => self.register_signal_km(MethodKM::Z as usize, &keyboard.state),
 
-                            _ => panic!("Virtual key code can not be converted: {0}",  active as u32)
+                            _ => return Err(InputError::UnknownKey(active as u32))
                         }
                     }
                     _ => ()
@@ -389,11 +433,13 @@ impl InputSystem
                     2 => self.register_signal_km(MethodKM::MouseMiddle as usize, state),
                     3 => self.register_signal_km(MethodKM::MouseRight as usize, state),
 
-                    _ => panic!("Button ID can not be converted: {0}",  *button as u32)
+                    _ => return Err(InputError::UnknownButton(*button))
                 }
             }
             _ => ()
         }
+
+        Ok(())
     }
 
     fn register_signal_km
@@ -462,17 +508,17 @@ impl InputSystem
 
     pub fn print_mappings
     (
-        &self
+        &self,
+        console: &mut Console
     )
     {
-        println!();
-        println!("List of input mappings:");
+        console.print("List of input mappings:");
         for (index, mapping) in self.mappings.all().iter().enumerate()
         {
             let mapping = mapping.read().unwrap();
 
-            println!();
-            println!("\t[{}] {}", index, mapping.name);
+            console.print("");
+            console.print(format!("\t[{}] {}", index, mapping.name));
 
             match mapping.custom
             {
@@ -480,13 +526,13 @@ impl InputSystem
                 {
                     //println!();
                     //println!("[{}] {}", index, mapping.name);
-                    println!("\tCustom: {}", custom.make_str());
-                    println!("\tDefault: {}", mapping.default.make_str())
+                    console.print(format!("\tCustom: {}", custom.make_str()));
+                    console.print(format!("\tDefault: {}", mapping.default.make_str()))
                 }
                 None => 
                 {
                     //println!("\t'{}' with default '{}'.", mapping.name, mapping.default.make_str())
-                    println!("\tDefault: {}", mapping.default.make_str())
+                    console.print(format!("\tDefault: {}", mapping.default.make_str()))
                 }
             };            
         }
@@ -502,38 +548,206 @@ impl InputSystem
             let mut mapping = mapping.write().unwrap();
             mapping.custom = None;
         }
-        println!("All mappings set to default.");
+        info!("input", "All mappings set to default.");
+    }
+
+    /// Replaces the command of a key that is already bound.
+    pub fn bind
+    (
+        &mut self,
+        key: MethodKM,
+        command: &str
+    )
+    {
+        self.binds.retain(|bind| bind.key as usize != key as usize);
+        self.binds.push(Bind { key, command: command.to_string() });
+    }
+
+    pub fn unbind
+    (
+        &mut self,
+        key: MethodKM
+    )
+    -> bool
+    {
+        let count = self.binds.len();
+        self.binds.retain(|bind| bind.key as usize != key as usize);
+        self.binds.len() != count
+    }
+
+    /// Issues the commands of pressed keys, not while typing.
+    /// Keys are checked once, a mapping of the same key does not see the press when checked later.
+    pub fn update_binds
+    (
+        &mut self,
+        framework: &mut Framework
+    )
+    {
+        if let Mode::Normal = self.mode
+        {
+            for bind in &self.binds
+            {
+                if Self::check_once_signal(&mut self.signals_km[bind.key as usize])
+                {
+                    framework.command(&bind.command);
+                }
+            }
+        }
+    }
+
+    fn print_binds
+    (
+        &self,
+        console: &mut Console
+    )
+    {
+        console.print("Binds:");
+        for bind in &self.binds
+        {
+            console.print(format!("\t{:<12}{}", bind.key.make_str(), bind.command));
+        }
     }
 }
 
 impl SystemEvents for InputSystem
 {
+    fn name
+    (
+        &self
+    )
+    -> &'static str
+    {
+        "input"
+    }
+
+    fn commands
+    (
+        &self
+    )
+    -> Vec<ConsoleCommand>
+    {
+        vec!
+        [
+            ConsoleCommand::new("imaps", Vec::new()).describe("Lists the input mappings."),
+            ConsoleCommand::new("ibind", vec![ConsoleParameterKind::Integer, ConsoleParameterKind::String]).describe("Binds an input mapping, by its number in imaps, to a key."),
+            ConsoleCommand::new("bind", vec![ConsoleParameterKind::String]).optional(vec![ConsoleParameterKind::String]).describe("Issues a command when a key is pressed, prints the command of the key without one."),
+            ConsoleCommand::new("unbind", vec![ConsoleParameterKind::String]).describe("Removes the command of a key."),
+            ConsoleCommand::new("binds", Vec::new()).describe("Lists the keys with commands.")
+        ]
+    }
+
     fn console
     (
         &mut self,
-        framework: &mut Framework
+        framework: &mut Framework,
+        command: &ConsoleCommand
     )
     {
-        let command = ConsoleCommand::new("imaps", Vec::new());        
-        if framework.command_event() == &command
+        match command.keyword()
         {
-            self.print_mappings();
-        }
+            "imaps" => self.print_mappings(framework.console()),
+            "ibind" =>
+            {
+                let index = command.get::<i32>(0).unwrap();
+                let name = command.get::<String>(1).unwrap();
+                let key = match MethodKM::from_name(&name)
+                {
+                    Some(key) => key,
+                    None =>
+                    {
+                        framework.console().print(format!("There is no key '{}'.", name));
+                        return;
+                    }
+                };
 
-        let command = ConsoleCommand::new("ibind", vec![ConsoleCommandParameter::U32]);
-        if framework.command_event() == &command
-        {
-            unimplemented!();
+                match usize::try_from(index).ok().and_then(|index| self.mappings.all().get(index))
+                {
+                    Some(mapping) => mapping.write().unwrap().bind_custom(key),
+                    None => framework.console().print(format!("There is no input mapping {}.", index))
+                }
+            }
+            "bind" | "unbind" =>
+            {
+                let name = command.get::<String>(0).unwrap();
+                let key = match MethodKM::from_name(&name)
+                {
+                    Some(key) => key,
+                    None =>
+                    {
+                        framework.console().print(format!("There is no key '{}'.", name));
+                        return;
+                    }
+                };
+
+                match (command.keyword(), command.get::<String>(1))
+                {
+                    ("bind", Some(bound)) =>
+                    {
+                        self.bind(key, &bound);
+                        if framework.cvars_mut().settings_mut().set_bind(key.make_str(), Some(&bound))
+                        {
+                            framework.cvars_mut().save_settings();
+                        }
+                    }
+                    ("bind", None) =>
+                    {
+                        let line = match self.binds.iter().find(|bind| bind.key as usize == key as usize)
+                        {
+                            Some(bind) => format!("{} = {}", key.make_str(), ConsoleCommandParameter::from(bind.command.as_str())),
+                            None => format!("'{}' is not bound.", key.make_str())
+                        };
+                        framework.console().print(line);
+                    }
+                    _ =>
+                    {
+                        if !self.unbind(key)
+                        {
+                            framework.console().print(format!("'{}' is not bound.", key.make_str()));
+                        }
+                        else if framework.cvars_mut().settings_mut().set_bind(key.make_str(), None)
+                        {
+                            framework.cvars_mut().save_settings();
+                        }
+                    }
+                }
+            }
+            "binds" => self.print_binds(framework.console()),
+            _ => ()
         }
     }
 
+    /// Mappings are loaded in place, so handles held by other systems stay valid.
     fn save_load
     (
         &mut self,
-        framework: &mut crate::Framework
+        _framework: &mut Framework,
+        section: SaveSection
     )
+    -> Result<(), SerializeError>
     {
-        println!("Saved.")    
+        match section
+        {
+            // Binds and aliases are kept in the settings of the user only, loading a game leaves them as they are.
+            SaveSection::Save(serializer) =>
+            {
+                serializer.field("mappings", &self.mappings);
+                Ok(())
+            }
+            SaveSection::Load(deserializer, staged) =>
+            {
+                let mappings: StorageEntries<Mapping> = deserializer.field("mappings")?;
+                staged.stage(mappings);
+                Ok(())
+            }
+            SaveSection::Commit(staged) =>
+            {
+                if let Some(mappings) = staged.take::<StorageEntries<Mapping>>()
+                {
+                    self.mappings.overwrite(mappings);
+                }
+                Ok(())
+            }
+        }
     }
 }
 
@@ -578,6 +792,50 @@ impl Mapping
     }
 }
 
+impl Serialize for Mapping
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("name", &self.name);
+        serializer.field("default", &self.default);
+        serializer.field("custom", &self.custom);
+        serializer.end();
+    }
+}
+
+impl Deserialize for Mapping
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Mapping, SerializeError>
+    {
+        deserializer.begin()?;
+        let mapping = Mapping
+        {
+            name: deserializer.field("name")?,
+            default: deserializer.field("default")?,
+            custom: deserializer.field("custom")?
+        };
+        deserializer.end()?;
+        Ok(mapping)
+    }
+}
+
+/// Console command issued when a key is pressed.
+#[derive(Clone)]
+pub struct Bind
+{
+    key: MethodKM,
+    command: String
+}
+
 pub enum Mode
 {
     Normal,
@@ -587,7 +845,7 @@ pub enum Mode
     HotKey
 }
 
-const MAX_SIGNAL_SLOTS_KM: usize = 58;
+const MAX_SIGNAL_SLOTS_KM: usize = 60;
 
 /// Input bindings for the mouse and keyboard input method.
 enum_str!
@@ -602,6 +860,8 @@ enum_str!
         Enter,
         ShiftLeft,
         ShiftRight,
+        ArrowUp,
+        ArrowDown,
 
         F1,
         F2,
@@ -660,7 +920,78 @@ enum_str!
     }
 }
 
-const MAX_SIGNAL_SLOTS_GP: usize = 4;
+impl MethodKM
+{
+    /// Also accepts names in lower case and digits for the number keys.
+    pub fn from_name
+    (
+        name: &str
+    )
+    -> Option<MethodKM>
+    {
+        let mut capitalized = name.to_lowercase();
+        if let Some(first) = capitalized.get_mut(0..1)
+        {
+            first.make_ascii_uppercase();
+        }
+
+        MethodKM::make_from_str(name)
+            .or_else(|| MethodKM::make_from_str(&name.to_uppercase()))
+            .or_else(|| MethodKM::make_from_str(&capitalized))
+            .or_else(|| MethodKM::make_from_str(&format!("Key{}", name)))
+    }
+}
+
+/// Written by name so saved mappings survive reordering of the variants.
+impl Serialize for MethodKM
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        self.make_str().serialize(serializer);
+    }
+}
+
+impl Deserialize for MethodKM
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<MethodKM, SerializeError>
+    {
+        let name = String::deserialize(deserializer)?;
+        MethodKM::make_from_str(&name).ok_or(SerializeError::Parse { label: "MethodKM".to_string(), value: name })
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError
+{
+    /// Code of the virtual key.
+    UnknownKey(u32),
+    UnknownButton(u32)
+}
+
+impl fmt::Display for InputError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            InputError::UnknownKey(code) => write!(f, "Virtual key code can not be converted: {}", code),
+            InputError::UnknownButton(button) => write!(f, "Button ID can not be converted: {}", button)
+        }
+    }
+}
 
 /// Input bindings for the gamepad input method.
 pub enum MethodGP // todo List needmappings finishing.
@@ -679,4 +1010,38 @@ pub enum Signal
 {
     Inactive,
     Active(f32, CheckedOnce)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use winit::event::{KeyboardInput, ModifiersState};
+
+    #[allow(deprecated)]
+    fn key
+    (
+        code: VirtualKeyCode
+    )
+    -> DeviceEvent
+    {
+        DeviceEvent::Key(KeyboardInput { scancode: 0, state: ElementState::Pressed, virtual_keycode: Some(code), modifiers: ModifiersState::empty() })
+    }
+
+    #[test]
+    fn unknown_input
+    ()
+    {
+        let mut input = InputSystem::new();
+        assert!(input.register_device_events(&key(VirtualKeyCode::A)).is_ok());
+        assert!(input.register_device_events(&DeviceEvent::Button { button: 1, state: ElementState::Pressed }).is_ok());
+
+        let err = input.register_device_events(&key(VirtualKeyCode::Numpad0)).err().unwrap();
+        assert!(matches!(err, InputError::UnknownKey(code) if code == VirtualKeyCode::Numpad0 as u32));
+        assert_eq!(err.to_string(), format!("Virtual key code can not be converted: {}", VirtualKeyCode::Numpad0 as u32));
+
+        let err = input.register_device_events(&DeviceEvent::Button { button: 9, state: ElementState::Pressed }).err().unwrap();
+        assert!(matches!(err, InputError::UnknownButton(9)));
+        assert_eq!(err.to_string(), "Button ID can not be converted: 9");
+    }
 }
\ No newline at end of file
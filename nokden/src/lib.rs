@@ -1,42 +1,120 @@
+#[macro_use]
+pub mod logger;
 pub mod defaults;
 pub mod input;
 pub mod widgets;
 pub mod graphics;
-
-use std::{env, fs};
+pub mod serialize;
+pub mod save;
+pub mod console;
+pub mod cvars;
+pub mod settings;
+pub mod arguments;
+pub mod script;
+pub mod remote;
+pub mod crash;
+pub mod vfs;
+pub mod archive;
+
+use std::{env, mem};
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::path::{PathBuf, Path};
-use std::time::Instant;
-use std::sync::{Arc, RwLock, RwLockWriteGuard, RwLockReadGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard, RwLockReadGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
 use winit::window::{Window, WindowBuilder};
 use winit::event::{WindowEvent, DeviceEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use nalgebra::{self, Isometry3, Vector3, Point3, Translation3};
+use rayon::prelude::*;
+use serialize::{Serialize, Deserialize, DeserializeInPlace, Serializer, Deserializer, SerializeError};
+use save::{SaveGames, SaveSection, QUICKSAVE_SLOT};
+use settings::{Settings, SETTINGS_FILE};
+use arguments::{Arguments, USAGE};
+use script::{Script, AUTOEXEC_FILE};
+use remote::RemoteConsole;
+use graphics::GraphicsError;
+use vfs::Vfs;
+pub use console::{split_commands, Console, ConsoleCommand, ConsoleCommandParameter, ConsoleParameterKind, ConsoleCompletion, ConsoleError, ConsoleCommands, ConsoleHandler, FromConsoleParameter};
+pub use cvars::{Cvar, Cvars};
 
 pub fn run
 <
-    TApplication: ApplicationEvents + 'static
+    TApplication: ApplicationEvents + Systems + 'static
 >
 ()
+{
+    let arguments = match Arguments::from_env()
+    {
+        Ok(arguments) => arguments,
+        Err(err) =>
+        {
+            error!("framework", "{}", err);
+            println!("{}", USAGE);
+            return;
+        }
+    };
+
+    if arguments.help
+    {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let log_directory = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)).unwrap_or_default();
+    match logger::open_file(&log_directory, TApplication::name())
+    {
+        Ok(path) => info!("framework", "Log:\t\t{}", path.to_string_lossy()),
+        Err(err) => warn!("framework", "Log file not opened: {}", err)
+    }
+
+    let versions = vec!
+    [
+        format!("Application:\t{} ({})", TApplication::name(), TApplication::version()),
+        format!("Engine:\t\t{} ({})", TApplication::engine_name(), TApplication::engine_version()),
+        format!("Framework:\t{} ({})", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ];
+    for version in &versions
+    {
+        info!("framework", "{}", version);
+    }
+    crash::install(&log_directory, TApplication::name(), versions);
+
+    match arguments.headless
+    {
+        true => run_headless::<TApplication>(arguments),
+        false => run_window::<TApplication>(arguments)
+    }
+}
+
+fn run_window
+<
+    TApplication: ApplicationEvents + Systems + 'static
+>
+(
+    arguments: Arguments
+)
 {
     let event_loop = EventLoop::new();
-    let console_deltaprint = ConsoleCommand::new("deltaprint", Vec::new());
-    let console_fpsprint = ConsoleCommand::new("fpsprint", Vec::new());
-    let console_quit = ConsoleCommand::new("quit", Vec::new());
-    let console_save = ConsoleCommand::new("save", Vec::new());
-    let console_load = ConsoleCommand::new("load", Vec::new());
     let mut device_events: Vec<DeviceEvent> = Vec::new();
     let mut window_events: Vec<WindowEvent> = Vec::new();    
 
-    println!("Application:\t{} ({})", TApplication::name(), TApplication::version());
-    println!("Engine:\t\t{} ({})", TApplication::engine_name(), TApplication::engine_version());
-    println!("Framework:\t{} ({})", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
-
-    let mut framework = Framework::new(&event_loop, TApplication::name());
-    let mut application = TApplication::new(&mut framework);
+    let started = Framework::new(Some(&event_loop), TApplication::name(), TApplication::version(), arguments)
+        .map_err(StartupError::from)
+        .and_then(|mut framework| start::<TApplication>(&mut framework).map(|application| (framework, application)));
+    let (mut framework, mut application) = match started
+    {
+        Ok(started) => started,
+        Err(err) =>
+        {
+            error!("framework", "Startup failed: {}", err);
+            return;
+        }
+    };
     
-    println!();
-    println!("Enter loop ...");
+    info!("framework", "Enter loop ...");
 
     framework.window_show();
 
@@ -57,45 +135,12 @@ pub fn run
                             *control_flow = ControlFlow::Exit
                         }
 
-                        if !framework.command.keyword.is_empty()
-                        {  
-                            if framework.command == console_fpsprint
-                            {
-                                framework.fps_print.toggle();
-                            }
-
-                            if framework.command == console_deltaprint
-                            {
-                                framework.delta_print.toggle();
-                            }                            
-
-                            if framework.command == console_quit
-                            {
-                                framework.shutdown();
-                            }
-
-                            if framework.command == console_save
-                            {
-                                framework.save_load(SaveLoad::Save)
-                            }
-
-                            if framework.command == console_load
-                            {
-                                framework.save_load(SaveLoad::Load)
-                            }
-
-                            application.console(&mut framework);
-                            framework.command.keyword.clear();
-                        }
-                                                
-                        application.update(&mut framework);                        
-                        application.update_engine(&device_events, &window_events);
-                        application.save_load(&mut framework);
+                        frame(&mut framework, &mut application, &device_events, &window_events);
                         device_events.clear();
                         window_events.clear();                        
-                        framework.frames.count();
-                        framework.fps.count(framework.fps_print);
-                        framework.frame_delta.reset(framework.delta_print);
+
+                        let max_fps = framework.cvars.get(defaults::MAX_FPS).unwrap_or(0.0);
+                        framework.frame_limiter.wait(max_fps);
                     }
                     ControlFlow::Exit => 
                     {
@@ -131,22 +176,224 @@ pub fn run
             }
             winit::event::Event::LoopDestroyed =>
             {
-                println!();
-                println!("... loop done. Engine shut down.");
+                info!("framework", "... loop done. Engine shut down.");
             }
             _ => ()
         }
     });
 }
 
+/// Updates on a timer of [defaults::HEADLESS_RATE] without a window, device events or graphics.
+fn run_headless
+<
+    TApplication: ApplicationEvents + Systems + 'static
+>
+(
+    arguments: Arguments
+)
+{
+    let started = Framework::headless(TApplication::name(), TApplication::version(), arguments)
+        .map_err(StartupError::from)
+        .and_then(|mut framework| start::<TApplication>(&mut framework).map(|application| (framework, application)));
+    let (mut framework, mut application) = match started
+    {
+        Ok(started) => started,
+        Err(err) =>
+        {
+            error!("framework", "Startup failed: {}", err);
+            return;
+        }
+    };
+
+    info!("framework", "Enter headless loop ...");
+
+    while framework.run
+    {
+        frame(&mut framework, &mut application, &Vec::new(), &Vec::new());
+
+        let rate = framework.cvars.get(defaults::HEADLESS_RATE).unwrap_or(60.0);
+        framework.frame_limiter.wait(rate);
+    }
+
+    info!("framework", "... loop done. Engine shut down.");
+}
+
+/// Creates the application and registers the commands of its systems.
+/// Queues the autoexec scripts and the commands of the command line to be issued by the first frame.
+pub fn start
+<
+    TApplication: ApplicationEvents + Systems
+>
+(
+    framework: &mut Framework
+)
+-> Result<TApplication, StartupError>
+{
+    let mut application = TApplication::new(framework)?;
+
+    application.systems(&mut |system|
+    {
+        let owner = system.name();
+        for declared in system.commands()
+        {
+            framework.commands.register(declared, ConsoleHandler::System(owner));
+        }
+    });
+
+    // Aliases and binds of the user, before the autoexecs so those can change them.
+    for command in framework.cvars.settings().commands()
+    {
+        framework.script.push(&command);
+    }
+
+    let autoexecs = [Some(framework.project_directory()), framework.user_directory()];
+    for autoexec in autoexecs.into_iter().flatten().map(|directory| directory.join(AUTOEXEC_FILE)).filter(|autoexec| autoexec.is_file())
+    {
+        framework.script.push(&format!("exec {}", ConsoleCommandParameter::from(autoexec.to_string_lossy().to_string())));
+    }
+
+    for command in framework.arguments.exec.clone()
+    {
+        framework.script.push(&command);
+    }
+
+    framework.remote_restart();
+    Ok(application)
+}
+
+/// Everything done once per frame: issued commands, changed cvars, the update of the application, saving and loading.
+/// Tests can drive a headless framework with this, see [Framework::headless].
+pub fn frame
+<
+    TApplication: ApplicationEvents + Systems
+>
+(
+    framework: &mut Framework,
+    application: &mut TApplication,
+    device_events: &[DeviceEvent],
+    window_events: &[WindowEvent]
+)
+{
+    framework.clock.advance(framework.frame_delta.delta(), framework.fixed_delta());
+    framework.log_update();
+    framework.remote_update();
+
+    // Commands of systems are handed out between the ones of the framework, so all run in the order issued.
+    if !framework.script.waiting()
+    {
+        while let Some(line) = framework.script.next()
+        {
+            if let Some((owner, command)) = framework.issue(&line)
+            {
+                application.systems(&mut |system|
+                {
+                    if system.name() == owner
+                    {
+                        system.console(framework, &command);
+                    }
+                });
+            }
+        }
+    }
+
+    let cvars_changed = framework.cvars.take_changed();
+    for name in &cvars_changed
+    {
+        if let Some(cvar) = framework.cvars.find(name).cloned()
+        {
+            application.systems(&mut |system| system.cvar_changed(framework, &cvar));
+        }
+    }
+
+    if !cvars_changed.is_empty()
+    {
+        framework.cvars.save_settings();
+    }
+
+    if cvars_changed.iter().any(|name| [defaults::RCON_ENABLED, defaults::RCON_ADDRESS, defaults::RCON_PASSWORD].contains(&name.as_str()))
+    {
+        framework.remote_restart();
+    }
+
+    application.update(framework);
+
+    let step_rate: f32 = framework.cvars.get(defaults::FIXED_RATE).unwrap_or(60.0);
+    let max_steps: u32 = framework.cvars.get(defaults::FIXED_MAX_STEPS).unwrap_or(8);
+    let steps = framework.fixed_step.advance(framework.clock.delta(), 1.0 / step_rate, max_steps);
+    for _ in 0..steps
+    {
+        application.update_fixed(framework);
+    }
+
+    application.update_engine(device_events, window_events, framework.fixed_step.alpha());
+
+    let (session, saving) = match mem::replace(&mut framework.save_load, SaveLoad::Idle)
+    {
+        SaveLoad::Idle => (None, false),
+        SaveLoad::Save(slot) => (Some(framework.saves.begin_save(&slot)), true),
+        SaveLoad::Load(slot) => (Some(framework.saves.begin_load(&slot)), false)
+    };
+
+    match session
+    {
+        Some(Ok(mut session)) =>
+        {
+            let slot = session.slot().to_string();
+            application.systems(&mut |system| session.system(system, framework));
+            application.systems(&mut |system| session.commit(system, framework));
+            match (session.finish(), saving)
+            {
+                (Ok(()), true) => framework.console.print(format!("Saved '{}'.", slot)),
+                (Ok(()), false) => framework.console.print(format!("Loaded '{}'.", slot)),
+                (Err(errors), _) =>
+                {
+                    framework.console.print(format!("{} '{}' failed:", if saving { "Save" } else { "Load" }, slot));
+                    for error in errors
+                    {
+                        framework.console.print(format!("\t{}", error));
+                    }
+                }
+            }
+        }
+        Some(Err(error)) => framework.console.print(format!("{} failed: {}", if saving { "Save" } else { "Load" }, error)),
+        None => ()
+    }
+
+    framework.frames.count();
+    if framework.arguments.frames.is_some_and(|frames| framework.frames.frames() >= frames)
+    {
+        framework.shutdown();
+    }
+    framework.fps.count(framework.cvars.get(defaults::FPS_PRINT).unwrap_or(false));
+    framework.frame_delta.reset(framework.cvars.get(defaults::DELTA_PRINT).unwrap_or(false));
+    framework.frame_times.record(framework.frame_delta.delta());
+}
+
+/// Keeps components behind handles.
+/// Removed slots are reused, and every reuse bumps the slot generation so old handles are detected as stale.
 pub struct Storage
 <
     TComponent
 >
 {
-    regsiter: Vec<Register>,
+    registers: Vec<Register>,
+
+    /// Register slots of removed entries that can be reused.
+    free: Vec<usize>,
     usage_count: Vec<u32>,
-    datas: Vec<Arc<RwLock<TComponent>>>
+
+    /// Register slot of each data entry.
+    owners: Vec<usize>,
+    datas: Vec<Arc<RwLock<TComponent>>>,
+
+    /// Tick of the last change of each data entry.
+    changed: Vec<AtomicU64>,
+
+    /// Counted by every change.
+    tick: u64,
+
+    /// Filled by cloned and dropped handles and processed on the next mutable access.
+    events: Arc<Mutex<Vec<HandleEvent>>>
 }
 
 impl
@@ -164,9 +411,14 @@ Storage
     {
         Storage
         {
-            regsiter: Vec::new(),
+            registers: Vec::new(),
+            free: Vec::new(),
+            usage_count: Vec::new(),
+            owners: Vec::new(),
             datas: Vec::new(),
-            usage_count: Vec::new()
+            changed: Vec::new(),
+            tick: 0,
+            events: Arc::new(Mutex::new(Vec::new()))
         }
     }
 
@@ -177,16 +429,36 @@ Storage
     )   
     -> Handle<TComponent> 
     {
+        self.release_dropped();
+
+        let data_index = self.datas.len();
+        let (index, generation) = match self.free.pop()
+        {
+            Some(index) =>
+            {
+                let generation = self.registers[index].info().generation;
+                self.registers[index] = Register::Enabled(RegisterInfo { data_index, generation });
+                self.usage_count[index] = 1;
+                (index, generation)
+            }
+            None =>
+            {
+                self.registers.push(Register::Enabled(RegisterInfo { data_index, generation: 0 }));
+                self.usage_count.push(1);
+                (self.registers.len() - 1, 0)
+            }
+        };
+
+        self.tick += 1;
         self.datas.push(Arc::new(RwLock::new(data)));
-        self.usage_count.push(1);
+        self.owners.push(index);
+        self.changed.push(AtomicU64::new(self.tick));
         
-        Handle
-        {
-            index: (self.datas.len()) - 1,
-            phantom: PhantomData::default()
-        }
+        self.handle(index, generation)
     }
 
+    /// Another handle to the same entry, the entry is kept until every handle is dropped or it is removed.
+    /// Duplicating a stale handle gives another stale handle.
     pub fn duplicate
     (
         &mut self,
@@ -194,596 +466,2502 @@ Storage
     )
     -> Handle<TComponent>        
     {
-        self.usage_count[handle.index] += 1;
-        Handle         
-        { 
-            index: handle.index,
-            phantom: PhantomData::default()
+        self.release_dropped();
+
+        if self.contains(handle)
+        {
+            self.usage_count[handle.index] += 1;
         }
+
+        self.handle(handle.index, handle.generation)
     }
 
+    /// Removes the entry right away, no matter how many handles still point to it.
+    /// Those handles become stale. Returns false if the handle was already stale.
     pub fn remove
     (
         &mut self,
         handle: Handle<TComponent>
     )
+    -> bool
     {
-        todo!()//self.datas.remove(handle.register_index);
+        self.release_dropped();
+
+        match self.contains(&handle)
+        {
+            true =>
+            {
+                self.release(handle.index);
+                true
+            }
+            false => false
+        }
     }
 
-    pub fn read
+    /// Whether the handle was made by this storage and still points to the entry it was made for.
+    pub fn contains
     (
         &self,
         handle: &Handle<TComponent>
     )
-    -> RwLockReadGuard<TComponent>
+    -> bool
     {
-        self.datas.get(handle.index).unwrap().read().unwrap()
+        match self.registers.get(handle.index)
+        {
+            Some(Register::Removed(_)) | None => false,
+            Some(register) => self.owns(handle) && register.info().generation == handle.generation
+        }
     }
 
-    pub fn write
+    /// Returns none if the handle is stale.
+    pub fn read
     (
-        &mut self,
+        &self,
         handle: &Handle<TComponent>
     )
-    -> RwLockWriteGuard<TComponent>
+    -> Option<RwLockReadGuard<TComponent>>
     {
-        self.datas.get(handle.index).unwrap().write().unwrap()
+        self.data_index(handle).map(|data_index| self.datas[data_index].read().unwrap())
     }
 
-    pub fn all
+    /// Marks the entry as changed.
+    /// Returns none if the handle is stale.
+    pub fn write
     (
-        &self
+        &mut self,
+        handle: &Handle<TComponent>
     )
-    -> &[Arc<RwLock<TComponent>>]
+    -> Option<RwLockWriteGuard<TComponent>>
     {
-        self.datas.as_slice()
+        let data_index = self.data_index(handle)?;
+        self.tick += 1;
+        self.changed[data_index].store(self.tick, Ordering::Relaxed);
+        Some(self.datas[data_index].write().unwrap())
     }
-}
-
-struct RegisterInfo
-{
-    data_index: u8,
-    
-    /// Counted when added and removed.
-    generation: u8
-}
 
-enum Register
-{
-    Disabled(RegisterInfo),
-    Enabled(RegisterInfo),
-    //Streaming,
-    Removed(RegisterInfo)
-}
-
-#[derive(Debug)]
-pub struct Handle
-<
-    TComponent
->
-{
-    index: usize,
-    phantom: PhantomData<TComponent>
-}
-
-pub enum SaveLoad
-{
-    Idle,
-    Load,
-    Save
-}
-
-pub struct Framework
-{
-    asset_path: PathBuf,
-    pub fps_print: bool,
-    fps: CPS,
-    frames: Frames,
-    pub delta_print: bool,
-    frame_delta: Delta,
-    window: Window, // TODO Needs to be moved to graphics core.
-    run: bool,
-    command: ConsoleCommand,
-    save_load: SaveLoad
-}
-
-impl Framework
-{
-    pub fn new
+    /// Needed after writing through the locks of [Storage::iter] or [Storage::all], which are not tracked.
+    /// Returns false if the handle is stale.
+    pub fn mark_changed
     (
-        event_loop: &EventLoop<()>,
-        app_name: &str
+        &mut self,
+        handle: &Handle<TComponent>
     )
-    -> Framework
+    -> bool
     {
-        let window =
-        {
-            // TODO Needs to be the OS set desktop resolution.
-            let (width, height) = (defaults::RESOLUTION_WIDTH, defaults::RESOLUTION_HEIGHT);
-            let window = WindowBuilder::new().build(&event_loop).unwrap();
-            window.set_visible(false);
-            window.set_title(app_name);
-            window.set_inner_size(winit::dpi::LogicalSize::new(f64::from(width), f64::from(height)));
-            window
-        };
-
-        let asset_path =
+        match self.data_index(handle)
         {
-            const DROP_FOLDERS: usize = 4;
-
-            let from_source = match env::current_exe().unwrap().iter().find(|item| *item == "target")
-            {
-                Some(_) => true,
-                None => false
-            };
-
-            let asset_path =
-            {
-                let mut source_directory_exe = env::current_exe().unwrap();
-                match from_source
-                {
-                    true => for _ in 0..DROP_FOLDERS { source_directory_exe.pop(); },
-                    false => { source_directory_exe.pop(); }
-                }
-                source_directory_exe.push("@main");
-                source_directory_exe.push("import");
-                source_directory_exe
-            };
-
-            if from_source
+            Some(data_index) =>
             {
-                let source_directory_exe = env::current_exe().unwrap();
-
-                let mut project_directory_exe = env::current_exe().unwrap();                
-                for _ in 0..DROP_FOLDERS { project_directory_exe.pop(); }
-                project_directory_exe.push(&env::current_exe().unwrap().components().last().unwrap().as_os_str().to_str().unwrap());
-                project_directory_exe.pop();
-                project_directory_exe.push(Path::new(format!("launch {}", app_name).as_str()));
-
-                match fs::copy(&source_directory_exe, &project_directory_exe)
-                {
-                    Ok(_) => println!("From source: {}", from_source),
-                    Err(err) =>
-                    {
-                        println!("App exe not copied: {}", err);
-                        println!("From: {}", &source_directory_exe.to_string_lossy());
-                        println!("To: {}", &project_directory_exe.to_string_lossy());
-                    }
-                }
+                self.tick += 1;
+                self.changed[data_index].store(self.tick, Ordering::Relaxed);
+                true
             }
-
-            asset_path
-        };
-
-        Framework
-        {
-            asset_path,
-            fps_print: false,
-            fps: CPS::new("Frames Per Second"),
-            frames: Frames::new(),
-            delta_print: false,
-            frame_delta: Delta::new(),
-            window,
-            run: true,
-            command: ConsoleCommand::cleared(),
-            save_load: SaveLoad::Idle
+            None => false
         }
     }
-    
-    pub fn shutdown
+
+    /// The tick of the latest change.
+    /// Keep it to later ask for everything that changed after it with [Storage::changed_since].
+    pub fn tick
     (
-        &mut self,
+        &self
     )
+    -> u64
     {
-        self.run = false;
+        self.tick
     }
 
-    /// Issue console command.
-    pub fn command
+    /// Whether the entry was added or written after [tick]. False if the handle is stale.
+    pub fn is_changed_since
     (
-        &mut self,
-        command: &str
+        &self,
+        handle: &Handle<TComponent>,
+        tick: u64
     )
+    -> bool
     {
-        let command_string = String::from(command);
-        let mut fragments: Vec<&str> = command_string.split(' ').collect();
-        let mut parameters: Vec<ConsoleCommandParameter> = Vec::new();
-
-        let keyword = fragments.remove(0);
-
-        for fragment in fragments
+        match self.data_index(handle)
         {
-            // TODO Once the if let is supported inside match by Rust then refactor instead of using else-if chains.
-            /*if let Some(value) = fragment.parse::<bool>().ok()
-            {
-                parameters.push(ConsoleCommandParameter::Bool(value));
-            }
-            else if let Some(value) = fragment.parse::<u32>().ok()
-            {
-                parameters.push(ConsoleCommandParameter::U32(value));
-            }
-            else if let Some(value) = fragment.parse::<f32>().ok()
-            {
-                parameters.push(ConsoleCommandParameter::F32(value));
-            }*/
-        }        
-
-        self.command = ConsoleCommand::new(keyword, parameters);
+            Some(data_index) => self.changed[data_index].load(Ordering::Relaxed) > tick,
+            None => false
+        }
     }
 
-    /// Get issued command event.
-    pub fn command_event
+    /// All live entries, enabled or disabled, added or written after [tick].
+    pub fn changed_since
     (
         &self,
+        tick: u64
     )
-    -> &ConsoleCommand
+    -> impl Iterator<Item = &Arc<RwLock<TComponent>>>
     {
-        &self.command
+        self.datas.iter()
+            .zip(self.changed.iter())
+            .filter(move |(_, changed)| changed.load(Ordering::Relaxed) > tick)
+            .map(|(data, _)| data)
     }
 
-    pub fn save_load
+    /// Disabled entries keep their data and handles but are skipped by [Storage::iter].
+    /// Returns false if the handle is stale.
+    pub fn disable
     (
         &mut self,
-        mode: SaveLoad
+        handle: &Handle<TComponent>
     )
+    -> bool
     {
-        self.save_load = mode;
+        self.release_dropped();
+
+        match self.contains(handle)
+        {
+            true =>
+            {
+                self.registers[handle.index] = Register::Disabled(self.registers[handle.index].info());
+                true
+            }
+            false => false
+        }
     }
 
-    /// Get issued save or load event.
-    pub fn save_load_event
+    /// Returns false if the handle is stale.
+    pub fn enable
     (
-        &self
+        &mut self,
+        handle: &Handle<TComponent>
     )
-    -> &SaveLoad
+    -> bool
     {
-        &self.save_load
+        self.release_dropped();
+
+        match self.contains(handle)
+        {
+            true =>
+            {
+                self.registers[handle.index] = Register::Enabled(self.registers[handle.index].info());
+                true
+            }
+            false => false
+        }
     }
 
-    pub fn window_show
+    pub fn is_enabled
     (
-        &mut self
+        &self,
+        handle: &Handle<TComponent>
     )
+    -> bool
     {
-        self.window.set_maximized(false);
-        self.window.set_visible(true);
+        match self.registers.get(handle.index)
+        {
+            Some(Register::Enabled(info)) => self.owns(handle) && info.generation == handle.generation,
+            _ => false
+        }
     }
 
-    pub fn window
+    /// All enabled entries, in no particular order.
+    pub fn iter
     (
         &self
     )
-    -> &Window
+    -> impl Iterator<Item = &Arc<RwLock<TComponent>>>
     {
-        &self.window
+        self.datas.iter()
+            .zip(self.owners.iter())
+            .filter(move |(_, owner)| matches!(self.registers[**owner], Register::Enabled(_)))
+            .map(|(data, _)| data)
     }
 
-    pub fn window_size
+    /// All live entries, enabled or disabled, in no particular order.
+    pub fn all
     (
         &self
     )
-    -> [u32; 2]
+    -> &[Arc<RwLock<TComponent>>]
     {
-        [self.window.inner_size().width, self.window.inner_size().height]
+        self.datas.as_slice()
     }
 
-    pub fn delta
+    /// Count of live entries.
+    pub fn len
     (
         &self
     )
-    -> f32
+    -> usize
     {
-        self.frame_delta.delta()
+        self.datas.len()
     }
 
-    pub fn asset_path
+    pub fn is_empty
     (
-        &self,
-        asset: &Path
+        &self
     )
-    -> AssetPath
+    -> bool
     {
-        let mut full_path = self.asset_path.clone();
-        full_path.push(asset);
-        AssetPath(full_path.clone())
+        self.datas.is_empty()
     }
-}
 
-pub enum ConsoleCommandParameter
-{
-    //Keyword(String),
-    Bool,
-    U32,
-    F32
-}
+    /// Counts handles cloned and dropped since the previous call and releases entries without handles left.
+    /// Already done by every mutable storage function, only needed when nothing else is called for a while.
+    pub fn release_dropped
+    (
+        &mut self
+    )
+    {
+        let events: Vec<HandleEvent> = self.events.lock().unwrap().drain(..).collect();
+        for event in events
+        {
+            let (index, generation) = match event
+            {
+                HandleEvent::Retained { index, generation } | HandleEvent::Released { index, generation } => (index, generation)
+            };
 
-pub struct ConsoleCommand
-{
-    keyword: String,
-    parameters: Vec<ConsoleCommandParameter>
-}
+            let alive = match self.registers.get(index)
+            {
+                Some(Register::Removed(_)) | None => false,
+                Some(register) => register.info().generation == generation
+            };
 
-impl ConsoleCommand
-{
-    pub fn new
+            if alive
+            {
+                match event
+                {
+                    HandleEvent::Retained { .. } => self.usage_count[index] += 1,
+                    HandleEvent::Released { .. } =>
+                    {
+                        self.usage_count[index] -= 1;
+                        if self.usage_count[index] == 0
+                        {
+                            self.release(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// A new handle for every live entry, keyed by register slot.
+    pub(crate) fn handles
     (
-        keyword: &str,
-        parameters: Vec<ConsoleCommandParameter>
-    )    
-    -> ConsoleCommand
+        &self
+    )
+    -> HashMap<usize, Handle<TComponent>>
     {
-        ConsoleCommand
+        let mut handles = HashMap::new();
+        for owner in &self.owners
         {
-            keyword: String::from(keyword),
-            parameters
+            let generation = self.registers[*owner].info().generation;
+            self.events.lock().unwrap().push(HandleEvent::Retained { index: *owner, generation });
+            handles.insert(*owner, self.handle(*owner, generation));
         }
-    }    
+        handles
+    }
 
-    fn cleared
-    ()
-    -> ConsoleCommand
+    /// Overwrites the entries that are still at the same register slot, for storages whose handles are held elsewhere and must stay valid.
+    /// Saved entries without a live counterpart are skipped.
+    pub fn overwrite
+    (
+        &mut self,
+        entries: StorageEntries<TComponent>
+    )
     {
-        ConsoleCommand
+        self.release_dropped();
+
+        for (index, enabled, data) in entries.entries
         {
-            keyword: String::new(),
-            parameters: Vec::new()
+            let info = match self.registers.get(index)
+            {
+                Some(Register::Removed(_)) | None => continue,
+                Some(register) => register.info()
+            };
+
+            self.tick += 1;
+            *self.datas[info.data_index].write().unwrap() = data;
+            self.changed[info.data_index].store(self.tick, Ordering::Relaxed);
+            self.registers[index] = match enabled
+            {
+                true => Register::Enabled(info),
+                false => Register::Disabled(info)
+            };
         }
     }
 
-    /// Get a parameter.
-    /// Returns none if there is no paramater or of the wrong type.
-    // TODO Perhaps getting can be replaced with one single generic trait based method that checks at compile time to.
-    pub fn get_bool
+    fn data_index
     (
         &self,
+        handle: &Handle<TComponent>
+    )
+    -> Option<usize>
+    {
+        match self.contains(handle)
+        {
+            true => Some(self.registers[handle.index].info().data_index),
+            false => None
+        }
+    }
+
+    /// Frees the register slot and moves the last data entry into the gap.
+    fn release
+    (
+        &mut self,
         index: usize
     )
-    -> Option<bool>
     {
-        /*if let ConsoleCommandParameter::Bool(value) = self.parameters.get(index).unwrap()
+        let info = self.registers[index].info();
+
+        self.datas.swap_remove(info.data_index);
+        self.owners.swap_remove(info.data_index);
+        self.changed.swap_remove(info.data_index);
+        if let Some(moved) = self.owners.get(info.data_index)
         {
-            Some(*value)
+            self.registers[*moved].info_mut().data_index = info.data_index;
         }
-        else
+
+        self.registers[index] = Register::Removed(RegisterInfo { data_index: 0, generation: info.generation.wrapping_add(1) });
+        self.usage_count[index] = 0;
+        self.free.push(index);
+    }
+
+    /// Lock and change tick of an enabled entry, borrowed from the storage instead of the handle.
+    fn enabled_lock
+    (
+        &self,
+        handle: &Handle<TComponent>
+    )
+    -> Option<(&Arc<RwLock<TComponent>>, &AtomicU64)>
+    {
+        match self.is_enabled(handle)
         {
-            None
-        }*/
-        unimplemented!()
+            true =>
+            {
+                let data_index = self.registers[handle.index].info().data_index;
+                Some((&self.datas[data_index], &self.changed[data_index]))
+            }
+            false => None
+        }
     }
-}
 
-impl PartialEq for ConsoleCommand
-{
-    fn eq
+    /// Handles share the event queue of the storage that made them, which tells storages of the same type apart.
+    fn owns
     (
         &self,
-        other: &Self
+        handle: &Handle<TComponent>
     )
     -> bool
     {
-        const INFO_EVENT: &str = "info";
+        Arc::ptr_eq(&self.events, &handle.events)
+    }
 
-        if self.keyword == INFO_EVENT || other.keyword == INFO_EVENT
-        {
-            println!("\t{}", other.keyword);
-            false
-        }
-        else
+    fn handle
+    (
+        &self,
+        index: usize,
+        generation: u32
+    )
+    -> Handle<TComponent>
+    {
+        Handle
         {
-            self.keyword == other.keyword
+            index,
+            generation,
+            events: self.events.clone(),
+            phantom: PhantomData::default()
         }
     }
 }
 
-pub struct AssetPath(pub PathBuf);
-
-pub trait ApplicationEvents
+impl
+<
+    TComponent: Send + Sync
+>
+Storage
+<
+    TComponent
+>
 {
-    fn name
-    ()
-    -> &'static str;
-
-    fn version
-    ()
-    -> &'static str;
-    
-    fn engine_name
-    ()
-    -> &'static str;
-
-    fn engine_version
-    ()
-    -> &'static str;
-
-    fn new
+    /// Runs [op] for every enabled entry across the rayon thread pool.
+    pub fn par_for_each
+    <
+        TOp: Fn(&TComponent) + Send + Sync
+    >
     (
-        framework: &mut Framework
+        &self,
+        op: TOp
     )
-    -> Self;
-    
-    fn update
-    (
-        &mut self,
-        framework: &mut Framework
-    );
-    
-    fn update_engine
-    (
-        &mut self,
-        device_event: &Vec<DeviceEvent>,
-        window_event: &Vec<WindowEvent>
-    );
+    {
+        self.par_enabled().for_each(|(data, _)| op(&data.read().unwrap()));
+    }
 
-    fn console
+    /// Runs [op] for every enabled entry across the rayon thread pool with write access.
+    pub fn par_for_each_mut
+    <
+        TOp: Fn(&mut TComponent) + Send + Sync
+    >
     (
         &mut self,
-        framework: &mut Framework
-    );
+        op: TOp
+    )
+    {
+        self.release_dropped();
+        self.tick += 1;
+        let tick = self.tick;
+        self.par_enabled().for_each
+        (
+            |(data, changed)|
+            {
+                changed.store(tick, Ordering::Relaxed);
+                op(&mut data.write().unwrap());
+            }
+        );
+    }
 
-    fn save_load
+    /// Joins every enabled entry with the entry of [other] its [link] handle points to, for instance transforms with velocities.
+    /// Entries with a stale or disabled link are skipped.
+    pub fn par_join
+    <
+        TOther: Send + Sync,
+        TLink: Fn(&TComponent) -> &Handle<TOther> + Send + Sync,
+        TOp: Fn(&mut TComponent, &TOther) + Send + Sync
+    >
     (
         &mut self,
-        framework: &mut Framework
-    );
-}
+        other: &Storage<TOther>,
+        link: TLink,
+        op: TOp
+    )
+    {
+        self.release_dropped();
+        self.tick += 1;
+        let tick = self.tick;
+        self.par_enabled().for_each
+        (
+            |(data, changed)|
+            {
+                let mut data = data.write().unwrap();
+                if let Some((other, _)) = other.enabled_lock(link(&data))
+                {
+                    changed.store(tick, Ordering::Relaxed);
+                    op(&mut data, &other.read().unwrap());
+                }
+            }
+        );
+    }
 
-pub trait SystemEvents
-{
-    fn console
+    /// Same as [Storage::par_join] but with write access to [other].
+    /// Entries linking to the same entry of [other] wait for each other.
+    pub fn par_join_mut
+    <
+        TOther: Send + Sync,
+        TLink: Fn(&TComponent) -> &Handle<TOther> + Send + Sync,
+        TOp: Fn(&mut TComponent, &mut TOther) + Send + Sync
+    >
     (
         &mut self,
-        framework: &mut Framework
-    );
+        other: &mut Storage<TOther>,
+        link: TLink,
+        op: TOp
+    )
+    {
+        self.release_dropped();
+        self.tick += 1;
+        let tick = self.tick;
+        other.release_dropped();
+        other.tick += 1;
+        let other_tick = other.tick;
+        let other = &*other;
+        self.par_enabled().for_each
+        (
+            |(data, changed)|
+            {
+                let mut data = data.write().unwrap();
+                if let Some((other, other_changed)) = other.enabled_lock(link(&data))
+                {
+                    changed.store(tick, Ordering::Relaxed);
+                    other_changed.store(other_tick, Ordering::Relaxed);
+                    op(&mut data, &mut other.write().unwrap());
+                }
+            }
+        );
+    }
 
-    fn save_load
+    /// Joins every enabled entry with the entries of two other storages its [link] handles point to.
+    pub fn par_join3
+    <
+        TFirst: Send + Sync,
+        TSecond: Send + Sync,
+        TLink: Fn(&TComponent) -> (&Handle<TFirst>, &Handle<TSecond>) + Send + Sync,
+        TOp: Fn(&mut TComponent, &TFirst, &TSecond) + Send + Sync
+    >
     (
         &mut self,
-        framework: &mut Framework
-    );
-}
+        first: &Storage<TFirst>,
+        second: &Storage<TSecond>,
+        link: TLink,
+        op: TOp
+    )
+    {
+        self.release_dropped();
+        self.tick += 1;
+        let tick = self.tick;
+        self.par_enabled().for_each
+        (
+            |(data, changed)|
+            {
+                let mut data = data.write().unwrap();
+                let (first_handle, second_handle) = link(&data);
+                if let (Some((first, _)), Some((second, _))) = (first.enabled_lock(first_handle), second.enabled_lock(second_handle))
+                {
+                    changed.store(tick, Ordering::Relaxed);
+                    op(&mut data, &first.read().unwrap(), &second.read().unwrap());
+                }
+            }
+        );
+    }
 
-/// Counts Per Second.
-/// For instance this can be used to count frames per second.
-pub struct CPS
-{
-    label: String,
-    current: u32,
-    per_second: Instant,
-    counts: u32
+    fn par_enabled
+    (
+        &self
+    )
+    -> impl ParallelIterator<Item = (&Arc<RwLock<TComponent>>, &AtomicU64)>
+    {
+        let registers = &self.registers;
+        self.datas.par_iter()
+            .zip(self.changed.par_iter())
+            .zip(self.owners.par_iter())
+            .filter(move |(_, owner)| matches!(registers[**owner], Register::Enabled(_)))
+            .map(|(data, _)| data)
+    }
 }
 
-impl CPS
+/// Writes every live entry with its register slot, so handles written after it can be remapped on load.
+impl
+<
+    TComponent: Serialize
+>
+Serialize for Storage
+<
+    TComponent
+>
 {
-    pub fn new
+    fn serialize
     (
-        name: &str
+        &self,
+        serializer: &mut Serializer
     )
-    -> CPS
     {
-        CPS
+        serializer.begin();
+        serializer.field("count", &self.datas.len());
+        for (data, owner) in self.datas.iter().zip(self.owners.iter())
         {
-            label: name.to_string(),
-            current: 0,
-            per_second: Instant::now(),
-            counts: 0
+            serializer.label("entry");
+            serializer.begin();
+            serializer.field("index", owner);
+            serializer.field("enabled", &matches!(self.registers[*owner], Register::Enabled(_)));
+            serializer.field("data", &*data.read().unwrap());
+            serializer.end();
         }
+        serializer.end();
     }
+}
 
-    pub fn count
+/// Loads into a new storage and remaps handles loaded afterwards on the same deserializer to the new entries.
+impl
+<
+    TComponent: Deserialize + 'static
+>
+Deserialize for Storage
+<
+    TComponent
+>
+{
+    fn deserialize
     (
-        &mut self,
-        print: bool
+        deserializer: &mut Deserializer
     )
+    -> Result<Storage<TComponent>, SerializeError>
     {
-        self.refresh_per_second(print);
-        self.counts += 1;
+        let mut storage = Storage::new();
+        let mut remap = HashMap::new();
+
+        deserializer.begin()?;
+        let count: usize = deserializer.field("count")?;
+        for _ in 0..count
+        {
+            deserializer.label("entry");
+            deserializer.begin()?;
+            let index: usize = deserializer.field("index")?;
+            let enabled: bool = deserializer.field("enabled")?;
+            let handle = storage.add(deserializer.field("data")?);
+            if !enabled
+            {
+                storage.disable(&handle);
+            }
+            remap.insert(index, handle);
+            deserializer.end()?;
+        }
+        deserializer.end()?;
+
+        deserializer.remap(remap);
+        Ok(storage)
     }
+}
 
-    pub fn current
-    (
-        &mut self
-    )
-    -> u32
+/// Saved entries of a storage read without touching it, written by [Storage::overwrite].
+/// Lets a load read everything before anything is replaced.
+pub struct StorageEntries
+<
+    TComponent
+>
+{
+    /// Register slot, enabled and data of each entry.
+    entries: Vec<(usize, bool, TComponent)>
+}
+
+impl
+<
+    TComponent: Deserialize
+>
+Deserialize for StorageEntries
+<
+    TComponent
+>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<StorageEntries<TComponent>, SerializeError>
+    {
+        deserializer.begin()?;
+        let count: usize = deserializer.field("count")?;
+        let mut entries = Vec::new();
+        for _ in 0..count
+        {
+            deserializer.label("entry");
+            deserializer.begin()?;
+            let index: usize = deserializer.field("index")?;
+            let enabled: bool = deserializer.field("enabled")?;
+            let data: TComponent = deserializer.field("data")?;
+            deserializer.end()?;
+            entries.push((index, enabled, data));
+        }
+        deserializer.end()?;
+
+        Ok(StorageEntries { entries })
+    }
+}
+
+/// A key for every live entry of a storage, such as the path an asset was loaded from, with its register slot.
+/// Saved in place of a storage that is rebuilt on every start, possibly in another order, see [Deserializer::bind_storage_by_key].
+pub struct StorageKeys
+<
+    TKey
+>
+{
+    keys: Vec<(usize, TKey)>
+}
+
+impl
+<
+    TKey
+>
+StorageKeys
+<
+    TKey
+>
+{
+    pub fn new
+    <
+        TComponent
+    >
+    (
+        storage: &Storage<TComponent>,
+        key: impl Fn(&TComponent) -> TKey
+    )
+    -> StorageKeys<TKey>
+    {
+        let keys = storage.datas.iter()
+            .zip(storage.owners.iter())
+            .map(|(data, owner)| (*owner, key(&data.read().unwrap())))
+            .collect();
+        StorageKeys { keys }
+    }
+
+    pub(crate) fn keys
+    (
+        &self
+    )
+    -> &[(usize, TKey)]
+    {
+        &self.keys
+    }
+}
+
+impl
+<
+    TKey: Serialize
+>
+Serialize for StorageKeys
+<
+    TKey
+>
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("count", &self.keys.len());
+        for (index, key) in &self.keys
+        {
+            serializer.label("entry");
+            serializer.begin();
+            serializer.field("index", index);
+            serializer.field("key", key);
+            serializer.end();
+        }
+        serializer.end();
+    }
+}
+
+impl
+<
+    TKey: Deserialize
+>
+Deserialize for StorageKeys
+<
+    TKey
+>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<StorageKeys<TKey>, SerializeError>
+    {
+        deserializer.begin()?;
+        let count: usize = deserializer.field("count")?;
+        let mut keys = Vec::new();
+        for _ in 0..count
+        {
+            deserializer.label("entry");
+            deserializer.begin()?;
+            let index: usize = deserializer.field("index")?;
+            let key: TKey = deserializer.field("key")?;
+            deserializer.end()?;
+            keys.push((index, key));
+        }
+        deserializer.end()?;
+
+        Ok(StorageKeys { keys })
+    }
+}
+
+/// Same as [Storage::overwrite] with the entries read right away.
+impl
+<
+    TComponent: Deserialize
+>
+DeserializeInPlace for Storage
+<
+    TComponent
+>
+{
+    fn deserialize_in_place
+    (
+        &mut self,
+        deserializer: &mut Deserializer
+    )
+    -> Result<(), SerializeError>
+    {
+        let entries = StorageEntries::deserialize(deserializer)?;
+        self.overwrite(entries);
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+struct RegisterInfo
+{
+    data_index: usize,
+    
+    /// Counted when removed.
+    generation: u32
+}
+
+enum Register
+{
+    Disabled(RegisterInfo),
+    Enabled(RegisterInfo),
+    //Streaming,
+    Removed(RegisterInfo)
+}
+
+impl Register
+{
+    fn info
+    (
+        &self
+    )
+    -> RegisterInfo
+    {
+        match self
+        {
+            Register::Disabled(info) | Register::Enabled(info) | Register::Removed(info) => *info
+        }
+    }
+
+    fn info_mut
+    (
+        &mut self
+    )
+    -> &mut RegisterInfo
+    {
+        match self
+        {
+            Register::Disabled(info) | Register::Enabled(info) | Register::Removed(info) => info
+        }
+    }
+}
+
+/// A cloned or dropped handle waiting to be counted by its storage.
+#[derive(Debug)]
+enum HandleEvent
+{
+    Retained
+    {
+        index: usize,
+        generation: u32
+    },
+    Released
+    {
+        index: usize,
+        generation: u32
+    }
+}
+
+/// Points to an entry of a [Storage].
+/// Dropping the last handle of an entry releases it.
+#[derive(Debug)]
+pub struct Handle
+<
+    TComponent
+>
+{
+    index: usize,
+    generation: u32,
+    events: Arc<Mutex<Vec<HandleEvent>>>,
+    phantom: PhantomData<TComponent>
+}
+
+/// Same as [Storage::duplicate], but the new handle is only counted on the next mutable access of the storage.
+impl
+<
+    TComponent
+>
+Clone for Handle
+<
+    TComponent
+>
+{
+    fn clone
+    (
+        &self
+    )
+    -> Handle<TComponent>
+    {
+        if let Ok(mut events) = self.events.lock()
+        {
+            events.push(HandleEvent::Retained { index: self.index, generation: self.generation });
+        }
+
+        Handle
+        {
+            index: self.index,
+            generation: self.generation,
+            events: self.events.clone(),
+            phantom: PhantomData::default()
+        }
+    }
+}
+
+impl
+<
+    TComponent
+>
+Drop for Handle
+<
+    TComponent
+>
+{
+    fn drop
+    (
+        &mut self
+    )
+    {
+        if let Ok(mut events) = self.events.lock()
+        {
+            events.push(HandleEvent::Released { index: self.index, generation: self.generation });
+        }
+    }
+}
+
+pub enum SaveLoad
+{
+    Idle,
+
+    /// Slot name.
+    Load(String),
+
+    /// Slot name.
+    Save(String)
+}
+
+#[derive(Debug)]
+pub enum FrameworkError
+{
+    /// The path of the executable is needed to find assets and saves.
+    Exe(std::io::Error),
+    Window(winit::error::OsError)
+}
+
+impl std::fmt::Display for FrameworkError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut std::fmt::Formatter
+    )
+    -> std::fmt::Result
+    {
+        match self
+        {
+            FrameworkError::Exe(err) => write!(f, "Path of the executable unknown: {}", err),
+            FrameworkError::Window(err) => write!(f, "Window not created: {}", err)
+        }
+    }
+}
+
+/// Why [ApplicationEvents::new] or the framework before it failed.
+#[derive(Debug)]
+pub enum StartupError
+{
+    Framework(FrameworkError),
+    Graphics(GraphicsError),
+
+    /// Anything else the application needs, described.
+    Application(String)
+}
+
+impl std::fmt::Display for StartupError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut std::fmt::Formatter
+    )
+    -> std::fmt::Result
+    {
+        match self
+        {
+            StartupError::Framework(err) => write!(f, "{}", err),
+            StartupError::Graphics(err) => write!(f, "Graphics: {}", err),
+            StartupError::Application(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl From<FrameworkError> for StartupError
+{
+    fn from
+    (
+        err: FrameworkError
+    )
+    -> StartupError
+    {
+        StartupError::Framework(err)
+    }
+}
+
+impl From<GraphicsError> for StartupError
+{
+    fn from
+    (
+        err: GraphicsError
+    )
+    -> StartupError
+    {
+        StartupError::Graphics(err)
+    }
+}
+
+pub struct Framework
+{
+    vfs: Vfs,
+    project_directory: PathBuf,
+    user_directory: Option<PathBuf>,
+    fps: CPS,
+    frames: Frames,
+    frame_delta: Delta,
+    fixed_step: FixedStep,
+    clock: Clock,
+    frame_limiter: FrameLimiter,
+    frame_times: FrameTimes,
+
+    /// [None] when headless.
+    window: Option<Window>, // TODO Needs to be moved to graphics core.
+    run: bool,
+    commands: ConsoleCommands,
+    cvars: Cvars,
+    save_load: SaveLoad,
+    saves: SaveGames,
+    arguments: Arguments,
+    script: Script,
+    console: Console,
+    remote: Option<RemoteConsole>
+}
+
+impl Framework
+{
+    /// Without an event loop there is no window, as with [Framework::headless].
+    pub fn new
+    (
+        event_loop: Option<&EventLoop<()>>,
+        app_name: &str,
+        app_version: &str,
+        arguments: Arguments
+    )
+    -> Result<Framework, FrameworkError>
+    {
+        let exe = env::current_exe().map_err(FrameworkError::Exe)?;
+
+        let exe_directory = exe.parent().map(Path::to_path_buf).unwrap_or_default();
+        let vfs = Vfs::standard(&exe_directory, arguments.asset_path.as_deref(), &arguments.mods);
+        let project_directory = vfs::project_directory(&exe_directory, arguments.asset_path.as_deref());
+        if vfs.mounts().is_empty()
+        {
+            warn!("framework", "No asset directory found, assets will not load.");
+        }
+
+        let user_directory = arguments.user_path.clone().or_else(|| Settings::user_directory(app_name));
+        let mut cvars =
+        {
+            let project_settings = project_directory.join(SETTINGS_FILE);
+            let user_settings = user_directory.as_ref().map(|directory| directory.join(SETTINGS_FILE));
+            Cvars::new(Settings::load(&project_settings, user_settings))
+        };
+        for cvar in defaults::cvars()
+        {
+            cvars.register(cvar);
+        }
+
+        let launch_overrides = [(defaults::RESOLUTION_WIDTH, arguments.width), (defaults::RESOLUTION_HEIGHT, arguments.height)];
+        for (name, value) in launch_overrides
+        {
+            if let Some(value) = value
+            {
+                if let Err(err) = cvars.set_transient(name, value)
+                {
+                    warn!("framework", "{}", err);
+                }
+            }
+        }
+
+        // Systems read the cvars when they are created, nothing needs to hear about these.
+        cvars.take_changed();
+
+        let window = match event_loop
+        {
+            Some(event_loop) =>
+            {
+                // TODO Needs to be the OS set desktop resolution.
+                let width: u32 = cvars.get(defaults::RESOLUTION_WIDTH).unwrap();
+                let height: u32 = cvars.get(defaults::RESOLUTION_HEIGHT).unwrap();
+                let window = WindowBuilder::new().build(event_loop).map_err(FrameworkError::Window)?;
+                window.set_visible(false);
+                window.set_title(app_name);
+                window.set_inner_size(winit::dpi::LogicalSize::new(f64::from(width), f64::from(height)));
+                Some(window)
+            }
+            None => None
+        };
+
+        let saves =
+        {
+            SaveGames::new(exe_directory.join("saves"), app_name, app_version)
+        };
+
+        Ok(Framework
+        {
+            vfs,
+            project_directory,
+            user_directory,
+            fps: CPS::new("Frames Per Second"),
+            frames: Frames::new(),
+            frame_delta: Delta::new(),
+            fixed_step: FixedStep::new(),
+            clock: Clock::new(),
+            frame_limiter: FrameLimiter::new(),
+            frame_times: FrameTimes::new(),
+            window,
+            run: true,
+            commands: Framework::engine_commands(),
+            cvars,
+            save_load: SaveLoad::Idle,
+            saves,
+            arguments,
+            script: Script::new(),
+            console: Console::new(),
+            remote: None
+        })
+    }
+    
+    /// No window and no display needed, for servers and tests.
+    /// Applications check [Framework::is_headless] to leave out graphics.
+    pub fn headless
+    (
+        app_name: &str,
+        app_version: &str,
+        arguments: Arguments
+    )
+    -> Result<Framework, FrameworkError>
+    {
+        Framework::new(None, app_name, app_version, Arguments { headless: true, ..arguments })
+    }
+
+    pub fn is_headless
+    (
+        &self
+    )
+    -> bool
+    {
+        self.window.is_none()
+    }
+
+    /// False after [Framework::shutdown], the frame in progress still finishes.
+    pub fn is_running
+    (
+        &self
+    )
+    -> bool
+    {
+        self.run
+    }
+
+    pub fn shutdown
+    (
+        &mut self,
+    )
+    {
+        self.run = false;
+    }
+
+    /// Issue console command, several can be separated by `;`.
+    /// Queued behind the commands issued before, the next frame runs them in order up to a `wait`.
+    pub fn command
+    (
+        &mut self,
+        command: &str
+    )
+    {
+        self.script.push(command);
+    }
+
+    /// Runs a single command of the framework or expands an alias, commands of systems are returned to hand out.
+    /// Prints why if it is unknown or its parameters do not fit.
+    fn issue
+    (
+        &mut self,
+        line: &str
+    )
+    -> Option<(&'static str, ConsoleCommand)>
+    {
+        let command = match ConsoleCommand::parse(line)
+        {
+            Ok(command) => command,
+            Err(err) =>
+            {
+                self.console.print(err.to_string());
+                return None;
+            }
+        };
+
+        if command.keyword.is_empty()
+        {
+            return None;
+        }
+        crash::command(line.trim());
+
+        match self.commands.resolve(&command)
+        {
+            Ok(ConsoleHandler::Framework(handler)) => handler(self, &command),
+            Ok(ConsoleHandler::System(owner)) => return Some((owner, command)),
+            Err(ConsoleError::Unknown(name)) if self.commands.find_alias(&name).is_some() =>
+            {
+                let commands = self.commands.find_alias(&name).unwrap_or_default().to_string();
+                if let Err(err) = self.script.alias(&name, &commands)
+                {
+                    self.console.print(err.to_string());
+                }
+            }
+            Err(err) =>
+            {
+                self.console.print(err.to_string());
+                if let Some(declared) = self.commands.find(command.keyword())
+                {
+                    self.console.print(format!("\tUsage: {}", declared.usage()));
+                }
+            }
+        }
+        None
+    }
+
+    /// Options of the command line, with the ones the framework does not know in [Arguments::leftovers].
+    pub fn arguments
+    (
+        &self
+    )
+    -> &Arguments
+    {
+        &self.arguments
+    }
+
+    pub fn cvars
+    (
+        &self
+    )
+    -> &Cvars
+    {
+        &self.cvars
+    }
+
+    pub fn cvars_mut
+    (
+        &mut self
+    )
+    -> &mut Cvars
+    {
+        &mut self.cvars
+    }
+
+    pub fn console
+    (
+        &mut self
+    )
+    -> &mut Console
+    {
+        &mut self.console
+    }
+
+    /// Tab completion of the line typed into the console.
+    pub fn console_complete
+    (
+        &mut self
+    )
+    {
+        self.console.complete(&self.commands, &self.cvars);
+    }
+
+    /// Registered console commands.
+    pub fn commands
+    (
+        &mut self
+    )
+    -> &mut ConsoleCommands
+    {
+        &mut self.commands
+    }
+
+    fn engine_commands
+    ()
+    -> ConsoleCommands
+    {
+        let mut commands = ConsoleCommands::new();
+
+        commands.register
+        (
+            ConsoleCommand::new("help", Vec::new()).optional(vec![ConsoleParameterKind::String]).completion(ConsoleCompletion::Commands).describe("Lists all commands, or how to use one."),
+            ConsoleHandler::Framework(|framework, command| framework.commands.print_help(&mut framework.console, command.get::<String>(0).as_deref()))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("set", vec![ConsoleParameterKind::String]).optional(vec![ConsoleParameterKind::String]).completion(ConsoleCompletion::Cvars).describe("Sets a cvar, or prints it without a value."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                let name = command.get::<String>(0).unwrap();
+                let result = match command.parameters().get(1)
+                {
+                    // String cvars keep the text as typed, `00123` would otherwise be parsed to `123`.
+                    Some(value) => match framework.cvars.find(&name).map(Cvar::kind)
+                    {
+                        Some(ConsoleParameterKind::String) => framework.cvars.set(&name, command.get::<String>(1).unwrap()),
+                        _ => framework.cvars.set(&name, value.clone())
+                    },
+                    None => match framework.cvars.find(&name)
+                    {
+                        Some(cvar) =>
+                        {
+                            let line = format!("{} = {} (default {})", cvar.name(), cvar.value(), cvar.default());
+                            framework.console.print(line);
+                            Ok(())
+                        }
+                        None => Err(ConsoleError::UnknownCvar(name))
+                    }
+                };
+
+                if let Err(err) = result
+                {
+                    framework.console.print(err.to_string());
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("reset", vec![ConsoleParameterKind::String]).completion(ConsoleCompletion::Cvars).describe("Sets a cvar back to its default."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                if let Err(err) = framework.cvars.reset(&command.get::<String>(0).unwrap())
+                {
+                    framework.console.print(err.to_string());
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("cvars", Vec::new()).describe("Lists all cvars with their values."),
+            ConsoleHandler::Framework(|framework, _| framework.cvars.print(&mut framework.console))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("exec", vec![ConsoleParameterKind::String]).describe("Runs a script of console commands, relative to the project directory."),
+            ConsoleHandler::Framework(|framework, command| framework.exec(&command.get::<String>(0).unwrap()))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("wait", vec![ConsoleParameterKind::Integer]).describe("Holds back the commands after it for a number of frames."),
+            ConsoleHandler::Framework(|framework, command| framework.script.wait(command.get::<u32>(0).unwrap_or(0)))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("alias", Vec::new()).optional(vec![ConsoleParameterKind::String, ConsoleParameterKind::String]).describe("Names commands separated by ';', lists or prints aliases with less."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                match (command.get::<String>(0), command.get::<String>(1))
+                {
+                    (Some(name), Some(commands)) =>
+                    {
+                        match framework.commands.alias(&name, &commands)
+                        {
+                            Ok(()) if framework.cvars.settings_mut().set_alias(&name, Some(&commands)) => framework.cvars.save_settings(),
+                            Ok(()) => (),
+                            Err(err) => framework.console.print(err.to_string())
+                        }
+                    }
+                    (Some(name), None) =>
+                    {
+                        let line = match framework.commands.find_alias(&name)
+                        {
+                            Some(commands) => format!("{} = {}", name, ConsoleCommandParameter::from(commands)),
+                            None => format!("There is no alias '{}'.", name)
+                        };
+                        framework.console.print(line);
+                    }
+                    _ =>
+                    {
+                        let lines: Vec<String> = framework.commands.aliases().map(|(name, commands)| format!("\t{:<24}{}", name, commands)).collect();
+                        framework.console.print("Aliases:");
+                        for line in lines
+                        {
+                            framework.console.print(line);
+                        }
+                    }
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("unalias", vec![ConsoleParameterKind::String]).describe("Removes an alias."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                let name = command.get::<String>(0).unwrap();
+                if !framework.commands.unalias(&name)
+                {
+                    framework.console.print(format!("There is no alias '{}'.", name));
+                }
+                else if framework.cvars.settings_mut().set_alias(&name, None)
+                {
+                    framework.cvars.save_settings();
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("timescale", Vec::new()).optional(vec![ConsoleParameterKind::Float]).describe("Sets how fast game time runs, or prints it without a value."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                match command.get::<f32>(0)
+                {
+                    Some(scale) if (Clock::MIN_SCALE..=Clock::MAX_SCALE).contains(&scale) => framework.clock.set_scale(scale),
+                    Some(_) => framework.console.print(format!("Time scale needs to be between {} and {}.", Clock::MIN_SCALE, Clock::MAX_SCALE)),
+                    None =>
+                    {
+                        let line = format!("Time scale {}, game time {:.3} seconds{}.", framework.clock.scale(), framework.clock.time(), if framework.clock.is_paused() { ", paused" } else { "" });
+                        framework.console.print(line);
+                    }
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("pause", Vec::new()).describe("Stops game time, the console keeps running."),
+            ConsoleHandler::Framework(|framework, _| framework.clock.pause())
+        );
+        commands.register
+        (
+            ConsoleCommand::new("resume", Vec::new()).describe("Runs game time again after a pause."),
+            ConsoleHandler::Framework(|framework, _| framework.clock.resume())
+        );
+        commands.register
+        (
+            ConsoleCommand::new("step", Vec::new()).optional(vec![ConsoleParameterKind::Integer]).describe("Pauses and runs a number of frames of one fixed step each, one without a count."),
+            ConsoleHandler::Framework(|framework, command| framework.clock.step(command.get::<u32>(0).unwrap_or(1)))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("mounts", Vec::new()).describe("Lists where assets are looked up, the last overrides the ones before."),
+            ConsoleHandler::Framework(|framework, _|
+            {
+                let lines: Vec<String> = framework.vfs.mounts().iter().map(|mount| format!("\t{}: {}", mount.name, mount.source)).collect();
+                match lines.is_empty()
+                {
+                    true => framework.console.print("Nothing mounted."),
+                    false => lines.into_iter().for_each(|line| framework.console.print(line))
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("frametimes", Vec::new()).describe("Prints statistics of the times of recent frames."),
+            ConsoleHandler::Framework(|framework, _|
+            {
+                let fps = framework.fps.current();
+                match framework.frame_times.stats()
+                {
+                    Some(stats) =>
+                    {
+                        framework.console.print(format!("Frame times of the last {} frames, {} frames per second:", stats.count, fps));
+                        framework.console.print(format!("\tmin {:.2} ms, avg {:.2} ms, max {:.2} ms", stats.min * 1000.0, stats.average * 1000.0, stats.max * 1000.0));
+                        framework.console.print(format!("\t50% {:.2} ms, 95% {:.2} ms, 99% {:.2} ms", stats.p50 * 1000.0, stats.p95 * 1000.0, stats.p99 * 1000.0));
+                    }
+                    None => framework.console.print("No frames recorded yet.")
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("loglevel", Vec::new()).optional(vec![ConsoleParameterKind::String, ConsoleParameterKind::String]).describe("Sets the lowest level logged, for one tag with two parameters, 'default' as level removes the filter of a tag. Lists them without."),
+            ConsoleHandler::Framework(|framework, command|
+            {
+                let level = |name: &str| logger::Level::from_name(name).ok_or_else(|| format!("'{}' is not a level, one of trace, debug, info, warn or error.", name));
+                let result = match (command.get::<String>(0), command.get::<String>(1))
+                {
+                    (Some(tag), Some(name)) if name == "default" =>
+                    {
+                        logger::set_filter(&tag, None);
+                        Ok(())
+                    }
+                    (Some(tag), Some(name)) => level(&name).map(|level| logger::set_filter(&tag, Some(level))),
+                    (Some(name), None) => level(&name).map(logger::set_level),
+                    _ =>
+                    {
+                        let (level, filters) = logger::filters();
+                        framework.console.print(format!("Log level {}.", level));
+                        for (tag, level) in filters
+                        {
+                            framework.console.print(format!("\t{:<12}{}", tag, level));
+                        }
+                        Ok(())
+                    }
+                };
+
+                if let Err(err) = result
+                {
+                    framework.console.print(err);
+                }
+            })
+        );
+        commands.register
+        (
+            ConsoleCommand::new("quit", Vec::new()).describe("Closes the application."),
+            ConsoleHandler::Framework(|framework, _| framework.shutdown())
+        );
+        commands.register
+        (
+            ConsoleCommand::new("save", Vec::new()).optional(vec![ConsoleParameterKind::String]).describe("Saves the game, to the quicksave slot if none is given."),
+            ConsoleHandler::Framework(|framework, command| framework.save_load(SaveLoad::Save(command.get::<String>(0).unwrap_or_else(|| QUICKSAVE_SLOT.to_string()))))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("load", Vec::new()).optional(vec![ConsoleParameterKind::String]).describe("Loads the game, from the quicksave slot if none is given."),
+            ConsoleHandler::Framework(|framework, command| framework.save_load(SaveLoad::Load(command.get::<String>(0).unwrap_or_else(|| QUICKSAVE_SLOT.to_string()))))
+        );
+        commands.register
+        (
+            ConsoleCommand::new("saves", Vec::new()).describe("Lists the save slots."),
+            ConsoleHandler::Framework(|framework, _|
+            {
+                framework.console.print("Saves:");
+                for slot in framework.saves.slots()
+                {
+                    framework.console.print(format!("\t{}", slot));
+                }
+            })
+        );
+
+        commands
+    }
+
+    pub fn save_load
+    (
+        &mut self,
+        mode: SaveLoad
+    )
+    {
+        self.save_load = mode;
+    }
+
+    /// Get issued save or load event.
+    pub fn save_load_event
+    (
+        &self
+    )
+    -> &SaveLoad
+    {
+        &self.save_load
+    }
+
+    pub fn saves
+    (
+        &mut self
+    )
+    -> &mut SaveGames
+    {
+        &mut self.saves
+    }
+
+    pub fn window_show
+    (
+        &mut self
+    )
+    {
+        if let Some(window) = &self.window
+        {
+            window.set_maximized(false);
+            window.set_visible(true);
+        }
+    }
+
+    pub fn window
+    (
+        &self
+    )
+    -> Option<&Window>
+    {
+        self.window.as_ref()
+    }
+
+    /// The resolution of the cvars when headless.
+    pub fn window_size
+    (
+        &self
+    )
+    -> [u32; 2]
+    {
+        match &self.window
+        {
+            Some(window) => [window.inner_size().width, window.inner_size().height],
+            None => [self.cvars.get(defaults::RESOLUTION_WIDTH).unwrap_or(0), self.cvars.get(defaults::RESOLUTION_HEIGHT).unwrap_or(0)]
+        }
+    }
+
+    /// Seconds of game time the frame took, scaled and zero while paused.
+    pub fn delta
+    (
+        &self
+    )
+    -> f32
+    {
+        self.clock.delta()
+    }
+
+    /// Seconds the last frame took, for the console and GUI that keep running while the game is paused.
+    pub fn real_delta
+    (
+        &self
+    )
+    -> f32
+    {
+        self.frame_delta.delta()
+    }
+
+    pub fn clock
+    (
+        &mut self
+    )
+    -> &mut Clock
+    {
+        &mut self.clock
+    }
+
+    /// Seconds simulated by every [ApplicationEvents::update_fixed], set by [defaults::FIXED_RATE].
+    pub fn fixed_delta
+    (
+        &self
+    )
+    -> f32
+    {
+        1.0 / self.cvars.get::<f32>(defaults::FIXED_RATE).unwrap_or(60.0)
+    }
+
+    /// Runs a script of console commands, starting this frame.
+    /// Relative paths are relative to the project directory and `.cfg` is added when there is no extension.
+    pub fn exec
+    (
+        &mut self,
+        file: &str
+    )
+    {
+        let path = Script::path(&self.project_directory(), file);
+        if let Err(err) = self.script.exec(&path)
+        {
+            self.console.print(err.to_string());
+        }
+    }
+
+    /// Starts, stops or moves the remote console to match its cvars.
+    fn remote_restart
+    (
+        &mut self
+    )
+    {
+        self.remote = None;
+
+        if !self.cvars.get(defaults::RCON_ENABLED).unwrap_or(false)
+        {
+            return;
+        }
+
+        let address: String = self.cvars.get(defaults::RCON_ADDRESS).unwrap_or_default();
+        let password: String = self.cvars.get(defaults::RCON_PASSWORD).unwrap_or_default();
+        match RemoteConsole::start(&address, &password, &self.console)
+        {
+            Ok(remote) =>
+            {
+                self.console.print(format!("Remote console on {}.", remote.address()));
+                self.remote = Some(remote);
+            }
+            Err(err) => self.console.print(format!("Remote console not started: {}", err))
+        }
+    }
+
+    /// Moves lines logged since the last frame into the console.
+    fn log_update
+    (
+        &mut self
+    )
+    {
+        for line in logger::take_pending()
+        {
+            self.console.append(line);
+        }
+    }
+
+    /// Issues commands of remote clients and sends them the output.
+    fn remote_update
+    (
+        &mut self
+    )
+    {
+        let lines = match &mut self.remote
+        {
+            Some(remote) => remote.update(&self.console),
+            None => return
+        };
+
+        for line in lines
+        {
+            self.console.print(format!("remote> {}", crash::redact(&line)));
+            self.command(&line);
+        }
+    }
+
+    /// Holds the settings and scripts of the project, see [vfs::project_directory].
+    pub fn project_directory
+    (
+        &self
+    )
+    -> PathBuf
+    {
+        self.project_directory.clone()
+    }
+
+    /// Holds the settings and scripts of the user, [None] when the system has no place for them.
+    pub fn user_directory
+    (
+        &self
+    )
+    -> Option<PathBuf>
+    {
+        self.user_directory.clone()
+    }
+
+    pub fn vfs
+    (
+        &self
+    )
+    -> &Vfs
+    {
+        &self.vfs
+    }
+
+    pub fn vfs_mut
+    (
+        &mut self
+    )
+    -> &mut Vfs
+    {
+        &mut self.vfs
+    }
+
+    /// Names an asset to be read from the mounts with [AssetPath::read].
+    pub fn asset_path
+    (
+        &self,
+        asset: &Path
+    )
+    -> AssetPath
+    {
+        AssetPath(asset.to_path_buf())
+    }
+}
+
+/// Relative path of an asset, the same in every mount of the [Vfs].
+pub struct AssetPath(pub PathBuf);
+
+impl AssetPath
+{
+    /// From the mount overriding all others, checked against its hash when in an archive.
+    pub fn read
+    (
+        &self,
+        vfs: &Vfs
+    )
+    -> Result<Vec<u8>, vfs::VfsError>
+    {
+        vfs.read(&self.0)
+    }
+
+    /// Another asset next to this one, as named in a file referring to it.
+    pub fn sibling
+    (
+        &self,
+        name: &Path
+    )
+    -> AssetPath
+    {
+        AssetPath(self.0.parent().unwrap_or(Path::new("")).join(name))
+    }
+}
+
+pub trait ApplicationEvents
+{
+    fn name
+    ()
+    -> &'static str;
+
+    fn version
+    ()
+    -> &'static str;
+    
+    fn engine_name
+    ()
+    -> &'static str;
+
+    fn engine_version
+    ()
+    -> &'static str;
+
+    /// Failing here ends [run] with the error logged.
+    fn new
+    (
+        framework: &mut Framework
+    )
+    -> Result<Self, StartupError>
+    where
+        Self: Sized;
+    
+    fn update
+    (
+        &mut self,
+        framework: &mut Framework
+    );
+    
+    /// Called zero or more times per frame, each simulating [Framework::fixed_delta] seconds.
+    fn update_fixed
+    (
+        &mut self,
+        framework: &mut Framework
+    );
+
+    /// Alpha is how far the time of the frame is between the last fixed step and the next one, for interpolating.
+    fn update_engine
+    (
+        &mut self,
+        device_event: &[DeviceEvent],
+        window_event: &[WindowEvent],
+        alpha: f32
+    );
+
+}
+
+/// Visits every field of the application that implements [SystemEvents], in field order.
+/// Implemented by declaring the application struct inside [find_traits].
+pub trait Systems
+{
+    fn systems
+    (
+        &mut self,
+        each: &mut dyn FnMut(&mut dyn SystemEvents)
+    );
+}
+
+/// Used by [find_traits] to pick fields implementing [SystemEvents] without listing them.
+#[doc(hidden)]
+pub struct SystemProbe<'a, T>(pub &'a mut T);
+
+#[doc(hidden)]
+pub trait ProbeSystem
+{
+    fn probe
+    (
+        &mut self,
+        each: &mut dyn FnMut(&mut dyn SystemEvents)
+    );
+}
+
+impl
+<
+    'a,
+    T: SystemEvents
+>
+ProbeSystem for SystemProbe<'a, T>
+{
+    fn probe
+    (
+        &mut self,
+        each: &mut dyn FnMut(&mut dyn SystemEvents)
+    )
+    {
+        each(self.0);
+    }
+}
+
+/// Optional systems, such as graphics when headless, are visited when present.
+impl
+<
+    'a,
+    T: SystemEvents
+>
+ProbeSystem for SystemProbe<'a, Option<T>>
+{
+    fn probe
+    (
+        &mut self,
+        each: &mut dyn FnMut(&mut dyn SystemEvents)
+    )
+    {
+        if let Some(system) = self.0
+        {
+            each(system);
+        }
+    }
+}
+
+/// Only picked by method resolution when [ProbeSystem] does not apply.
+#[doc(hidden)]
+pub trait ProbeOther
+{
+    fn probe
+    (
+        &mut self,
+        each: &mut dyn FnMut(&mut dyn SystemEvents)
+    );
+}
+
+impl
+<
+    'a,
+    T
+>
+ProbeOther for &mut SystemProbe<'a, T>
+{
+    fn probe
+    (
+        &mut self,
+        _each: &mut dyn FnMut(&mut dyn SystemEvents)
+    )
+    {
+    }
+}
+
+pub trait SystemEvents
+{
+    /// Key of the section of the system in save games.
+    fn name
+    (
+        &self
+    )
+    -> &'static str;
+
+    /// Console commands of the system, registered once after [ApplicationEvents::new].
+    fn commands
+    (
+        &self
+    )
+    -> Vec<ConsoleCommand>;
+
+    /// Called with issued commands the system registered, parameters are already validated.
+    fn console
+    (
+        &mut self,
+        framework: &mut Framework,
+        command: &ConsoleCommand
+    );
+
+    /// Called before the next update for every cvar that changed.
+    fn cvar_changed
+    (
+        &mut self,
+        _framework: &mut Framework,
+        _cvar: &Cvar
+    )
+    {
+    }
+
+    fn save_load
+    (
+        &mut self,
+        framework: &mut Framework,
+        section: SaveSection
+    )
+    -> Result<(), SerializeError>;
+}
+
+/// Counts Per Second.
+/// For instance this can be used to count frames per second.
+pub struct CPS
+{
+    label: String,
+    current: u32,
+    per_second: Instant,
+    counts: u32
+}
+
+impl CPS
+{
+    pub fn new
+    (
+        name: &str
+    )
+    -> CPS
+    {
+        CPS
+        {
+            label: name.to_string(),
+            current: 0,
+            per_second: Instant::now(),
+            counts: 0
+        }
+    }
+
+    pub fn count
+    (
+        &mut self,
+        print: bool
+    )
+    {
+        self.refresh_per_second(print);
+        self.counts += 1;
+    }
+
+    pub fn current
+    (
+        &mut self
+    )
+    -> u32
+    {
+        self.refresh_per_second(false);
+        self.current
+    }
+
+    /// Print every second to console if [print] is true.
+    fn refresh_per_second
+    (
+        &mut self,
+        print: bool // TODO Needs to be enabled via internal bool and pub function instead.
+    )
+    {
+        if self.per_second.elapsed().as_secs_f32() >= 1.0
+        {
+            self.current = self.counts;
+            self.counts = 0;
+            self.per_second = Instant::now();
+
+            if print
+            {
+                info!("framework", "{}: {}", self.label, self.current)
+            }
+        }
+    }
+}
+
+/// Holds frames to a rate, sleeping most of the wait and spinning the rest for precision.
+pub struct FrameLimiter
+{
+    next_frame: Instant
+}
+
+impl Default for FrameLimiter
+{
+    fn default
+    ()
+    -> FrameLimiter
+    {
+        FrameLimiter::new()
+    }
+}
+
+impl FrameLimiter
+{
+    /// Sleeping can overshoot by about this much, the last part of a wait is spun.
+    const SPIN: Duration = Duration::from_millis(2);
+
+    /// Slower rates would leave the console unusable between frames, they do not wait.
+    const MIN_RATE: f32 = 1.0;
+
+    pub fn new
+    ()
+    -> FrameLimiter
+    {
+        FrameLimiter
+        {
+            next_frame: Instant::now()
+        }
+    }
+
+    /// Waits until the next frame is due at `rate` frames per second, below one does not wait.
+    /// Frames that run late move the schedule instead of rushing the following ones.
+    pub fn wait
+    (
+        &mut self,
+        rate: f32
+    )
+    {
+        if !(Self::MIN_RATE..).contains(&rate)
+        {
+            self.next_frame = Instant::now();
+            return;
+        }
+
+        self.next_frame += Duration::from_secs_f32(1.0 / rate);
+        let now = Instant::now();
+        if self.next_frame <= now
+        {
+            self.next_frame = now;
+            return;
+        }
+
+        let wait = self.next_frame - now;
+        if wait > Self::SPIN
+        {
+            thread::sleep(wait - Self::SPIN);
+        }
+
+        while Instant::now() < self.next_frame
+        {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Times of recent frames, in seconds.
+pub struct FrameTimes
+{
+    times: VecDeque<f32>
+}
+
+pub struct FrameStats
+{
+    pub count: usize,
+    pub min: f32,
+    pub average: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p95: f32,
+    pub p99: f32
+}
+
+impl Default for FrameTimes
+{
+    fn default
+    ()
+    -> FrameTimes
+    {
+        FrameTimes::new()
+    }
+}
+
+impl FrameTimes
+{
+    /// Enough for a few seconds at high frame rates.
+    const RECORDED: usize = 1000;
+
+    pub fn new
+    ()
+    -> FrameTimes
+    {
+        FrameTimes
+        {
+            times: VecDeque::with_capacity(Self::RECORDED)
+        }
+    }
+
+    pub fn record
+    (
+        &mut self,
+        seconds: f32
+    )
+    {
+        if self.times.len() == Self::RECORDED
+        {
+            self.times.pop_front();
+        }
+        self.times.push_back(seconds);
+    }
+
+    /// [None] before the first frame.
+    pub fn stats
+    (
+        &self
+    )
+    -> Option<FrameStats>
+    {
+        let mut sorted: Vec<f32> = self.times.iter().cloned().collect();
+        if sorted.is_empty()
+        {
+            return None;
+        }
+        sorted.sort_by(f32::total_cmp);
+
+        let percentile = |percent: usize| sorted[(sorted.len() - 1) * percent / 100];
+        Some
+        (
+            FrameStats
+            {
+                count: sorted.len(),
+                min: sorted[0],
+                average: sorted.iter().sum::<f32>() / sorted.len() as f32,
+                max: sorted[sorted.len() - 1],
+                p50: percentile(50),
+                p95: percentile(95),
+                p99: percentile(99)
+            }
+        )
+    }
+}
+
+pub struct Frames(u32);
+
+impl Frames
+{
+    pub fn new
+    ()
+    -> Frames
+    {
+        Frames(0)
+    }
+
+    pub fn count
+    (
+        &mut self
+    )
+    {
+        self.0 += 1;
+    }
+
+    pub fn clear
+    (
+        &mut self
+    )
+    {
+        self.0 = 0;
+    }
+
+    pub fn frames(&self) -> u32
+    {
+        self.0
+    }
+}
+
+pub struct Delta
+{
+    duration: Instant,
+    last_frame: f32
+}
+
+impl Delta
+{
+    pub fn new
+    ()
+    -> Delta
+    {
+        Delta
+        {
+            duration: Instant::now(),
+            last_frame: 0.0
+        }
+    }
+
+    pub fn reset
+    (
+        &mut self,
+        print: bool
+    )
+    {        
+        self.last_frame = self.duration.elapsed().as_secs_f32();
+        self.duration = Instant::now();
+        if print
+        {
+            info!("framework", "Delta: {}", self.last_frame);
+        }        
+    }
+
+    pub fn delta
+    (
+        &self
+    ) -> f32
+    {
+        //self.0.elapsed().as_secs_f32()
+        self.last_frame
+    }
+}
+
+/// Accumulates frame time and hands it out in fixed steps.
+pub struct FixedStep
+{
+    accumulator: f32,
+    alpha: f32
+}
+
+impl Default for FixedStep
+{
+    fn default
+    ()
+    -> FixedStep
+    {
+        FixedStep::new()
+    }
+}
+
+impl FixedStep
+{
+    pub fn new
+    ()
+    -> FixedStep
+    {
+        FixedStep
+        {
+            accumulator: 0.0,
+            alpha: 0.0
+        }
+    }
+
+    /// Number of steps to take for the time of a frame.
+    /// Time for more than `max_steps` is dropped, so a slow frame does not make the next one slower.
+    pub fn advance
+    (
+        &mut self,
+        delta: f32,
+        step: f32,
+        max_steps: u32
+    )
+    -> u32
+    {
+        self.accumulator += delta;
+        let mut steps = (self.accumulator / step) as u32;
+        if steps > max_steps
+        {
+            steps = max_steps;
+            self.accumulator %= step;
+        }
+        else
+        {
+            self.accumulator -= steps as f32 * step;
+        }
+
+        self.alpha = (self.accumulator / step).clamp(0.0, 1.0);
+        steps
+    }
+
+    pub fn alpha
+    (
+        &self
+    )
+    -> f32
+    {
+        self.alpha
+    }
+}
+
+/// Game time, separate from the real time frames take.
+/// Scaled for slow motion, stopped while paused and advanced frame by frame with [Clock::step].
+pub struct Clock
+{
+    scale: f32,
+    paused: bool,
+
+    /// Frames still to run while paused.
+    steps: u32,
+    delta: f32,
+    time: f64
+}
+
+impl Default for Clock
+{
+    fn default
+    ()
+    -> Clock
+    {
+        Clock::new()
+    }
+}
+
+impl Clock
+{
+    pub const MIN_SCALE: f32 = 0.01;
+    pub const MAX_SCALE: f32 = 100.0;
+
+    pub fn new
+    ()
+    -> Clock
     {
-        self.refresh_per_second(false);
-        self.current
+        Clock
+        {
+            scale: 1.0,
+            paused: false,
+            steps: 0,
+            delta: 0.0,
+            time: 0.0
+        }
     }
 
-    /// Print every second to console if [print] is true.
-    fn refresh_per_second
+    /// Called once per frame with the real time of the last frame.
+    /// A stepped frame takes `step` seconds of game time, whatever the real time was.
+    pub fn advance
     (
         &mut self,
-        print: bool // TODO Needs to be enabled via internal bool and pub function instead.
+        real_delta: f32,
+        step: f32
     )
     {
-        if self.per_second.elapsed().as_secs_f32() >= 1.0
+        self.delta = match (self.paused, self.steps)
         {
-            self.current = self.counts;
-            self.counts = 0;
-            self.per_second = Instant::now();
-
-            if print
+            (false, _) => real_delta * self.scale,
+            (true, 0) => 0.0,
+            (true, _) =>
             {
-                println!("{}: {}", self.label, self.current)
+                self.steps -= 1;
+                step
             }
-        }
+        };
+        self.time += f64::from(self.delta);
     }
-}
-
-pub struct Frames(u32);
 
-impl Frames
-{
-    pub fn new
-    ()
-    -> Frames
+    pub fn delta
+    (
+        &self
+    )
+    -> f32
     {
-        Frames(0)
+        self.delta
     }
 
-    pub fn count
+    /// Seconds of game time since start.
+    pub fn time
     (
-        &mut self
+        &self
     )
+    -> f64
     {
-        self.0 += 1;
+        self.time
     }
 
-    pub fn clear
+    pub fn scale
     (
-        &mut self
+        &self
     )
+    -> f32
     {
-        self.0 = 0;
+        self.scale
     }
 
-    pub fn frames(&self) -> u32
+    /// Clamped to [Clock::MIN_SCALE] and [Clock::MAX_SCALE].
+    pub fn set_scale
+    (
+        &mut self,
+        scale: f32
+    )
     {
-        self.0
+        self.scale = scale.clamp(Self::MIN_SCALE, Self::MAX_SCALE);
     }
-}
 
-pub struct Delta
-{
-    duration: Instant,
-    last_frame: f32
-}
+    pub fn is_paused
+    (
+        &self
+    )
+    -> bool
+    {
+        self.paused
+    }
 
-impl Delta
-{
-    pub fn new
-    ()
-    -> Delta
+    pub fn pause
+    (
+        &mut self
+    )
     {
-        Delta
-        {
-            duration: Instant::now(),
-            last_frame: 0.0
-        }
+        self.paused = true;
     }
 
-    pub fn reset
+    /// Also drops steps not run yet.
+    pub fn resume
     (
-        &mut self,
-        print: bool
+        &mut self
     )
-    {        
-        self.last_frame = self.duration.elapsed().as_secs_f32();
-        self.duration = Instant::now();
-        if print
-        {
-            println!("Delta: {}", self.last_frame);
-        }        
+    {
+        self.paused = false;
+        self.steps = 0;
     }
 
-    pub fn delta
+    /// Pauses and runs a number of frames.
+    pub fn step
     (
-        &self
-    ) -> f32
+        &mut self,
+        frames: u32
+    )
     {
-        //self.0.elapsed().as_secs_f32()
-        self.last_frame
+        self.paused = true;
+        self.steps = self.steps.saturating_add(frames);
     }
 }
 
@@ -939,19 +3117,576 @@ macro_rules! enum_str // TODO Needs to be more fexliable regarding pub, derive,
                     $($name::$variant => stringify!($variant)),*
                 }
             }
+
+            fn make_from_str
+            (
+                name: &str
+            )
+            -> Option<$name>
+            {
+                match name
+                {
+                    $(stringify!($variant) => Some($name::$variant)),*,
+                    _ => None
+                }
+            }
         }
     };
 }
 
-/// Find all fields with a specific trait. // TODO What are the specific traits?
+/// Declares the application struct and implements [Systems] for it.
+/// Finds all fields implementing [SystemEvents], so a new system is dispatched without being listed anywhere.
+/// Save games hand out sections in field order, storages need to come before the systems holding handles to them.
 #[macro_export]
 macro_rules! find_traits
 {
     (
-        $field:ident
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident
+        {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $type:ty),* $(,)?
+        }
     )
     =>
     {
+        $(#[$meta])*
+        $vis struct $name
+        {
+            $($(#[$field_meta])* $field_vis $field: $type),*
+        }
 
+        impl $crate::Systems for $name
+        {
+            fn systems
+            (
+                &mut self,
+                each: &mut dyn FnMut(&mut dyn $crate::SystemEvents)
+            )
+            {
+                #[allow(unused_imports)]
+                use $crate::{ProbeSystem, ProbeOther};
+
+                $((&mut $crate::SystemProbe(&mut self.$field)).probe(each);)*
+            }
+        }
     };
-}
\ No newline at end of file
+}
+
+/// Empty directory of a test, left behind for a look after a failure.
+#[cfg(test)]
+pub(crate) fn test_directory
+(
+    name: &str
+)
+-> PathBuf
+{
+    let directory = env::temp_dir().join(format!("nokden-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn storage_generations
+    ()
+    {
+        let mut storage = Storage::new();
+        let first = storage.add(1);
+        let stale = storage.duplicate(&first);
+        assert!(storage.remove(first));
+        assert!(!storage.contains(&stale));
+        assert!(storage.read(&stale).is_none());
+
+        // The slot is reused with the next generation, the stale handle stays stale.
+        let second = storage.add(2);
+        assert_eq!(second.index, stale.index);
+        assert_ne!(second.generation, stale.generation);
+        assert!(!storage.contains(&stale));
+        assert_eq!(*storage.read(&second).unwrap(), 2);
+    }
+
+    #[test]
+    fn storage_release
+    ()
+    {
+        let mut storage = Storage::new();
+        let handle = storage.add("kept");
+        let clone = handle.clone();
+        drop(handle);
+        storage.release_dropped();
+        assert!(storage.contains(&clone));
+        assert_eq!(storage.len(), 1);
+
+        drop(clone);
+        storage.release_dropped();
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn storage_identity
+    ()
+    {
+        let mut first = Storage::new();
+        let mut second = Storage::new();
+        let handle = first.add(1);
+        let _other = second.add(2);
+        assert!(first.contains(&handle));
+        assert!(!second.contains(&handle));
+        assert!(second.read(&handle).is_none());
+    }
+
+    #[test]
+    fn storage_disabled
+    ()
+    {
+        let mut storage = Storage::new();
+        let shown = storage.add(1);
+        let hidden = storage.add(2);
+        assert!(storage.disable(&hidden));
+        assert!(storage.contains(&hidden));
+        assert!(!storage.is_enabled(&hidden));
+
+        // Disabled entries keep their data and handles but are skipped when iterating.
+        assert_eq!(storage.iter().map(|data| *data.read().unwrap()).collect::<Vec<i32>>(), vec![1]);
+        assert_eq!(storage.all().len(), 2);
+        assert_eq!(*storage.read(&hidden).unwrap(), 2);
+
+        assert!(storage.enable(&hidden));
+        let mut enabled: Vec<i32> = storage.iter().map(|data| *data.read().unwrap()).collect();
+        enabled.sort();
+        assert_eq!(enabled, vec![1, 2]);
+
+        let stale = storage.duplicate(&shown);
+        assert!(storage.remove(shown));
+        assert!(!storage.disable(&stale));
+        assert!(!storage.enable(&stale));
+        assert!(!storage.is_enabled(&stale));
+    }
+
+    struct Body
+    {
+        id: usize,
+        position: f32,
+        velocity: Handle<f32>
+    }
+
+    #[test]
+    fn storage_par_join
+    ()
+    {
+        let mut velocities = Storage::new();
+        let mut bodies = Storage::new();
+        let velocity_handles: Vec<Handle<f32>> = (0..1000).map(|id| velocities.add(id as f32)).collect();
+        let body_handles: Vec<Handle<Body>> = velocity_handles.iter().enumerate().map(|(id, velocity)| bodies.add(Body { id, position: 1.0, velocity: velocity.clone() })).collect();
+
+        // Skipped for a disabled or stale velocity and for a disabled body.
+        velocities.disable(&velocity_handles[10]);
+        velocities.remove(velocity_handles[30].clone());
+        bodies.disable(&body_handles[20]);
+
+        // The same join done one entry after the other.
+        let mut expected: Vec<(usize, f32)> = bodies.iter().map(|body|
+        {
+            let body = body.read().unwrap();
+            match velocities.is_enabled(&body.velocity)
+            {
+                true => (body.id, body.position + *velocities.read(&body.velocity).unwrap()),
+                false => (body.id, body.position)
+            }
+        }).collect();
+        expected.sort_by_key(|(id, _)| *id);
+
+        bodies.par_join(&velocities, |body| &body.velocity, |body, velocity| body.position += velocity);
+        let mut joined: Vec<(usize, f32)> = bodies.iter().map(|body| { let body = body.read().unwrap(); (body.id, body.position) }).collect();
+        joined.sort_by_key(|(id, _)| *id);
+
+        assert_eq!(joined.len(), 999);
+        assert_eq!(joined, expected);
+        assert_eq!(joined[10], (10, 1.0));
+        assert_eq!(joined[29], (30, 1.0));
+        assert_eq!(bodies.read(&body_handles[20]).unwrap().position, 1.0);
+    }
+
+    #[test]
+    fn storage_ticks
+    ()
+    {
+        let mut storage = Storage::new();
+        let first = storage.add(1);
+        let second = storage.add(2);
+        let tick = storage.tick();
+
+        // Reading is no change.
+        assert_eq!(*storage.read(&first).unwrap(), 1);
+        storage.par_for_each(|_| {});
+        assert_eq!(storage.tick(), tick);
+        assert_eq!(storage.changed_since(tick).count(), 0);
+
+        *storage.write(&second).unwrap() = 3;
+        assert!(storage.tick() > tick);
+        assert!(storage.is_changed_since(&second, tick));
+        assert!(!storage.is_changed_since(&first, tick));
+        assert_eq!(storage.changed_since(tick).map(|data| *data.read().unwrap()).collect::<Vec<i32>>(), vec![3]);
+
+        // Writes through the locks of iter are only seen once marked.
+        let tick = storage.tick();
+        for data in storage.iter()
+        {
+            *data.write().unwrap() += 1;
+        }
+        assert_eq!(storage.tick(), tick);
+        assert!(storage.mark_changed(&first));
+        assert!(storage.is_changed_since(&first, tick));
+        assert!(!storage.is_changed_since(&second, tick));
+    }
+
+    #[test]
+    fn fixed_step
+    ()
+    {
+        // Powers of two, exact in floats.
+        let mut fixed_step = FixedStep::new();
+        assert_eq!(fixed_step.advance(0.3125, 0.125, 8), 2);
+        assert_eq!(fixed_step.alpha(), 0.5);
+
+        // The rest carries over to the next frame.
+        assert_eq!(fixed_step.advance(0.0625, 0.125, 8), 1);
+        assert_eq!(fixed_step.alpha(), 0.0);
+
+        // A long frame is cut to the maximum, the time beyond it is dropped.
+        assert_eq!(fixed_step.advance(10.0, 0.125, 8), 8);
+        assert_eq!(fixed_step.advance(0.0, 0.125, 8), 0);
+    }
+
+    #[test]
+    fn clock
+    ()
+    {
+        let mut clock = Clock::new();
+        clock.set_scale(2.0);
+        clock.advance(0.5, 0.1);
+        assert_eq!(clock.delta(), 1.0);
+
+        clock.set_scale(1000.0);
+        assert_eq!(clock.scale(), Clock::MAX_SCALE);
+        clock.set_scale(1.0);
+
+        clock.pause();
+        clock.advance(0.5, 0.1);
+        assert_eq!(clock.delta(), 0.0);
+
+        clock.step(2);
+        clock.advance(0.5, 0.1);
+        clock.advance(0.5, 0.1);
+        assert_eq!(clock.delta(), 0.1);
+        clock.advance(0.5, 0.1);
+        assert_eq!(clock.delta(), 0.0);
+        assert!((clock.time() - 1.2).abs() < 1e-6);
+
+        clock.step(u32::MAX);
+        clock.step(u32::MAX);
+        clock.resume();
+        clock.advance(0.5, 0.1);
+        assert_eq!(clock.delta(), 0.5);
+    }
+
+    #[test]
+    fn frame_limiter
+    ()
+    {
+        let mut limiter = FrameLimiter::new();
+        let started = Instant::now();
+        for _ in 0..10
+        {
+            limiter.wait(100.0);
+        }
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(99), "{:?}", elapsed);
+
+        // A late frame moves the schedule, the next one is not rushed and the one after waits a whole frame.
+        thread::sleep(Duration::from_millis(30));
+        let late = Instant::now();
+        limiter.wait(100.0);
+        assert!(limiter.next_frame >= late);
+        limiter.wait(100.0);
+        assert!(late.elapsed() >= Duration::from_millis(10));
+
+        // No limit, nothing is scheduled ahead, not even for rates too slow to wait for.
+        for rate in [0.0, 0.001, 0.0000000001, -1.0, f32::NAN]
+        {
+            limiter.wait(rate);
+            assert!(limiter.next_frame <= Instant::now());
+        }
+    }
+
+    #[test]
+    fn frame_times
+    ()
+    {
+        let mut times = FrameTimes::new();
+        assert!(times.stats().is_none());
+
+        for time in (1..=100).rev()
+        {
+            times.record(time as f32 / 1000.0);
+        }
+        let stats = times.stats().unwrap();
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.min, 0.001);
+        assert_eq!(stats.max, 0.1);
+        assert!((stats.average - 0.0505).abs() < 1e-6);
+        assert_eq!(stats.p50, 0.050);
+        assert_eq!(stats.p95, 0.095);
+        assert_eq!(stats.p99, 0.099);
+
+        // Only the most recent frames are kept.
+        for _ in 0..FrameTimes::RECORDED
+        {
+            times.record(0.02);
+        }
+        let stats = times.stats().unwrap();
+        assert_eq!(stats.count, FrameTimes::RECORDED);
+        assert_eq!(stats.max, 0.02);
+    }
+
+    #[test]
+    fn startup_errors
+    ()
+    {
+        let err = StartupError::from(FrameworkError::Exe(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        assert!(matches!(err, StartupError::Framework(FrameworkError::Exe(_))));
+        assert_eq!(err.to_string(), format!("Path of the executable unknown: {}", std::io::Error::from(std::io::ErrorKind::NotFound)));
+        assert_eq!(StartupError::Application(String::from("No level.")).to_string(), "No level.");
+
+        // Graphics fail without a window instead of panicking.
+        let arguments = Arguments { asset_path: Some(crate::test_directory("startup-errors").join("import")), ..Arguments::default() };
+        let framework = Framework::headless("nokden-startup-errors-test", "1", arguments).unwrap();
+        let err = StartupError::from(graphics::GraphicsSystem::new(&framework).err().unwrap());
+        assert!(matches!(err, StartupError::Graphics(GraphicsError::NoWindow)));
+        assert_eq!(err.to_string(), "Graphics: Graphics need a window, there is none when headless.");
+    }
+
+    crate::find_traits!
+    {
+        /// Counts its updates, without graphics.
+        #[derive(Default)]
+        struct Headless
+        {
+            updates: u32,
+            fixed_updates: u32,
+            notes: Notes,
+
+            /// Left out unless a test sets it, like graphics when headless.
+            optional: Option<Notes>
+        }
+    }
+
+    /// Keeps the text of its command, marked when game time was paused, to see the order commands run in.
+    #[derive(Default)]
+    struct Notes
+    {
+        notes: Vec<String>
+    }
+
+    impl SystemEvents
+    for Notes
+    {
+        fn name
+        (
+            &self
+        )
+        -> &'static str
+        {
+            "notes"
+        }
+
+        fn commands
+        (
+            &self
+        )
+        -> Vec<ConsoleCommand>
+        {
+            vec![ConsoleCommand::new("note", vec![ConsoleParameterKind::String])]
+        }
+
+        fn console
+        (
+            &mut self,
+            framework: &mut Framework,
+            command: &ConsoleCommand
+        )
+        {
+            let note = command.get::<String>(0).unwrap();
+            match framework.clock.is_paused()
+            {
+                true => self.notes.push(format!("{} paused", note)),
+                false => self.notes.push(note)
+            }
+        }
+
+        fn save_load
+        (
+            &mut self,
+            _framework: &mut Framework,
+            _section: SaveSection
+        )
+        -> Result<(), SerializeError>
+        {
+            Ok(())
+        }
+    }
+
+    impl ApplicationEvents
+    for Headless
+    {
+        fn name
+        ()
+        -> &'static str
+        {
+            "nokden-headless-test"
+        }
+
+        fn version
+        ()
+        -> &'static str
+        {
+            "1"
+        }
+
+        fn engine_name
+        ()
+        -> &'static str
+        {
+            "none"
+        }
+
+        fn engine_version
+        ()
+        -> &'static str
+        {
+            "1"
+        }
+
+        fn new
+        (
+            framework: &mut Framework
+        )
+        -> Result<Headless, StartupError>
+        {
+            assert!(framework.is_headless());
+            Ok(Headless::default())
+        }
+
+        fn update
+        (
+            &mut self,
+            _framework: &mut Framework
+        )
+        {
+            self.updates += 1;
+        }
+
+        fn update_fixed
+        (
+            &mut self,
+            _framework: &mut Framework
+        )
+        {
+            self.fixed_updates += 1;
+        }
+
+        fn update_engine
+        (
+            &mut self,
+            _device_event: &[DeviceEvent],
+            _window_event: &[WindowEvent],
+            _alpha: f32
+        )
+        {
+        }
+    }
+
+    #[test]
+    fn systems_found
+    ()
+    {
+        let visited = |application: &mut Headless|
+        {
+            let mut visited = 0;
+            application.systems(&mut |system| { assert_eq!(system.name(), "notes"); visited += 1; });
+            visited
+        };
+
+        let mut application = Headless::default();
+        assert_eq!(visited(&mut application), 1);
+        application.optional = Some(Notes::default());
+        assert_eq!(visited(&mut application), 2);
+    }
+
+    #[test]
+    fn headless
+    ()
+    {
+        let directory = crate::test_directory("headless");
+        let arguments = Arguments { asset_path: Some(directory.join("import")), user_path: Some(directory.join("user")), frames: Some(6), ..Arguments::default() };
+        let mut framework = Framework::headless(Headless::name(), Headless::version(), arguments).unwrap();
+        let mut application = start::<Headless>(&mut framework).unwrap();
+        assert!(framework.is_headless());
+
+        // Paced like the headless loop at its default rate, until the framework quits after the last frame.
+        let mut frames = 0;
+        while framework.is_running()
+        {
+            frame(&mut framework, &mut application, &[], &[]);
+            frames += 1;
+            thread::sleep(Duration::from_secs_f32(1.0 / 60.0));
+        }
+        assert_eq!(frames, 6);
+        assert_eq!(application.updates, 6);
+
+        // Frames three to six each see at least a sixtieth of a second pass, at the default fixed rate of 60.
+        assert!(application.fixed_updates >= 3);
+    }
+
+    #[test]
+    fn commands_in_order
+    ()
+    {
+        let directory = crate::test_directory("commands");
+        let arguments = Arguments { asset_path: Some(directory.join("import")), user_path: Some(directory.join("user")), ..Arguments::default() };
+        std::fs::create_dir_all(directory.join("user")).unwrap();
+        std::fs::write(directory.join("user").join(AUTOEXEC_FILE), "note autoexec\n").unwrap();
+        let mut framework = Framework::headless(Headless::name(), Headless::version(), arguments).unwrap();
+        let mut application = start::<Headless>(&mut framework).unwrap();
+        frame(&mut framework, &mut application, &[], &[]);
+        assert_eq!(mem::take(&mut application.notes.notes), ["autoexec"]);
+
+        // Commands of the framework and of systems run in the order issued.
+        framework.command("note a; pause; note b");
+        framework.command("resume; note c");
+        frame(&mut framework, &mut application, &[], &[]);
+        assert_eq!(mem::take(&mut application.notes.notes), ["a", "b paused", "c"]);
+
+        // A wait in an alias holds back the rest of the alias and of the line it is in.
+        framework.commands().alias("hop", "note up; wait 1; note down").unwrap();
+        framework.command("hop; note after");
+        frame(&mut framework, &mut application, &[], &[]);
+        assert_eq!(mem::take(&mut application.notes.notes), ["up"]);
+        frame(&mut framework, &mut application, &[], &[]);
+        assert!(application.notes.notes.is_empty());
+        frame(&mut framework, &mut application, &[], &[]);
+        assert_eq!(mem::take(&mut application.notes.notes), ["down", "after"]);
+
+        // Scripts run their lines in order as well, before the commands queued after them.
+        let script = framework.project_directory().join("order.cfg");
+        std::fs::write(&script, "note one\npause; note two\nresume\nnote three\n").unwrap();
+        framework.command("exec order; note four");
+        frame(&mut framework, &mut application, &[], &[]);
+        assert_eq!(mem::take(&mut application.notes.notes), ["one", "two paused", "three", "four"]);
+    }
+}
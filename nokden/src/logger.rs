@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::{fmt, fs};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
+use std::time::Instant;
+
+pub const LOG_EXTENSION: &str = "log";
+
+/// A file is started over at this size, the previous ones are kept as `name.1.log`, `name.2.log` and so on.
+const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+const KEPT_FILES: u32 = 5;
+
+/// Lines waiting for the console are dropped beyond this, oldest first.
+const PENDING_LINES: usize = 512;
+
+/// Lines kept for a crash report, see [recent].
+const RECENT_LINES: usize = 100;
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger::new());
+
+/// A panic while the logger was locked leaves it usable, the crash report still needs it.
+fn lock
+()
+-> MutexGuard<'static, Logger>
+{
+    LOGGER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level
+{
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error
+}
+
+impl Level
+{
+    pub const ALL: [Level; 5] = [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+    pub fn name
+    (
+        &self
+    )
+    -> &'static str
+    {
+        match self
+        {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error"
+        }
+    }
+
+    pub fn from_name
+    (
+        name: &str
+    )
+    -> Option<Level>
+    {
+        Self::ALL.into_iter().find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Level
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        f.pad(self.name())
+    }
+}
+
+struct LogFile
+{
+    path: PathBuf,
+    file: File,
+    written: u64
+}
+
+/// Messages with a level and a tag naming the subsystem, filtered per tag.
+/// Written to stdout, a log file and the console, see [log].
+struct Logger
+{
+    level: Level,
+    filters: BTreeMap<String, Level>,
+    file: Option<LogFile>,
+    started: Option<Instant>,
+
+    /// Lines for the console, taken by the framework every frame.
+    pending: VecDeque<String>,
+
+    /// The last lines as written to the file, whether or not there is one.
+    recent: VecDeque<String>
+}
+
+impl Logger
+{
+    const fn new
+    ()
+    -> Logger
+    {
+        Logger
+        {
+            level: Level::Info,
+            filters: BTreeMap::new(),
+            file: None,
+            started: None,
+            pending: VecDeque::new(),
+            recent: VecDeque::new()
+        }
+    }
+
+    fn enabled
+    (
+        &self,
+        level: Level,
+        tag: &str
+    )
+    -> bool
+    {
+        level >= *self.filters.get(tag).unwrap_or(&self.level)
+    }
+
+    fn write_file
+    (
+        &mut self,
+        line: &str
+    )
+    {
+        let seconds = self.started.get_or_insert_with(Instant::now).elapsed().as_secs_f32();
+        let line = format!("{:>10.3} {}", seconds, line);
+
+        if self.recent.len() == RECENT_LINES
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line.clone());
+
+        let log_file = match &mut self.file
+        {
+            Some(log_file) => log_file,
+            None => return
+        };
+
+        let text = line + "\n";
+        if log_file.file.write_all(text.as_bytes()).is_err()
+        {
+            self.file = None;
+            return;
+        }
+
+        log_file.written += text.len() as u64;
+        if log_file.written >= MAX_FILE_SIZE
+        {
+            let path = log_file.path.clone();
+            self.file = None;
+            self.file = open(&path).ok();
+        }
+    }
+}
+
+/// Starts writing to `name.log` in a directory, the file of the previous run is kept as `name.1.log`.
+pub fn open_file
+(
+    directory: &Path,
+    name: &str
+)
+-> io::Result<PathBuf>
+{
+    let mut path = directory.join(name);
+    path.set_extension(LOG_EXTENSION);
+
+    let log_file = open(&path)?;
+    lock().file = Some(log_file);
+    Ok(path)
+}
+
+fn open
+(
+    path: &Path
+)
+-> io::Result<LogFile>
+{
+    rotate(path);
+    Ok
+    (
+        LogFile
+        {
+            path: path.to_path_buf(),
+            file: File::create(path)?,
+            written: 0
+        }
+    )
+}
+
+/// Moves `name.log` to `name.1.log`, that one to `name.2.log` and so on, dropping the oldest.
+fn rotate
+(
+    path: &Path
+)
+{
+    let numbered = |number: u32| path.with_extension(format!("{}.{}", number, LOG_EXTENSION));
+
+    let _ = fs::remove_file(numbered(KEPT_FILES));
+    for number in (1..KEPT_FILES).rev()
+    {
+        let _ = fs::rename(numbered(number), numbered(number + 1));
+    }
+    let _ = fs::rename(path, numbered(1));
+}
+
+/// Used by the macros, [crate::info] and the like.
+pub fn log
+(
+    level: Level,
+    tag: &str,
+    message: fmt::Arguments
+)
+{
+    let mut logger = lock();
+    if !logger.enabled(level, tag)
+    {
+        return;
+    }
+
+    let line = match level
+    {
+        Level::Info => format!("{}", message),
+        _ => format!("[{}] {}", level, message)
+    };
+    let tagged = format!("{:<5} {:<10} {}", level, tag, message);
+
+    println!("{}", line);
+    logger.write_file(&tagged);
+
+    if logger.pending.len() == PENDING_LINES
+    {
+        logger.pending.pop_front();
+    }
+    logger.pending.push_back(line);
+}
+
+/// Output of console commands, always written and already in the console.
+pub(crate) fn echo
+(
+    line: &str
+)
+{
+    println!("{}", line);
+    lock().write_file(&format!("{:<5} {:<10} {}", Level::Info, "console", line));
+}
+
+pub(crate) fn take_pending
+()
+-> Vec<String>
+{
+    lock().pending.drain(..).collect()
+}
+
+/// The last lines written, oldest first.
+/// Also works while panicking with the logger locked, there is nothing to give then.
+pub fn recent
+()
+-> Vec<String>
+{
+    match LOGGER.try_lock()
+    {
+        Ok(logger) => logger.recent.iter().cloned().collect(),
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().recent.iter().cloned().collect(),
+        Err(TryLockError::WouldBlock) => Vec::new()
+    }
+}
+
+pub fn enabled
+(
+    level: Level,
+    tag: &str
+)
+-> bool
+{
+    lock().enabled(level, tag)
+}
+
+/// Lowest level written for tags without a filter.
+pub fn set_level
+(
+    level: Level
+)
+{
+    lock().level = level;
+}
+
+/// [None] removes the filter, the tag follows [set_level] again.
+pub fn set_filter
+(
+    tag: &str,
+    level: Option<Level>
+)
+{
+    let mut logger = lock();
+    match level
+    {
+        Some(level) => logger.filters.insert(tag.to_string(), level),
+        None => logger.filters.remove(tag)
+    };
+}
+
+/// The level for tags without a filter and every filter.
+pub fn filters
+()
+-> (Level, Vec<(String, Level)>)
+{
+    let logger = lock();
+    (logger.level, logger.filters.iter().map(|(tag, level)| (tag.clone(), *level)).collect())
+}
+
+#[macro_export]
+macro_rules! trace
+{
+    ($tag:expr, $($arg:tt)+) => { $crate::logger::log($crate::logger::Level::Trace, $tag, format_args!($($arg)+)) };
+}
+
+#[macro_export]
+macro_rules! debug
+{
+    ($tag:expr, $($arg:tt)+) => { $crate::logger::log($crate::logger::Level::Debug, $tag, format_args!($($arg)+)) };
+}
+
+#[macro_export]
+macro_rules! info
+{
+    ($tag:expr, $($arg:tt)+) => { $crate::logger::log($crate::logger::Level::Info, $tag, format_args!($($arg)+)) };
+}
+
+#[macro_export]
+macro_rules! warn
+{
+    ($tag:expr, $($arg:tt)+) => { $crate::logger::log($crate::logger::Level::Warn, $tag, format_args!($($arg)+)) };
+}
+
+#[macro_export]
+macro_rules! error
+{
+    ($tag:expr, $($arg:tt)+) => { $crate::logger::log($crate::logger::Level::Error, $tag, format_args!($($arg)+)) };
+}
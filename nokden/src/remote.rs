@@ -0,0 +1,677 @@
+use std::{fmt, io, thread};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::console::Console;
+
+/// Addresses starting with this are Unix sockets, everything else a TCP address on localhost.
+pub const UNIX_PREFIX: &str = "unix:";
+
+const GREETING: &str = "nokden remote console";
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Makes guessing the password slow, for all connections together.
+const DENIED_DELAY: Duration = Duration::from_secs(1);
+
+/// Clients that did not send the password in time are dropped.
+const PASSWORD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections waiting to send the password, more are closed right away.
+const MAX_PENDING: usize = 4;
+
+/// Longest line read before the password is checked, longer ones are denied.
+const MAX_PASSWORD_LINE: u64 = 256;
+
+trait Stream: Read + Write + Send
+{
+    fn try_clone_stream
+    (
+        &self
+    )
+    -> io::Result<Box<dyn Stream>>;
+
+    fn shutdown_stream
+    (
+        &self
+    );
+
+    fn set_read_timeout_stream
+    (
+        &self,
+        timeout: Option<Duration>
+    )
+    -> io::Result<()>;
+}
+
+impl Stream for TcpStream
+{
+    fn try_clone_stream
+    (
+        &self
+    )
+    -> io::Result<Box<dyn Stream>>
+    {
+        Ok(Box::new(self.try_clone()?))
+    }
+
+    fn shutdown_stream
+    (
+        &self
+    )
+    {
+        let _ = self.shutdown(std::net::Shutdown::Both);
+    }
+
+    fn set_read_timeout_stream
+    (
+        &self,
+        timeout: Option<Duration>
+    )
+    -> io::Result<()>
+    {
+        self.set_read_timeout(timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream
+{
+    fn try_clone_stream
+    (
+        &self
+    )
+    -> io::Result<Box<dyn Stream>>
+    {
+        Ok(Box::new(self.try_clone()?))
+    }
+
+    fn shutdown_stream
+    (
+        &self
+    )
+    {
+        let _ = self.shutdown(std::net::Shutdown::Both);
+    }
+
+    fn set_read_timeout_stream
+    (
+        &self,
+        timeout: Option<Duration>
+    )
+    -> io::Result<()>
+    {
+        self.set_read_timeout(timeout)
+    }
+}
+
+enum Listener
+{
+    Tcp(TcpListener),
+
+    /// With the device and inode of the socket file, so only that file is removed on drop.
+    #[cfg(unix)]
+    Unix(UnixListener, std::path::PathBuf, (u64, u64))
+}
+
+impl Listener
+{
+    fn bind
+    (
+        address: &str
+    )
+    -> Result<Listener, RemoteError>
+    {
+        if let Some(path) = address.strip_prefix(UNIX_PREFIX)
+        {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+                // A socket left by an earlier run is replaced, anything else at the path is never touched.
+                let path = std::path::PathBuf::from(path);
+                match std::fs::symlink_metadata(&path)
+                {
+                    Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(&path).map_err(RemoteError::Io)?,
+                    Ok(_) => return Err(RemoteError::Address(address.to_string())),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(RemoteError::Io(err))
+                }
+
+                let listener = UnixListener::bind(&path).map_err(RemoteError::Io)?;
+                listener.set_nonblocking(true).map_err(RemoteError::Io)?;
+                let metadata = std::fs::symlink_metadata(&path).map_err(RemoteError::Io)?;
+                return Ok(Listener::Unix(listener, path, (metadata.dev(), metadata.ino())));
+            }
+
+            #[cfg(not(unix))]
+            {
+                let _ = path;
+                return Err(RemoteError::Address(address.to_string()));
+            }
+        }
+
+        let socket_address: SocketAddr = address.parse().map_err(|_| RemoteError::Address(address.to_string()))?;
+        if !socket_address.ip().is_loopback()
+        {
+            return Err(RemoteError::NotLocal(address.to_string()));
+        }
+
+        let listener = TcpListener::bind(socket_address).map_err(RemoteError::Io)?;
+        listener.set_nonblocking(true).map_err(RemoteError::Io)?;
+        Ok(Listener::Tcp(listener))
+    }
+
+    fn accept
+    (
+        &self
+    )
+    -> io::Result<Box<dyn Stream>>
+    {
+        match self
+        {
+            Listener::Tcp(listener) =>
+            {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, _, _) =>
+            {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+impl Drop for Listener
+{
+    fn drop
+    (
+        &mut self
+    )
+    {
+        #[cfg(unix)]
+        if let Listener::Unix(_, path, created) = self
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            if std::fs::symlink_metadata(&path).is_ok_and(|metadata| (metadata.dev(), metadata.ino()) == *created)
+            {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Shared by the connections of one remote console, so guessing in parallel is no faster.
+struct Throttle
+{
+    /// Connections that did not send the password yet.
+    pending: AtomicUsize,
+
+    /// When the next password may be checked, moved on by every wrong one.
+    next_check: Mutex<Instant>
+}
+
+/// A client that sent the password, output of the console is written by a thread of its own.
+struct Client
+{
+    output: Sender<String>,
+
+    /// Kept to close the connection when the remote console stops.
+    stream: Box<dyn Stream>
+}
+
+/// Accepts console connections on localhost or a Unix socket.
+/// Clients first send the password on a line of its own, then one command per line.
+/// Commands are handed to the main thread, output of the console is sent to every client.
+pub struct RemoteConsole
+{
+    address: String,
+    stop: Arc<AtomicBool>,
+
+    /// Owns the listener, joined on drop so the address is free again for a restart.
+    listen: Option<JoinHandle<()>>,
+    lines: Receiver<String>,
+    clients: Arc<Mutex<Vec<Client>>>,
+
+    /// Output of the console up to here was sent.
+    printed: u64
+}
+
+impl RemoteConsole
+{
+    /// An empty password is refused, anyone on the machine could connect otherwise.
+    pub fn start
+    (
+        address: &str,
+        password: &str,
+        console: &Console
+    )
+    -> Result<RemoteConsole, RemoteError>
+    {
+        if password.is_empty()
+        {
+            return Err(RemoteError::NoPassword);
+        }
+
+        let listener = Listener::bind(address)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients: Arc<Mutex<Vec<Client>>> = Arc::new(Mutex::new(Vec::new()));
+        let (sender, lines) = mpsc::channel();
+
+        let listen =
+        {
+            let stop = stop.clone();
+            let clients = clients.clone();
+            let password = password.to_string();
+            thread::spawn(move || Self::listen(listener, password, sender, clients, stop))
+        };
+
+        Ok
+        (
+            RemoteConsole
+            {
+                address: address.to_string(),
+                stop,
+                listen: Some(listen),
+                lines,
+                clients,
+                printed: console.printed()
+            }
+        )
+    }
+
+    pub fn address
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.address
+    }
+
+    /// Commands received since the last call, sends new output of the console to the clients.
+    /// Output is only queued here, a slow client never holds up the frame.
+    pub fn update
+    (
+        &mut self,
+        console: &Console
+    )
+    -> Vec<String>
+    {
+        let mut clients = self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        if !clients.is_empty()
+        {
+            let mut output = String::new();
+            for line in console.printed_since(self.printed)
+            {
+                output.push_str(line);
+                output.push('\n');
+            }
+
+            if !output.is_empty()
+            {
+                clients.retain(|client| client.output.send(output.clone()).is_ok());
+            }
+        }
+        drop(clients);
+        self.printed = console.printed();
+
+        self.lines.try_iter().collect()
+    }
+
+    fn listen
+    (
+        listener: Listener,
+        password: String,
+        sender: Sender<String>,
+        clients: Arc<Mutex<Vec<Client>>>,
+        stop: Arc<AtomicBool>
+    )
+    {
+        let throttle = Arc::new(Throttle { pending: AtomicUsize::new(0), next_check: Mutex::new(Instant::now()) });
+        while !stop.load(Ordering::Relaxed)
+        {
+            match listener.accept()
+            {
+                Ok(stream) if throttle.pending.load(Ordering::Relaxed) >= MAX_PENDING => stream.shutdown_stream(),
+                Ok(stream) =>
+                {
+                    throttle.pending.fetch_add(1, Ordering::Relaxed);
+                    let password = password.clone();
+                    let sender = sender.clone();
+                    let clients = clients.clone();
+                    let throttle = throttle.clone();
+                    thread::spawn(move || Self::serve(stream, &password, sender, clients, throttle));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(err) =>
+                {
+                    error!("remote", "Remote console stopped accepting: {}", err);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn serve
+    (
+        mut stream: Box<dyn Stream>,
+        password: &str,
+        sender: Sender<String>,
+        clients: Arc<Mutex<Vec<Client>>>,
+        throttle: Arc<Throttle>
+    )
+    -> io::Result<()>
+    {
+        let authenticated = Self::authenticate(&mut stream, password, &throttle);
+        throttle.pending.fetch_sub(1, Ordering::Relaxed);
+        let reader = match authenticated?
+        {
+            Some(reader) => reader,
+            None =>
+            {
+                stream.shutdown_stream();
+                return Ok(());
+            }
+        };
+
+        stream.set_read_timeout_stream(None)?;
+        writeln!(stream, "ok")?;
+
+        let (output, outputs) = mpsc::channel();
+        let writer = stream.try_clone_stream()?;
+        thread::spawn(move || Self::write(writer, outputs));
+        clients.lock().unwrap_or_else(PoisonError::into_inner).push(Client { output, stream });
+
+        for line in reader.lines()
+        {
+            let line = line?;
+            if sender.send(line).is_err()
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends queued output until the client is gone, one that takes longer than [WRITE_TIMEOUT] is closed.
+    /// The client is dropped from the list with the next output, the channel is closed by then.
+    fn write
+    (
+        mut stream: Box<dyn Stream>,
+        outputs: Receiver<String>
+    )
+    {
+        for output in outputs
+        {
+            if stream.write_all(output.as_bytes()).is_err()
+            {
+                stream.shutdown_stream();
+                return;
+            }
+        }
+    }
+
+    /// The reader for the commands of the client, [None] if the password was wrong or did not come in time.
+    fn authenticate
+    (
+        stream: &mut Box<dyn Stream>,
+        password: &str,
+        throttle: &Throttle
+    )
+    -> io::Result<Option<BufReader<Box<dyn Stream>>>>
+    {
+        stream.set_read_timeout_stream(Some(PASSWORD_TIMEOUT))?;
+        writeln!(stream, "{}", GREETING)?;
+        writeln!(stream, "password:")?;
+
+        let mut reader = BufReader::new(stream.try_clone_stream()?);
+        let mut line = String::new();
+        (&mut reader).take(MAX_PASSWORD_LINE).read_line(&mut line)?;
+
+        // Checks wait in turn, each wrong password holds back every connection.
+        let mut next_check = throttle.next_check.lock().unwrap_or_else(PoisonError::into_inner);
+        thread::sleep(next_check.saturating_duration_since(Instant::now()));
+        if !line.ends_with('\n') || !same_password(line.trim_end_matches(['\r', '\n']), password)
+        {
+            *next_check = Instant::now() + DENIED_DELAY;
+            drop(next_check);
+            writeln!(stream, "denied")?;
+            return Ok(None);
+        }
+
+        Ok(Some(reader))
+    }
+}
+
+impl Drop for RemoteConsole
+{
+    fn drop
+    (
+        &mut self
+    )
+    {
+        self.stop.store(true, Ordering::Relaxed);
+        for client in self.clients.lock().unwrap_or_else(PoisonError::into_inner).drain(..)
+        {
+            client.stream.shutdown_stream();
+        }
+
+        // The listener is dropped with its thread, within one accept poll.
+        if let Some(listen) = self.listen.take()
+        {
+            let _ = listen.join();
+        }
+    }
+}
+
+/// Takes as long for every wrong password of the same length.
+fn same_password
+(
+    given: &str,
+    password: &str
+)
+-> bool
+{
+    given.len() == password.len() && given.bytes().zip(password.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+#[derive(Debug)]
+pub enum RemoteError
+{
+    Io(io::Error),
+    Address(String),
+    NotLocal(String),
+    NoPassword
+}
+
+impl fmt::Display for RemoteError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            RemoteError::Io(err) => write!(f, "{}", err),
+            RemoteError::Address(address) => write!(f, "'{}' is not an address to listen on.", address),
+            RemoteError::NotLocal(address) => write!(f, "'{}' is not on localhost.", address),
+            RemoteError::NoPassword => write!(f, "A password is needed.")
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests
+{
+    use super::*;
+
+    fn connect
+    (
+        address: &str
+    )
+    -> BufReader<UnixStream>
+    {
+        let stream = UnixStream::connect(address.strip_prefix(UNIX_PREFIX).unwrap()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        BufReader::new(stream)
+    }
+
+    fn read_line
+    (
+        client: &mut BufReader<UnixStream>
+    )
+    -> String
+    {
+        let mut line = String::new();
+        client.read_line(&mut line).unwrap();
+        line.trim_end().to_string()
+    }
+
+    fn send
+    (
+        client: &mut BufReader<UnixStream>,
+        line: &str
+    )
+    {
+        writeln!(client.get_mut(), "{}", line).unwrap();
+    }
+
+    /// Connects and sends the password, the answer to it is returned.
+    fn login
+    (
+        address: &str,
+        password: &str
+    )
+    -> (BufReader<UnixStream>, String)
+    {
+        let mut client = connect(address);
+        assert_eq!(read_line(&mut client), GREETING);
+        assert_eq!(read_line(&mut client), "password:");
+        send(&mut client, password);
+        let answer = read_line(&mut client);
+        (client, answer)
+    }
+
+    fn address
+    (
+        name: &str
+    )
+    -> String
+    {
+        format!("{}{}", UNIX_PREFIX, crate::test_directory(name).join("remote.sock").to_string_lossy())
+    }
+
+    #[test]
+    fn start
+    ()
+    {
+        let console = Console::new();
+        assert!(matches!(RemoteConsole::start(&address("remote-start"), "", &console), Err(RemoteError::NoPassword)));
+        assert!(matches!(RemoteConsole::start("10.0.0.1:27015", "secret", &console), Err(RemoteError::NotLocal(_))));
+        assert!(matches!(RemoteConsole::start("localhost", "secret", &console), Err(RemoteError::Address(_))));
+    }
+
+    #[test]
+    fn commands_and_output
+    ()
+    {
+        let address = address("remote-commands");
+        let mut console = Console::new();
+        console.print("before");
+        let mut remote = RemoteConsole::start(&address, "secret", &console).unwrap();
+
+        let (mut client, answer) = login(&address, "secret");
+        assert_eq!(answer, "ok");
+        send(&mut client, "echo hello");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut lines = Vec::new();
+        while lines.is_empty() && Instant::now() < deadline
+        {
+            lines = remote.update(&console);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(lines, ["echo hello"]);
+
+        // Only output printed after the start is sent.
+        console.print("hello");
+        remote.update(&console);
+        assert_eq!(read_line(&mut client), "hello");
+
+        // Stopping closes the connection and removes the socket.
+        drop(remote);
+        assert_eq!(client.read_line(&mut String::new()).unwrap(), 0);
+        assert!(!std::path::Path::new(address.strip_prefix(UNIX_PREFIX).unwrap()).exists());
+    }
+
+    #[test]
+    fn wrong_password
+    ()
+    {
+        let address = address("remote-password");
+        let console = Console::new();
+        let _remote = RemoteConsole::start(&address, "secret", &console).unwrap();
+
+        let started = Instant::now();
+        assert_eq!(login(&address, "guess").1, "denied");
+
+        // Every connection waits for the wrong password of another one.
+        let (_client, answer) = login(&address, "secret");
+        assert_eq!(answer, "ok");
+        assert!(started.elapsed() >= DENIED_DELAY);
+    }
+
+    #[test]
+    fn pending
+    ()
+    {
+        let address = address("remote-pending");
+        let console = Console::new();
+        let _remote = RemoteConsole::start(&address, "secret", &console).unwrap();
+
+        let mut waiting: Vec<BufReader<UnixStream>> = (0..MAX_PENDING).map(|_| connect(&address)).collect();
+        for client in &mut waiting
+        {
+            assert_eq!(read_line(client), GREETING);
+        }
+
+        // Closed without a greeting while as many clients wait for their password.
+        let mut refused = connect(&address);
+        assert_eq!(refused.read_line(&mut String::new()).unwrap(), 0);
+
+        // Leaving makes room again.
+        drop(waiting.pop());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop
+        {
+            let mut client = connect(&address);
+            let mut line = String::new();
+            client.read_line(&mut line).unwrap();
+            if line.trim_end() == GREETING
+            {
+                break;
+            }
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
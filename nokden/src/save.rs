@@ -0,0 +1,869 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use crate::{Framework, SystemEvents};
+use crate::serialize::{Serialize, Deserialize, Serializer, Deserializer, Format, SerializeError, Remaps};
+
+/// Counted when the layout of the save container changes.
+pub const SAVE_VERSION: u32 = 1;
+
+const BINARY_MAGIC: &[u8] = b"NKSV";
+const TEXT_SECTION: &str = "[section ";
+const HEADER_SECTION: &str = "header";
+const FILE_EXTENSION: &str = "sav";
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+
+#[derive(Debug)]
+pub enum SaveError
+{
+    Io(io::Error),
+    Serialize
+    {
+        section: String,
+        error: SerializeError
+    },
+    MissingSlot(String),
+
+    /// Empty, or a path leaving the save directory.
+    InvalidSlot(String),
+
+    /// Not a save container or cut off.
+    Corrupt(String),
+    MissingHeader,
+    WrongApplication
+    {
+        found: String,
+        expected: String
+    },
+    VersionMismatch
+    {
+        found: String,
+        expected: String
+    }
+}
+
+impl fmt::Display for SaveError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Serialize { section, error } => write!(f, "Section '{}': {}", section, error),
+            SaveError::MissingSlot(slot) => write!(f, "There is no save named '{}'.", slot),
+            SaveError::InvalidSlot(slot) => write!(f, "'{}' can not name a save, it needs to be a file name without '/', '\\' or '..'.", slot),
+            SaveError::Corrupt(reason) => write!(f, "Save is corrupt: {}", reason),
+            SaveError::MissingHeader => write!(f, "Save has no header."),
+            SaveError::WrongApplication { found, expected } => write!(f, "Save belongs to '{}', not '{}'.", found, expected),
+            SaveError::VersionMismatch { found, expected } => write!(f, "Save was made by version {}, this is version {}.", found, expected)
+        }
+    }
+}
+
+impl From<io::Error> for SaveError
+{
+    fn from
+    (
+        err: io::Error
+    )
+    -> SaveError
+    {
+        SaveError::Io(err)
+    }
+}
+
+/// Handed to [SystemEvents::save_load], one per system and keyed by [SystemEvents::name].
+/// Loading is done in two steps so nothing changes unless every section loaded:
+/// [SaveSection::Load] only reads and stages, then [SaveSection::Commit] hands back what was staged.
+pub enum SaveSection<'a>
+{
+    Save(&'a mut Serializer),
+    Load(&'a mut Deserializer, &'a mut Staged),
+    Commit(Staged)
+}
+
+/// What a system read from its section, kept until every section loaded.
+#[derive(Default)]
+pub struct Staged(Option<Box<dyn Any>>);
+
+impl Staged
+{
+    pub fn stage
+    <
+        T: Any
+    >
+    (
+        &mut self,
+        value: T
+    )
+    {
+        self.0 = Some(Box::new(value));
+    }
+
+    /// [None] if nothing was staged, or something of another type.
+    pub fn take
+    <
+        T: Any
+    >
+    (
+        self
+    )
+    -> Option<T>
+    {
+        self.0.and_then(|value| value.downcast().ok()).map(|value| *value)
+    }
+}
+
+/// Identifies the application that made the save.
+pub struct SaveHeader
+{
+    pub app_name: String,
+    pub app_version: String,
+    pub framework_version: String,
+    pub save_version: u32
+}
+
+impl Serialize for SaveHeader
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.field("app_name", &self.app_name);
+        serializer.field("app_version", &self.app_version);
+        serializer.field("framework_version", &self.framework_version);
+        serializer.field("save_version", &self.save_version);
+    }
+}
+
+impl Deserialize for SaveHeader
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<SaveHeader, SerializeError>
+    {
+        Ok
+        (
+            SaveHeader
+            {
+                app_name: deserializer.field("app_name")?,
+                app_version: deserializer.field("app_version")?,
+                framework_version: deserializer.field("framework_version")?,
+                save_version: deserializer.field("save_version")?
+            }
+        )
+    }
+}
+
+/// Save slots on disk, one file per slot.
+pub struct SaveGames
+{
+    directory: PathBuf,
+    app_name: String,
+    app_version: String,
+    pub format: Format
+}
+
+impl SaveGames
+{
+    pub fn new
+    (
+        directory: PathBuf,
+        app_name: &str,
+        app_version: &str
+    )
+    -> SaveGames
+    {
+        SaveGames
+        {
+            directory,
+            app_name: app_name.to_string(),
+            app_version: app_version.to_string(),
+            format: default_format()
+        }
+    }
+
+    /// Slots come from the console, names that would leave the save directory are refused.
+    pub fn slot_path
+    (
+        &self,
+        slot: &str
+    )
+    -> Result<PathBuf, SaveError>
+    {
+        if slot.is_empty() || slot.contains(['/', '\\']) || slot.contains("..") || Path::new(slot).is_absolute()
+        {
+            return Err(SaveError::InvalidSlot(slot.to_string()));
+        }
+        Ok(self.directory.join(format!("{}.{}", slot, FILE_EXTENSION)))
+    }
+
+    /// Names of all slots on disk, sorted.
+    pub fn slots
+    (
+        &self
+    )
+    -> Vec<String>
+    {
+        let mut slots: Vec<String> = match fs::read_dir(&self.directory)
+        {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == FILE_EXTENSION))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+                .collect(),
+            Err(_) => Vec::new()
+        };
+        slots.sort();
+        slots
+    }
+
+    /// Starts collecting sections for [slot], written by [SaveSession::finish].
+    pub fn begin_save
+    (
+        &self,
+        slot: &str
+    )
+    -> Result<SaveSession, SaveError>
+    {
+        let path = self.slot_path(slot)?;
+        let header = SaveHeader
+        {
+            app_name: self.app_name.clone(),
+            app_version: self.app_version.clone(),
+            framework_version: env!("CARGO_PKG_VERSION").to_string(),
+            save_version: SAVE_VERSION
+        };
+
+        let mut serializer = Serializer::new(self.format);
+        header.serialize(&mut serializer);
+
+        Ok
+        (
+            SaveSession
+            {
+                slot: slot.to_string(),
+                path,
+                mode: SessionMode::Save { format: self.format, sections: vec![(HEADER_SECTION.to_string(), serializer.finish())] },
+                remaps: Remaps::new(),
+                errors: Vec::new()
+            }
+        )
+    }
+
+    /// Reads [slot] and checks its header before any system gets to load.
+    pub fn begin_load
+    (
+        &self,
+        slot: &str
+    )
+    -> Result<SaveSession, SaveError>
+    {
+        let path = self.slot_path(slot)?;
+        let bytes = match fs::read(&path)
+        {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(SaveError::MissingSlot(slot.to_string())),
+            Err(err) => return Err(err.into())
+        };
+
+        let mut sections = unpack(bytes)?;
+        let header = match sections.remove(HEADER_SECTION)
+        {
+            Some(header) => header,
+            None => return Err(SaveError::MissingHeader)
+        };
+
+        let header = Deserializer::new(header)
+            .and_then(|mut deserializer| SaveHeader::deserialize(&mut deserializer))
+            .map_err(|error| SaveError::Serialize { section: HEADER_SECTION.to_string(), error })?;
+
+        if header.app_name != self.app_name
+        {
+            return Err(SaveError::WrongApplication { found: header.app_name, expected: self.app_name.clone() });
+        }
+
+        if header.app_version != self.app_version
+        {
+            return Err(SaveError::VersionMismatch { found: header.app_version, expected: self.app_version.clone() });
+        }
+
+        if header.save_version != SAVE_VERSION
+        {
+            return Err(SaveError::VersionMismatch { found: header.save_version.to_string(), expected: SAVE_VERSION.to_string() });
+        }
+
+        Ok
+        (
+            SaveSession
+            {
+                slot: slot.to_string(),
+                path,
+                mode: SessionMode::Load { sections, staged: HashMap::new() },
+                remaps: Remaps::new(),
+                errors: Vec::new()
+            }
+        )
+    }
+}
+
+enum SessionMode
+{
+    Save
+    {
+        format: Format,
+        sections: Vec<(String, Vec<u8>)>
+    },
+    Load
+    {
+        sections: HashMap<String, Vec<u8>>,
+
+        /// Per system, committed by [SaveSession::commit].
+        staged: HashMap<String, Staged>
+    }
+}
+
+/// One save or load of a slot. Systems are handed their section in the order they are passed to [SaveSession::system],
+/// so a storage needs to come before the systems holding handles to it.
+pub struct SaveSession
+{
+    slot: String,
+    path: PathBuf,
+    mode: SessionMode,
+
+    /// Carried from section to section, so handles can point into storages of other systems.
+    remaps: Remaps,
+    errors: Vec<SaveError>
+}
+
+impl SaveSession
+{
+    pub fn slot
+    (
+        &self
+    )
+    -> &str
+    {
+        &self.slot
+    }
+
+    pub fn is_save
+    (
+        &self
+    )
+    -> bool
+    {
+        matches!(self.mode, SessionMode::Save { .. })
+    }
+
+    /// Hands [system] its section. Systems without a section in the loaded save are skipped.
+    pub fn system
+    (
+        &mut self,
+        system: &mut dyn SystemEvents,
+        framework: &mut Framework
+    )
+    {
+        let name = system.name().to_string();
+        match &mut self.mode
+        {
+            SessionMode::Save { format, sections } =>
+            {
+                let mut serializer = Serializer::new(*format);
+                match system.save_load(framework, SaveSection::Save(&mut serializer))
+                {
+                    Ok(()) => sections.push((name, serializer.finish())),
+                    Err(error) => self.errors.push(SaveError::Serialize { section: name, error })
+                }
+            }
+            SessionMode::Load { sections, staged } =>
+            {
+                let bytes = match sections.remove(&name)
+                {
+                    Some(bytes) => bytes,
+                    None => return
+                };
+
+                let mut deserializer = match Deserializer::new(bytes)
+                {
+                    Ok(deserializer) => deserializer,
+                    Err(error) =>
+                    {
+                        self.errors.push(SaveError::Serialize { section: name, error });
+                        return;
+                    }
+                };
+
+                let mut staging = Staged::default();
+                deserializer.set_remaps(mem::take(&mut self.remaps));
+                let result = system.save_load(framework, SaveSection::Load(&mut deserializer, &mut staging));
+                self.remaps = deserializer.take_remaps();
+
+                match result
+                {
+                    Ok(()) => { staged.insert(name, staging); }
+                    Err(error) => self.errors.push(SaveError::Serialize { section: name, error })
+                }
+            }
+        }
+    }
+
+    /// Hands [system] what it staged, once every system had [SaveSession::system] and no section failed.
+    pub fn commit
+    (
+        &mut self,
+        system: &mut dyn SystemEvents,
+        framework: &mut Framework
+    )
+    {
+        if !self.errors.is_empty()
+        {
+            return;
+        }
+
+        if let SessionMode::Load { staged, .. } = &mut self.mode
+        {
+            let name = system.name().to_string();
+            if let Some(staging) = staged.remove(&name)
+            {
+                if let Err(error) = system.save_load(framework, SaveSection::Commit(staging))
+                {
+                    self.errors.push(SaveError::Serialize { section: name, error });
+                }
+            }
+        }
+    }
+
+    /// Writes the save atomically, a crash while writing leaves the previous save of the slot intact.
+    /// Returns every error of the session.
+    pub fn finish
+    (
+        self
+    )
+    -> Result<(), Vec<SaveError>>
+    {
+        let mut errors = self.errors;
+
+        if let SessionMode::Save { format, sections } = self.mode
+        {
+            if errors.is_empty()
+            {
+                if let Err(err) = write_atomic(&self.path, &pack(format, &sections))
+                {
+                    errors.push(err.into());
+                }
+            }
+        }
+
+        match errors.is_empty()
+        {
+            true => Ok(()),
+            false => Err(errors)
+        }
+    }
+}
+
+/// Text saves in debug builds so they can be read, binary otherwise.
+#[cfg(debug_assertions)]
+fn default_format
+()
+-> Format
+{
+    Format::Text
+}
+
+#[cfg(not(debug_assertions))]
+fn default_format
+()
+-> Format
+{
+    Format::Binary
+}
+
+/// Writes next to [path] first, so a crash never leaves a half written file behind.
+pub(crate) fn write_atomic
+(
+    path: &Path,
+    bytes: &[u8]
+)
+-> io::Result<()>
+{
+    if let Some(directory) = path.parent()
+    {
+        fs::create_dir_all(directory)?;
+    }
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".");
+    temporary.push(TEMPORARY_EXTENSION);
+    let temporary = PathBuf::from(temporary);
+    {
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temporary, path)
+}
+
+fn pack
+(
+    format: Format,
+    sections: &[(String, Vec<u8>)]
+)
+-> Vec<u8>
+{
+    let mut bytes = Vec::new();
+    match format
+    {
+        Format::Binary =>
+        {
+            bytes.extend_from_slice(BINARY_MAGIC);
+            bytes.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+            for (name, section) in sections
+            {
+                bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(name.as_bytes());
+                bytes.extend_from_slice(&(section.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(section);
+            }
+        }
+        Format::Text =>
+        {
+            for (name, section) in sections
+            {
+                bytes.extend_from_slice(format!("{}{}]\n", TEXT_SECTION, name).as_bytes());
+                bytes.extend_from_slice(section);
+            }
+        }
+    }
+    bytes
+}
+
+fn unpack
+(
+    bytes: Vec<u8>
+)
+-> Result<HashMap<String, Vec<u8>>, SaveError>
+{
+    let mut sections = HashMap::new();
+
+    if bytes.starts_with(BINARY_MAGIC)
+    {
+        let mut cursor = BINARY_MAGIC.len();
+        let mut take = |size: usize| -> Result<&[u8], SaveError>
+        {
+            let end = cursor.checked_add(size).filter(|end| *end <= bytes.len()).ok_or_else(|| SaveError::Corrupt("Unexpected end of file.".to_string()))?;
+            let taken = &bytes[cursor..end];
+            cursor = end;
+            Ok(taken)
+        };
+
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        for _ in 0..count
+        {
+            let name_length = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let name = String::from_utf8(take(name_length)?.to_vec()).map_err(|_| SaveError::Corrupt("Section name is not UTF-8.".to_string()))?;
+            let section_length = usize::try_from(u64::from_le_bytes(take(8)?.try_into().unwrap())).map_err(|_| SaveError::Corrupt("Section is too large.".to_string()))?;
+            sections.insert(name, take(section_length)?.to_vec());
+        }
+    }
+    else
+    {
+        let text = String::from_utf8(bytes).map_err(|_| SaveError::Corrupt("Neither binary nor text.".to_string()))?;
+        let mut current: Option<(String, String)> = None;
+        for line in text.lines()
+        {
+            match line.strip_prefix(TEXT_SECTION).and_then(|name| name.strip_suffix(']'))
+            {
+                Some(name) =>
+                {
+                    if let Some((name, section)) = current.take()
+                    {
+                        sections.insert(name, section.into_bytes());
+                    }
+                    current = Some((name.to_string(), String::new()));
+                }
+                None => match &mut current
+                {
+                    Some((_, section)) =>
+                    {
+                        *section += line;
+                        *section += "\n";
+                    }
+                    None => return Err(SaveError::Corrupt("Text before the first section.".to_string()))
+                }
+            }
+        }
+
+        if let Some((name, section)) = current
+        {
+            sections.insert(name, section.into_bytes());
+        }
+    }
+
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{Arguments, ConsoleCommand, Handle, Storage};
+
+    /// Owns a storage, its section comes first.
+    struct Owner
+    {
+        things: Storage<u32>
+    }
+
+    /// Holds handles into the storage of [Owner], fails to load when told to.
+    struct Holder
+    {
+        held: Vec<Handle<u32>>,
+        fail: bool
+    }
+
+    impl SystemEvents
+    for Owner
+    {
+        fn name
+        (
+            &self
+        )
+        -> &'static str
+        {
+            "owner"
+        }
+
+        fn commands
+        (
+            &self
+        )
+        -> Vec<ConsoleCommand>
+        {
+            Vec::new()
+        }
+
+        fn console
+        (
+            &mut self,
+            _framework: &mut Framework,
+            _command: &ConsoleCommand
+        )
+        {
+        }
+
+        fn save_load
+        (
+            &mut self,
+            _framework: &mut Framework,
+            section: SaveSection
+        )
+        -> Result<(), SerializeError>
+        {
+            match section
+            {
+                SaveSection::Save(serializer) => serializer.field("things", &self.things),
+                SaveSection::Load(deserializer, staged) => staged.stage(deserializer.field::<Storage<u32>>("things")?),
+                SaveSection::Commit(staged) => self.things = staged.take().unwrap()
+            }
+            Ok(())
+        }
+    }
+
+    impl SystemEvents
+    for Holder
+    {
+        fn name
+        (
+            &self
+        )
+        -> &'static str
+        {
+            "holder"
+        }
+
+        fn commands
+        (
+            &self
+        )
+        -> Vec<ConsoleCommand>
+        {
+            Vec::new()
+        }
+
+        fn console
+        (
+            &mut self,
+            _framework: &mut Framework,
+            _command: &ConsoleCommand
+        )
+        {
+        }
+
+        fn save_load
+        (
+            &mut self,
+            _framework: &mut Framework,
+            section: SaveSection
+        )
+        -> Result<(), SerializeError>
+        {
+            match section
+            {
+                SaveSection::Save(serializer) => serializer.field("held", &self.held),
+                SaveSection::Load(_, _) if self.fail => return Err(SerializeError::UnexpectedEnd),
+                SaveSection::Load(deserializer, staged) => staged.stage(deserializer.field::<Vec<Handle<u32>>>("held")?),
+                SaveSection::Commit(staged) => self.held = staged.take().unwrap()
+            }
+            Ok(())
+        }
+    }
+
+    fn session
+    (
+        session: Result<SaveSession, SaveError>,
+        owner: &mut Owner,
+        holder: &mut Holder,
+        framework: &mut Framework
+    )
+    -> Result<(), Vec<SaveError>>
+    {
+        let mut session = session.unwrap();
+        session.system(owner, framework);
+        session.system(holder, framework);
+        session.commit(owner, framework);
+        session.commit(holder, framework);
+        session.finish()
+    }
+
+    fn sections
+    ()
+    -> Vec<(String, Vec<u8>)>
+    {
+        vec![("first".to_string(), b"one\n".to_vec()), ("second".to_string(), b"two\nlines\n".to_vec())]
+    }
+
+    #[test]
+    fn pack_round_trip
+    ()
+    {
+        for format in [Format::Binary, Format::Text]
+        {
+            let unpacked = unpack(pack(format, &sections())).unwrap();
+            assert_eq!(unpacked.len(), 2);
+            for (name, section) in sections()
+            {
+                assert_eq!(unpacked[&name], section);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated
+    ()
+    {
+        let bytes = pack(Format::Binary, &sections());
+        for length in BINARY_MAGIC.len()..bytes.len()
+        {
+            assert!(matches!(unpack(bytes[..length].to_vec()), Err(SaveError::Corrupt(_))));
+        }
+
+        // A section length beyond the end of the file, also one that overflows.
+        for length in [1000, u64::MAX]
+        {
+            let mut bytes = BINARY_MAGIC.to_vec();
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+            bytes.extend_from_slice(b"a");
+            bytes.extend_from_slice(&length.to_le_bytes());
+            assert!(matches!(unpack(bytes), Err(SaveError::Corrupt(_))));
+        }
+    }
+
+    #[test]
+    fn header
+    ()
+    {
+        let directory = crate::test_directory("save");
+        let games = SaveGames::new(directory.clone(), "test", "1");
+        assert!(games.begin_save("slot").unwrap().finish().is_ok());
+        assert_eq!(games.slots(), vec!["slot".to_string()]);
+        assert!(games.begin_load("slot").is_ok());
+        assert!(matches!(games.begin_load("missing"), Err(SaveError::MissingSlot(_))));
+
+        let other = SaveGames::new(directory.clone(), "other", "1");
+        assert!(matches!(other.begin_load("slot"), Err(SaveError::WrongApplication { .. })));
+        let newer = SaveGames::new(directory, "test", "2");
+        assert!(matches!(newer.begin_load("slot"), Err(SaveError::VersionMismatch { .. })));
+    }
+
+    #[test]
+    fn sessions
+    ()
+    {
+        let directory = crate::test_directory("save-sessions");
+        let arguments = Arguments { asset_path: Some(directory.join("import")), user_path: Some(directory.join("user")), ..Arguments::default() };
+        let mut framework = Framework::headless("nokden-save-test", "1", arguments).unwrap();
+        let games = SaveGames::new(directory.join("saves"), "test", "1");
+
+        let mut owner = Owner { things: Storage::new() };
+        let removed = owner.things.add(1);
+        let two = owner.things.add(2);
+        let three = owner.things.add(3);
+        owner.things.remove(removed);
+        let mut holder = Holder { held: vec![three, two], fail: false };
+        assert!(session(games.begin_save("slot"), &mut owner, &mut holder, &mut framework).is_ok());
+
+        owner.things = Storage::new();
+        owner.things.add(9);
+        holder.held.clear();
+
+        // One section failing leaves every system as it was, the ones that loaded fine included.
+        holder.fail = true;
+        let errors = session(games.begin_load("slot"), &mut owner, &mut holder, &mut framework).unwrap_err();
+        assert!(matches!(errors.as_slice(), [SaveError::Serialize { section, .. }] if section == "holder"));
+        assert_eq!(owner.things.len(), 1);
+        assert_eq!(*owner.things.iter().next().unwrap().read().unwrap(), 9);
+        assert!(holder.held.is_empty());
+
+        // Handles of one section point into the storage loaded by another.
+        holder.fail = false;
+        assert!(session(games.begin_load("slot"), &mut owner, &mut holder, &mut framework).is_ok());
+        assert_eq!(owner.things.len(), 2);
+        assert_eq!(*owner.things.read(&holder.held[0]).unwrap(), 3);
+        assert_eq!(*owner.things.read(&holder.held[1]).unwrap(), 2);
+    }
+
+    #[test]
+    fn slot_names
+    ()
+    {
+        let directory = crate::test_directory("save-slots");
+        let games = SaveGames::new(directory.join("saves"), "test", "1");
+        for slot in ["", "../outside", "..", "a/b", "a\\b", "/tmp/absolute"]
+        {
+            assert!(matches!(games.begin_save(slot), Err(SaveError::InvalidSlot(_))), "{}", slot);
+            assert!(matches!(games.begin_load(slot), Err(SaveError::InvalidSlot(_))), "{}", slot);
+        }
+        assert!(games.begin_save("quick save 2").is_ok());
+        assert!(!directory.join("outside.sav").exists());
+    }
+}
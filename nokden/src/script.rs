@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+use std::{fmt, fs, io};
+use std::path::{Path, PathBuf};
+use crate::console::split_commands;
+
+pub const SCRIPT_EXTENSION: &str = "cfg";
+pub const AUTOEXEC_FILE: &str = "autoexec.cfg";
+
+/// Scripts executing scripts deeper than this are stopped, it is most likely a loop.
+const MAX_DEPTH: u32 = 16;
+
+/// Aliases running each other deeper than this are stopped, it is most likely a loop.
+const MAX_ALIAS_DEPTH: u32 = 16;
+
+struct ScriptLine
+{
+    line: String,
+    depth: u32,
+
+    /// Aliases this line was expanded from.
+    aliases: u32
+}
+
+/// Console commands waiting to be issued, in order each frame until a `wait`.
+/// Every command goes through here, typed, bound or from scripts, so a `wait` holds back all that follows it.
+pub struct Script
+{
+    lines: VecDeque<ScriptLine>,
+    wait: u32,
+
+    /// Depth of the line issued last, files it executes go one deeper.
+    depth: u32,
+
+    /// Aliases the line issued last was expanded from.
+    aliases: u32
+}
+
+impl Default for Script
+{
+    fn default
+    ()
+    -> Script
+    {
+        Script::new()
+    }
+}
+
+impl Script
+{
+    pub fn new
+    ()
+    -> Script
+    {
+        Script
+        {
+            lines: VecDeque::new(),
+            wait: 0,
+            depth: 0,
+            aliases: 0
+        }
+    }
+
+    /// Lines of a file go before everything queued, so a script finishes before the one that executed it continues.
+    /// Empty lines and lines starting with `#` or `//` are skipped.
+    pub fn exec
+    (
+        &mut self,
+        path: &Path
+    )
+    -> Result<(), ScriptError>
+    {
+        let depth = self.depth + 1;
+        if depth > MAX_DEPTH
+        {
+            return Err(ScriptError::TooDeep(path.to_path_buf()));
+        }
+
+        let text = fs::read_to_string(path).map_err(|err| ScriptError::Io(path.to_path_buf(), err))?;
+        let lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"));
+
+        for line in lines.collect::<Vec<&str>>().into_iter().rev()
+        {
+            self.lines.push_front(ScriptLine { line: line.to_string(), depth, aliases: 0 });
+        }
+
+        Ok(())
+    }
+
+    /// Queues a line after everything else, several commands can be separated by `;`.
+    pub fn push
+    (
+        &mut self,
+        line: &str
+    )
+    {
+        self.lines.push_back(ScriptLine { line: line.to_string(), depth: 0, aliases: 0 });
+    }
+
+    /// Commands of an alias go before everything queued, in place of the alias.
+    pub fn alias
+    (
+        &mut self,
+        name: &str,
+        commands: &str
+    )
+    -> Result<(), ScriptError>
+    {
+        let aliases = self.aliases + 1;
+        if aliases > MAX_ALIAS_DEPTH
+        {
+            return Err(ScriptError::AliasTooDeep(name.to_string()));
+        }
+
+        self.lines.push_front(ScriptLine { line: commands.to_string(), depth: self.depth, aliases });
+        Ok(())
+    }
+
+    /// Holds back the remaining lines for a number of frames, including lines queued meanwhile.
+    pub fn wait
+    (
+        &mut self,
+        frames: u32
+    )
+    {
+        self.wait = frames;
+        self.depth = 0;
+    }
+
+    pub fn is_empty
+    (
+        &self
+    )
+    -> bool
+    {
+        self.lines.is_empty()
+    }
+
+    /// Counts down a wait, true while waiting.
+    pub(crate) fn waiting
+    (
+        &mut self
+    )
+    -> bool
+    {
+        match self.wait
+        {
+            0 => false,
+            _ =>
+            {
+                self.wait -= 1;
+                true
+            }
+        }
+    }
+
+    /// Next command to issue this frame, lines are split at `;` so the commands after a `wait` stay queued.
+    pub(crate) fn next
+    (
+        &mut self
+    )
+    -> Option<String>
+    {
+        while self.wait == 0
+        {
+            let line = match self.lines.pop_front()
+            {
+                Some(line) => line,
+                None =>
+                {
+                    self.depth = 0;
+                    self.aliases = 0;
+                    return None;
+                }
+            };
+            self.depth = line.depth;
+            self.aliases = line.aliases;
+
+            let mut commands = split_commands(&line.line);
+            if commands.is_empty()
+            {
+                continue;
+            }
+
+            let first = commands.remove(0);
+            for command in commands.into_iter().rev()
+            {
+                self.lines.push_front(ScriptLine { line: command, depth: line.depth, aliases: line.aliases });
+            }
+            return Some(first);
+        }
+
+        None
+    }
+
+    /// Files without an extension get the one of scripts.
+    pub fn path
+    (
+        directory: &Path,
+        file: &str
+    )
+    -> PathBuf
+    {
+        let mut path = directory.join(file);
+        if path.extension().is_none()
+        {
+            path.set_extension(SCRIPT_EXTENSION);
+        }
+        path
+    }
+}
+
+#[derive(Debug)]
+pub enum ScriptError
+{
+    Io(PathBuf, io::Error),
+    TooDeep(PathBuf),
+    AliasTooDeep(String)
+}
+
+impl fmt::Display for ScriptError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            ScriptError::Io(path, err) => write!(f, "Script {} not read: {}", path.to_string_lossy(), err),
+            ScriptError::TooDeep(path) => write!(f, "Script {} not executed, scripts execute each other more than {} deep.", path.to_string_lossy(), MAX_DEPTH),
+            ScriptError::AliasTooDeep(name) => write!(f, "Alias '{}' not run, aliases run each other more than {} deep.", name, MAX_ALIAS_DEPTH)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn order_and_depth
+    ()
+    {
+        let directory = crate::test_directory("script");
+        let path = directory.join("inner.cfg");
+        fs::write(&path, "# comment\nfirst\n\nwait 1\nsecond\n").unwrap();
+
+        let mut script = Script::new();
+        script.push("after");
+        script.exec(&path).unwrap();
+        assert_eq!(script.next().as_deref(), Some("first"));
+        assert_eq!(script.next().as_deref(), Some("wait 1"));
+
+        // Waiting, a command typed meanwhile executes at the top level again.
+        script.wait(1);
+        assert_eq!(script.depth, 0);
+        assert!(script.next().is_none());
+        assert!(script.waiting());
+        assert!(!script.waiting());
+        assert_eq!(script.next().as_deref(), Some("second"));
+        assert_eq!(script.depth, 1);
+        assert_eq!(script.next().as_deref(), Some("after"));
+        assert!(script.next().is_none());
+    }
+
+    #[test]
+    fn too_deep
+    ()
+    {
+        let directory = crate::test_directory("script-deep");
+        let path = directory.join("loop.cfg");
+        fs::write(&path, "exec loop\n").unwrap();
+
+        let mut script = Script::new();
+        let mut result = Ok(());
+        while result.is_ok()
+        {
+            result = script.exec(&path);
+            script.next();
+        }
+        assert!(matches!(result, Err(ScriptError::TooDeep(_))));
+    }
+
+    #[test]
+    fn split_and_alias
+    ()
+    {
+        let mut script = Script::new();
+        script.push("first; wait 1; \"a; b\"");
+        script.push(";;");
+        script.push("jump");
+        assert_eq!(script.next().as_deref(), Some("first"));
+        assert_eq!(script.next().as_deref(), Some("wait 1"));
+        script.wait(1);
+        assert!(script.next().is_none());
+        assert!(script.waiting());
+        assert_eq!(script.next().as_deref(), Some("\"a; b\""));
+
+        // The commands of the alias go in its place.
+        assert_eq!(script.next().as_deref(), Some("jump"));
+        script.alias("jump", "up; wait 2; down").unwrap();
+        script.push("after");
+        assert_eq!(script.next().as_deref(), Some("up"));
+        assert_eq!(script.next().as_deref(), Some("wait 2"));
+        assert_eq!(script.next().as_deref(), Some("down"));
+        assert_eq!(script.next().as_deref(), Some("after"));
+        assert!(script.next().is_none());
+
+        // An alias running itself.
+        script.push("loop");
+        let mut result = Ok(());
+        while result.is_ok()
+        {
+            assert_eq!(script.next().as_deref(), Some("loop"));
+            result = script.alias("loop", "loop");
+        }
+        assert!(matches!(result, Err(ScriptError::AliasTooDeep(_))));
+        assert!(script.next().is_none());
+    }
+}
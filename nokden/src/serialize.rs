@@ -0,0 +1,1222 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem::{self, size_of};
+use std::path::Path;
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+
+use crate::{Handle, Storage, StorageKeys};
+
+/// Counted when the layout of the file header changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+const BINARY_MAGIC: &[u8] = b"NOKD";
+const TEXT_MAGIC: &str = "nokden";
+
+/// Label used for values that are not written through [Serializer::field].
+const DEFAULT_LABEL: &str = "value";
+
+/// Per component type, maps the saved handle index to a handle of the loaded or bound storage.
+pub(crate) type Remaps = HashMap<TypeId, Box<dyn Any>>;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format
+{
+    /// Compact little endian, labels are not written.
+    Binary,
+
+    /// One labeled value per line, meant to be read and edited by hand.
+    Text
+}
+
+#[derive(Debug)]
+pub enum SerializeError
+{
+    Io(io::Error),
+
+    /// The file does not start with a binary or text header.
+    NotNokden,
+    Version
+    {
+        found: u32,
+        expected: u32
+    },
+    UnexpectedEnd,
+    Label
+    {
+        expected: String,
+        found: String
+    },
+    Parse
+    {
+        label: String,
+        value: String
+    },
+
+    /// A handle points to an entry that was not part of the loaded storage.
+    DanglingHandle
+    {
+        component: &'static str,
+        index: usize
+    },
+
+    /// A handle was loaded before its storage was loaded or bound.
+    MissingStorage(&'static str)
+}
+
+impl fmt::Display for SerializeError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            SerializeError::Io(err) => write!(f, "{}", err),
+            SerializeError::NotNokden => write!(f, "Not a nokden file."),
+            SerializeError::Version { found, expected } => write!(f, "File version {} is not supported, expected {}.", found, expected),
+            SerializeError::UnexpectedEnd => write!(f, "Unexpected end of data."),
+            SerializeError::Label { expected, found } => write!(f, "Expected '{}' but found '{}'.", expected, found),
+            SerializeError::Parse { label, value } => write!(f, "Value '{}' of '{}' can not be parsed.", value, label),
+            SerializeError::DanglingHandle { component, index } => write!(f, "Handle to {} at index {} points to nothing.", component, index),
+            SerializeError::MissingStorage(component) => write!(f, "Storage of {} needs to be loaded or bound before its handles.", component)
+        }
+    }
+}
+
+impl From<io::Error> for SerializeError
+{
+    fn from
+    (
+        err: io::Error
+    )
+    -> SerializeError
+    {
+        SerializeError::Io(err)
+    }
+}
+
+pub trait Serialize
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    );
+}
+
+pub trait Deserialize: Sized
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Self, SerializeError>;
+}
+
+/// Loads into an existing value instead of making a new one.
+pub trait DeserializeInPlace
+{
+    fn deserialize_in_place
+    (
+        &mut self,
+        deserializer: &mut Deserializer
+    )
+    -> Result<(), SerializeError>;
+}
+
+pub struct Serializer
+{
+    format: Format,
+    binary: Vec<u8>,
+    text: String,
+    depth: usize,
+    label: Option<String>
+}
+
+impl Serializer
+{
+    pub fn new
+    (
+        format: Format
+    )
+    -> Serializer
+    {
+        Serializer
+        {
+            format,
+            binary: Vec::new(),
+            text: String::new(),
+            depth: 0,
+            label: None
+        }
+    }
+
+    pub fn format
+    (
+        &self
+    )
+    -> Format
+    {
+        self.format
+    }
+
+    pub fn field
+    <
+        T: Serialize + ?Sized
+    >
+    (
+        &mut self,
+        label: &str,
+        value: &T
+    )
+    {
+        self.label = Some(label.to_string());
+        value.serialize(self);
+    }
+
+    /// Label of the next value, for values not written through [Serializer::field].
+    pub fn label
+    (
+        &mut self,
+        label: &str
+    )
+    {
+        self.label = Some(label.to_string());
+    }
+
+    /// Opens a group of fields, closed by [Serializer::end].
+    pub fn begin
+    (
+        &mut self
+    )
+    {
+        if self.format == Format::Text
+        {
+            let line = format!("{} {{", self.take_label());
+            self.line(&line);
+            self.depth += 1;
+        }
+        else
+        {
+            self.label = None;
+        }
+    }
+
+    pub fn end
+    (
+        &mut self
+    )
+    {
+        if self.format == Format::Text
+        {
+            self.depth -= 1;
+            self.line("}");
+        }
+    }
+
+    /// Writes a single value, [text] is used by the text format and [bytes] by the binary format.
+    pub fn scalar
+    (
+        &mut self,
+        text: &str,
+        bytes: &[u8]
+    )
+    {
+        match self.format
+        {
+            Format::Binary =>
+            {
+                self.label = None;
+                self.binary.extend_from_slice(bytes);
+            }
+            Format::Text =>
+            {
+                let line = format!("{} = {}", self.take_label(), text);
+                self.line(&line);
+            }
+        }
+    }
+
+    /// Header followed by everything serialized so far.
+    pub fn finish
+    (
+        self
+    )
+    -> Vec<u8>
+    {
+        match self.format
+        {
+            Format::Binary =>
+            {
+                let mut bytes = BINARY_MAGIC.to_vec();
+                bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+                bytes.extend_from_slice(&self.binary);
+                bytes
+            }
+            Format::Text => format!("{} {}\n{}", TEXT_MAGIC, FORMAT_VERSION, self.text).into_bytes()
+        }
+    }
+
+    pub fn write_file
+    (
+        self,
+        path: &Path
+    )
+    -> Result<(), SerializeError>
+    {
+        fs::write(path, self.finish())?;
+        Ok(())
+    }
+
+    fn take_label
+    (
+        &mut self
+    )
+    -> String
+    {
+        self.label.take().unwrap_or_else(|| DEFAULT_LABEL.to_string())
+    }
+
+    fn line
+    (
+        &mut self,
+        line: &str
+    )
+    {
+        for _ in 0..self.depth
+        {
+            self.text += "    ";
+        }
+        self.text += line;
+        self.text += "\n";
+    }
+}
+
+enum TextLine
+{
+    Scalar(String, String),
+    Begin(String),
+    End
+}
+
+pub struct Deserializer
+{
+    format: Format,
+    binary: Vec<u8>,
+    binary_cursor: usize,
+    text: Vec<TextLine>,
+    text_cursor: usize,
+    label: Option<String>,
+    remaps: Remaps
+}
+
+impl Deserializer
+{
+    /// Detects the format from the header.
+    pub fn new
+    (
+        bytes: Vec<u8>
+    )
+    -> Result<Deserializer, SerializeError>
+    {
+        let mut deserializer = Deserializer
+        {
+            format: Format::Binary,
+            binary: Vec::new(),
+            binary_cursor: 0,
+            text: Vec::new(),
+            text_cursor: 0,
+            label: None,
+            remaps: HashMap::new()
+        };
+
+        if bytes.starts_with(BINARY_MAGIC)
+        {
+            deserializer.binary = bytes;
+            deserializer.binary_cursor = BINARY_MAGIC.len();
+            let version = u32::deserialize(&mut deserializer)?;
+            Self::check_version(version)?;
+        }
+        else if bytes.starts_with(TEXT_MAGIC.as_bytes())
+        {
+            let text = String::from_utf8(bytes).map_err(|_| SerializeError::NotNokden)?;
+            let mut lines = text.lines();
+            let version = lines.next()
+                .and_then(|header| header[TEXT_MAGIC.len()..].trim().parse::<u32>().ok())
+                .ok_or(SerializeError::NotNokden)?;
+            Self::check_version(version)?;
+
+            deserializer.format = Format::Text;
+            for line in lines
+            {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#')
+                {
+                    continue;
+                }
+
+                deserializer.text.push
+                (
+                    if line == "}"
+                    {
+                        TextLine::End
+                    }
+                    else if let Some(label) = line.strip_suffix('{')
+                    {
+                        TextLine::Begin(label.trim().to_string())
+                    }
+                    else
+                    {
+                        match line.split_once('=')
+                        {
+                            Some((label, value)) => TextLine::Scalar(label.trim().to_string(), value.trim().to_string()),
+                            None => return Err(SerializeError::Parse { label: String::new(), value: line.to_string() })
+                        }
+                    }
+                );
+            }
+        }
+        else
+        {
+            return Err(SerializeError::NotNokden);
+        }
+
+        Ok(deserializer)
+    }
+
+    pub fn read_file
+    (
+        path: &Path
+    )
+    -> Result<Deserializer, SerializeError>
+    {
+        Self::new(fs::read(path)?)
+    }
+
+    pub fn format
+    (
+        &self
+    )
+    -> Format
+    {
+        self.format
+    }
+
+    pub fn field
+    <
+        T: Deserialize
+    >
+    (
+        &mut self,
+        label: &str
+    )
+    -> Result<T, SerializeError>
+    {
+        self.label = Some(label.to_string());
+        T::deserialize(self)
+    }
+
+    pub fn field_in_place
+    <
+        T: DeserializeInPlace
+    >
+    (
+        &mut self,
+        label: &str,
+        value: &mut T
+    )
+    -> Result<(), SerializeError>
+    {
+        self.label = Some(label.to_string());
+        value.deserialize_in_place(self)
+    }
+
+    /// Label of the next value, for values not read through [Deserializer::field].
+    pub fn label
+    (
+        &mut self,
+        label: &str
+    )
+    {
+        self.label = Some(label.to_string());
+    }
+
+    pub fn begin
+    (
+        &mut self
+    )
+    -> Result<(), SerializeError>
+    {
+        let expected = self.take_label();
+        if self.format == Format::Text
+        {
+            match self.text.get(self.text_cursor)
+            {
+                Some(TextLine::Begin(label)) if *label == expected => self.text_cursor += 1,
+                Some(line) => return Err(SerializeError::Label { expected: format!("{} {{", expected), found: Self::describe(line) }),
+                None => return Err(SerializeError::UnexpectedEnd)
+            }
+        }
+        Ok(())
+    }
+
+    pub fn end
+    (
+        &mut self
+    )
+    -> Result<(), SerializeError>
+    {
+        if self.format == Format::Text
+        {
+            match self.text.get(self.text_cursor)
+            {
+                Some(TextLine::End) => self.text_cursor += 1,
+                Some(line) => return Err(SerializeError::Label { expected: "}".to_string(), found: Self::describe(line) }),
+                None => return Err(SerializeError::UnexpectedEnd)
+            }
+        }
+        Ok(())
+    }
+
+    /// Next [size] bytes of the binary format.
+    pub fn bytes
+    (
+        &mut self,
+        size: usize
+    )
+    -> Result<&[u8], SerializeError>
+    {
+        self.label = None;
+        let start = self.binary_cursor;
+        match start.checked_add(size).and_then(|end| self.binary.get(start..end))
+        {
+            Some(bytes) =>
+            {
+                self.binary_cursor += size;
+                Ok(bytes)
+            }
+            None => Err(SerializeError::UnexpectedEnd)
+        }
+    }
+
+    /// Next value of the text format, with its label checked.
+    pub fn text
+    (
+        &mut self
+    )
+    -> Result<(String, String), SerializeError>
+    {
+        let expected = self.take_label();
+        match self.text.get(self.text_cursor)
+        {
+            Some(TextLine::Scalar(label, value)) if *label == expected =>
+            {
+                self.text_cursor += 1;
+                Ok((label.clone(), value.clone()))
+            }
+            Some(line) => Err(SerializeError::Label { expected, found: Self::describe(line) }),
+            None => Err(SerializeError::UnexpectedEnd)
+        }
+    }
+
+    /// Makes handles to an existing storage loadable without loading the storage itself.
+    /// For storages that are rebuilt the same way on every start, like GPU assets.
+    pub fn bind_storage
+    <
+        TComponent: 'static
+    >
+    (
+        &mut self,
+        storage: &Storage<TComponent>
+    )
+    {
+        self.remaps.insert(TypeId::of::<TComponent>(), Box::new(storage.handles()));
+    }
+
+    /// Same as [Deserializer::bind_storage] for storages that are rebuilt in any order, handles are matched by the key of their entry.
+    /// Handles to saved entries whose key is no longer in the storage fail with [SerializeError::DanglingHandle].
+    pub fn bind_storage_by_key
+    <
+        TComponent: 'static,
+        TKey: PartialEq
+    >
+    (
+        &mut self,
+        storage: &Storage<TComponent>,
+        saved: &StorageKeys<TKey>,
+        key: impl Fn(&TComponent) -> TKey
+    )
+    {
+        let live: Vec<(TKey, Handle<TComponent>)> = storage.handles().into_values()
+            .filter_map(|handle| storage.read(&handle).map(|data| key(&data)).map(|key| (key, handle)))
+            .collect();
+
+        let mut remap = HashMap::new();
+        for (index, saved_key) in saved.keys()
+        {
+            if let Some((_, handle)) = live.iter().find(|(key, _)| key == saved_key)
+            {
+                remap.insert(*index, handle.clone());
+            }
+        }
+        self.remap(remap);
+    }
+
+    pub(crate) fn set_remaps
+    (
+        &mut self,
+        remaps: Remaps
+    )
+    {
+        self.remaps = remaps;
+    }
+
+    pub(crate) fn take_remaps
+    (
+        &mut self
+    )
+    -> Remaps
+    {
+        mem::take(&mut self.remaps)
+    }
+
+    pub(crate) fn remap
+    <
+        TComponent: 'static
+    >
+    (
+        &mut self,
+        remap: HashMap<usize, Handle<TComponent>>
+    )
+    {
+        self.remaps.insert(TypeId::of::<TComponent>(), Box::new(remap));
+    }
+
+    fn take_label
+    (
+        &mut self
+    )
+    -> String
+    {
+        self.label.take().unwrap_or_else(|| DEFAULT_LABEL.to_string())
+    }
+
+    fn check_version
+    (
+        version: u32
+    )
+    -> Result<(), SerializeError>
+    {
+        match version == FORMAT_VERSION
+        {
+            true => Ok(()),
+            false => Err(SerializeError::Version { found: version, expected: FORMAT_VERSION })
+        }
+    }
+
+    fn describe
+    (
+        line: &TextLine
+    )
+    -> String
+    {
+        match line
+        {
+            TextLine::Scalar(label, value) => format!("{} = {}", label, value),
+            TextLine::Begin(label) => format!("{} {{", label),
+            TextLine::End => "}".to_string()
+        }
+    }
+}
+
+macro_rules! serialize_number
+{
+    (
+        $($number:ty),*
+    )
+    =>
+    {
+        $(
+            impl Serialize for $number
+            {
+                fn serialize
+                (
+                    &self,
+                    serializer: &mut Serializer
+                )
+                {
+                    serializer.scalar(&self.to_string(), &self.to_le_bytes());
+                }
+            }
+
+            impl Deserialize for $number
+            {
+                fn deserialize
+                (
+                    deserializer: &mut Deserializer
+                )
+                -> Result<$number, SerializeError>
+                {
+                    match deserializer.format
+                    {
+                        Format::Binary =>
+                        {
+                            let mut bytes = [0; size_of::<$number>()];
+                            bytes.copy_from_slice(deserializer.bytes(size_of::<$number>())?);
+                            Ok(<$number>::from_le_bytes(bytes))
+                        }
+                        Format::Text =>
+                        {
+                            let (label, value) = deserializer.text()?;
+                            value.parse().map_err(|_| SerializeError::Parse { label, value })
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+serialize_number!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Serialize for usize
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        (*self as u64).serialize(serializer);
+    }
+}
+
+impl Deserialize for usize
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<usize, SerializeError>
+    {
+        Ok(u64::deserialize(deserializer)? as usize)
+    }
+}
+
+impl Serialize for bool
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.scalar(&self.to_string(), &[*self as u8]);
+    }
+}
+
+impl Deserialize for bool
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<bool, SerializeError>
+    {
+        match deserializer.format
+        {
+            Format::Binary => Ok(deserializer.bytes(1)?[0] != 0),
+            Format::Text =>
+            {
+                let (label, value) = deserializer.text()?;
+                value.parse().map_err(|_| SerializeError::Parse { label, value })
+            }
+        }
+    }
+}
+
+impl Serialize for str
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        match serializer.format
+        {
+            Format::Binary =>
+            {
+                (self.len() as u64).serialize(serializer);
+                serializer.scalar("", self.as_bytes());
+            }
+            Format::Text =>
+            {
+                let mut quoted = String::from("\"");
+                for character in self.chars()
+                {
+                    match character
+                    {
+                        '"' => quoted += "\\\"",
+                        '\\' => quoted += "\\\\",
+                        '\n' => quoted += "\\n",
+                        character => quoted.push(character)
+                    }
+                }
+                quoted.push('"');
+                serializer.scalar(&quoted, &[]);
+            }
+        }
+    }
+}
+
+impl Serialize for String
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        self.as_str().serialize(serializer);
+    }
+}
+
+impl Deserialize for String
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<String, SerializeError>
+    {
+        match deserializer.format
+        {
+            Format::Binary =>
+            {
+                let length = u64::deserialize(deserializer)? as usize;
+                let bytes = deserializer.bytes(length)?.to_vec();
+                String::from_utf8(bytes).map_err(|err| SerializeError::Parse { label: DEFAULT_LABEL.to_string(), value: err.to_string() })
+            }
+            Format::Text =>
+            {
+                let (label, value) = deserializer.text()?;
+                let inner = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"'))
+                {
+                    Some(inner) => inner,
+                    None => return Err(SerializeError::Parse { label, value })
+                };
+
+                let mut unquoted = String::new();
+                let mut characters = inner.chars();
+                while let Some(character) = characters.next()
+                {
+                    match character
+                    {
+                        '\\' => match characters.next()
+                        {
+                            Some('n') => unquoted.push('\n'),
+                            Some(escaped) => unquoted.push(escaped),
+                            None => return Err(SerializeError::Parse { label, value })
+                        },
+                        character => unquoted.push(character)
+                    }
+                }
+                Ok(unquoted)
+            }
+        }
+    }
+}
+
+impl
+<
+    T: Serialize
+>
+Serialize for Option<T>
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("some", &self.is_some());
+        if let Some(value) = self
+        {
+            serializer.field("value", value);
+        }
+        serializer.end();
+    }
+}
+
+impl
+<
+    T: Deserialize
+>
+Deserialize for Option<T>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Option<T>, SerializeError>
+    {
+        deserializer.begin()?;
+        let value = match deserializer.field::<bool>("some")?
+        {
+            true => Some(deserializer.field("value")?),
+            false => None
+        };
+        deserializer.end()?;
+        Ok(value)
+    }
+}
+
+impl
+<
+    T: Serialize
+>
+Serialize for Vec<T>
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        serializer.field("count", &self.len());
+        for item in self
+        {
+            serializer.field("item", item);
+        }
+        serializer.end();
+    }
+}
+
+impl
+<
+    T: Deserialize
+>
+Deserialize for Vec<T>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Vec<T>, SerializeError>
+    {
+        deserializer.begin()?;
+        // Grown item by item, the count comes from the file and a corrupt one runs out of data first.
+        let count: usize = deserializer.field("count")?;
+        let mut items = Vec::new();
+        for _ in 0..count
+        {
+            items.push(deserializer.field("item")?);
+        }
+        deserializer.end()?;
+        Ok(items)
+    }
+}
+
+impl
+<
+    T: Serialize,
+    const N: usize
+>
+Serialize for [T; N]
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        serializer.begin();
+        for item in self
+        {
+            serializer.field("item", item);
+        }
+        serializer.end();
+    }
+}
+
+impl
+<
+    T: Deserialize + Copy + Default,
+    const N: usize
+>
+Deserialize for [T; N]
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<[T; N], SerializeError>
+    {
+        deserializer.begin()?;
+        let mut items = [T::default(); N];
+        for item in items.iter_mut()
+        {
+            *item = deserializer.field("item")?;
+        }
+        deserializer.end()?;
+        Ok(items)
+    }
+}
+
+impl Serialize for Isometry3<f32>
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        let translation = self.translation.vector;
+        let rotation = self.rotation.coords;
+
+        serializer.begin();
+        serializer.field("translation", &[translation[0], translation[1], translation[2]]);
+        serializer.field("rotation", &[rotation[0], rotation[1], rotation[2], rotation[3]]);
+        serializer.end();
+    }
+}
+
+impl Deserialize for Isometry3<f32>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Isometry3<f32>, SerializeError>
+    {
+        deserializer.begin()?;
+        let translation: [f32; 3] = deserializer.field("translation")?;
+        let rotation: [f32; 4] = deserializer.field("rotation")?;
+        deserializer.end()?;
+
+        Ok
+        (
+            Isometry3::from_parts
+            (
+                Translation3::new(translation[0], translation[1], translation[2]),
+                UnitQuaternion::from_quaternion(Quaternion::new(rotation[3], rotation[0], rotation[1], rotation[2]))
+            )
+        )
+    }
+}
+
+/// Only the index is written, generations start over on load.
+impl
+<
+    TComponent
+>
+Serialize for Handle<TComponent>
+{
+    fn serialize
+    (
+        &self,
+        serializer: &mut Serializer
+    )
+    {
+        self.index.serialize(serializer);
+    }
+}
+
+/// Needs the storage of [TComponent] to be loaded or bound first on the same deserializer.
+impl
+<
+    TComponent: 'static
+>
+Deserialize for Handle<TComponent>
+{
+    fn deserialize
+    (
+        deserializer: &mut Deserializer
+    )
+    -> Result<Handle<TComponent>, SerializeError>
+    {
+        let index = usize::deserialize(deserializer)?;
+        let remap = deserializer.remaps.get(&TypeId::of::<TComponent>())
+            .and_then(|remap| remap.downcast_ref::<HashMap<usize, Handle<TComponent>>>())
+            .ok_or(SerializeError::MissingStorage(std::any::type_name::<TComponent>()))?;
+
+        match remap.get(&index)
+        {
+            Some(handle) => Ok(handle.clone()),
+            None => Err(SerializeError::DanglingHandle { component: std::any::type_name::<TComponent>(), index })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn round_trip
+    (
+        format: Format
+    )
+    {
+        let mut serializer = Serializer::new(format);
+        serializer.field("number", &-7i32);
+        serializer.field("text", &"quoted \"line\"\nnext".to_string());
+        serializer.field("list", &vec![1u32, 2, 3]);
+        serializer.field("none", &None::<f32>);
+        serializer.field("some", &Some(0.5f32));
+
+        let mut deserializer = Deserializer::new(serializer.finish()).unwrap();
+        assert_eq!(deserializer.format(), format);
+        assert_eq!(deserializer.field::<i32>("number").unwrap(), -7);
+        assert_eq!(deserializer.field::<String>("text").unwrap(), "quoted \"line\"\nnext");
+        assert_eq!(deserializer.field::<Vec<u32>>("list").unwrap(), vec![1, 2, 3]);
+        assert_eq!(deserializer.field::<Option<f32>>("none").unwrap(), None);
+        assert_eq!(deserializer.field::<Option<f32>>("some").unwrap(), Some(0.5));
+    }
+
+    #[test]
+    fn round_trip_binary
+    ()
+    {
+        round_trip(Format::Binary);
+    }
+
+    #[test]
+    fn round_trip_text
+    ()
+    {
+        round_trip(Format::Text);
+    }
+
+    fn storage_round_trip
+    (
+        format: Format
+    )
+    {
+        let mut storage = Storage::new();
+        let removed = storage.add(10u32);
+        let stale = storage.duplicate(&removed);
+        let enabled = storage.add(20);
+        let disabled = storage.add(30);
+        storage.remove(removed);
+        storage.disable(&disabled);
+
+        let mut serializer = Serializer::new(format);
+        serializer.field("storage", &storage);
+        serializer.field("handles", &vec![disabled.clone(), enabled.clone()]);
+        serializer.field("stale", &stale);
+
+        // Handles loaded after their storage point to its new entries, saved slots need not match the new ones.
+        let mut deserializer = Deserializer::new(serializer.finish()).unwrap();
+        let loaded: Storage<u32> = deserializer.field("storage").unwrap();
+        let handles: Vec<Handle<u32>> = deserializer.field("handles").unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(*loaded.read(&handles[0]).unwrap(), 30);
+        assert_eq!(*loaded.read(&handles[1]).unwrap(), 20);
+        assert!(!loaded.is_enabled(&handles[0]));
+        assert!(loaded.is_enabled(&handles[1]));
+        assert!(matches!(deserializer.field::<Handle<u32>>("stale"), Err(SerializeError::DanglingHandle { index: 0, .. })));
+
+        // Without the storage, handles need one bound instead.
+        let mut serializer = Serializer::new(format);
+        serializer.field("handles", &vec![disabled.clone(), enabled.clone()]);
+        let bytes = serializer.finish();
+        let mut deserializer = Deserializer::new(bytes.clone()).unwrap();
+        assert!(matches!(deserializer.field::<Vec<Handle<u32>>>("handles"), Err(SerializeError::MissingStorage(_))));
+
+        let mut deserializer = Deserializer::new(bytes).unwrap();
+        deserializer.bind_storage(&storage);
+        let handles: Vec<Handle<u32>> = deserializer.field("handles").unwrap();
+        assert_eq!(*storage.read(&handles[0]).unwrap(), 30);
+        assert_eq!(*storage.read(&handles[1]).unwrap(), 20);
+    }
+
+    #[test]
+    fn storage_round_trip_binary
+    ()
+    {
+        storage_round_trip(Format::Binary);
+    }
+
+    #[test]
+    fn storage_round_trip_text
+    ()
+    {
+        storage_round_trip(Format::Text);
+    }
+
+    #[test]
+    fn storage_keys
+    ()
+    {
+        for format in [Format::Binary, Format::Text]
+        {
+            let mut storage = Storage::new();
+            let a = storage.add("a".to_string());
+            let b = storage.add("b".to_string());
+            let gone = storage.add("gone".to_string());
+
+            let mut serializer = Serializer::new(format);
+            serializer.field("keys", &StorageKeys::new(&storage, String::clone));
+            serializer.field("handles", &vec![b, a]);
+            serializer.field("gone", &gone);
+
+            // Rebuilt in another order and without one entry, handles follow the keys.
+            let mut rebuilt = Storage::new();
+            let _kept = [rebuilt.add("b".to_string()), rebuilt.add("a".to_string())];
+
+            let mut deserializer = Deserializer::new(serializer.finish()).unwrap();
+            let keys: StorageKeys<String> = deserializer.field("keys").unwrap();
+            deserializer.bind_storage_by_key(&rebuilt, &keys, String::clone);
+            let handles: Vec<Handle<String>> = deserializer.field("handles").unwrap();
+            assert_eq!(*rebuilt.read(&handles[0]).unwrap(), "b");
+            assert_eq!(*rebuilt.read(&handles[1]).unwrap(), "a");
+            assert!(matches!(deserializer.field::<Handle<String>>("gone"), Err(SerializeError::DanglingHandle { index: 2, .. })));
+        }
+    }
+
+    #[test]
+    fn truncated
+    ()
+    {
+        for format in [Format::Binary, Format::Text]
+        {
+            let mut serializer = Serializer::new(format);
+            serializer.field("list", &vec![1u32, 2, 3]);
+            let mut bytes = serializer.finish();
+            bytes.truncate(bytes.len() - 3);
+
+            let result = Deserializer::new(bytes).and_then(|mut deserializer| deserializer.field::<Vec<u32>>("list"));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn counts_and_lengths_from_the_file
+    ()
+    {
+        // Counts and lengths that are far beyond the data fail instead of allocating or overflowing.
+        let mut bytes = Serializer::new(Format::Binary).finish();
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        let mut deserializer = Deserializer::new(bytes.clone()).unwrap();
+        assert!(matches!(deserializer.field::<Vec<u32>>("list"), Err(SerializeError::UnexpectedEnd)));
+        let mut deserializer = Deserializer::new(bytes).unwrap();
+        assert!(matches!(deserializer.field::<String>("text"), Err(SerializeError::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn not_nokden
+    ()
+    {
+        assert!(matches!(Deserializer::new(b"something else".to_vec()), Err(SerializeError::NotNokden)));
+    }
+}
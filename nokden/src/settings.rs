@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::{env, fs, io};
+use std::path::{Path, PathBuf};
+use crate::console::{ConsoleCommand, ConsoleCommandParameter};
+use crate::save::write_atomic;
+
+pub const SETTINGS_FILE: &str = "settings.cfg";
+
+/// Cvar values read from disk, applied when a cvar is registered.
+/// The file next to the asset path holds the settings of the project, the file of the user overrides them.
+/// Changes are only ever written to the file of the user.
+/// The file of the user also keeps aliases and key binds as `alias` and `bind` lines, issued at startup.
+pub struct Settings
+{
+    user_path: Option<PathBuf>,
+    project: BTreeMap<String, ConsoleCommandParameter>,
+    user: BTreeMap<String, ConsoleCommandParameter>,
+    aliases: BTreeMap<String, String>,
+
+    /// Commands by key name.
+    binds: BTreeMap<String, String>
+}
+
+impl Settings
+{
+    /// Without files, every cvar keeps its default and nothing is written.
+    pub fn empty
+    ()
+    -> Settings
+    {
+        Settings
+        {
+            user_path: None,
+            project: BTreeMap::new(),
+            user: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            binds: BTreeMap::new()
+        }
+    }
+
+    /// Missing files count as empty, malformed lines are printed and skipped.
+    /// Aliases and binds are only taken from the file of the user.
+    pub fn load
+    (
+        project_path: &Path,
+        user_path: Option<PathBuf>
+    )
+    -> Settings
+    {
+        let mut settings = Settings { user_path, ..Settings::empty() };
+        settings.project = Self::read(project_path, &mut BTreeMap::new(), &mut BTreeMap::new());
+        if let Some(user_path) = &settings.user_path
+        {
+            settings.user = Self::read(user_path, &mut settings.aliases, &mut settings.binds);
+        }
+        settings
+    }
+
+    /// Directory for files of the user of an application, created when written to.
+    pub fn user_directory
+    (
+        app_name: &str
+    )
+    -> Option<PathBuf>
+    {
+        let base = if cfg!(windows)
+        {
+            env::var_os("APPDATA").map(PathBuf::from)
+        }
+        else
+        {
+            env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        };
+
+        base.map(|base| base.join(app_name))
+    }
+
+    pub fn project_value
+    (
+        &self,
+        name: &str
+    )
+    -> Option<&ConsoleCommandParameter>
+    {
+        self.project.get(name)
+    }
+
+    pub fn user_value
+    (
+        &self,
+        name: &str
+    )
+    -> Option<&ConsoleCommandParameter>
+    {
+        self.user.get(name)
+    }
+
+    /// [None] removes the override so the project setting applies again.
+    pub(crate) fn set_user_value
+    (
+        &mut self,
+        name: &str,
+        value: Option<ConsoleCommandParameter>
+    )
+    {
+        match value
+        {
+            Some(value) => self.user.insert(name.to_string(), value),
+            None => self.user.remove(name)
+        };
+    }
+
+    /// [None] removes the alias, false if that changed nothing.
+    pub(crate) fn set_alias
+    (
+        &mut self,
+        name: &str,
+        commands: Option<&str>
+    )
+    -> bool
+    {
+        set_command(&mut self.aliases, name, commands)
+    }
+
+    /// [None] removes the bind of the key, false if that changed nothing.
+    pub(crate) fn set_bind
+    (
+        &mut self,
+        key: &str,
+        command: Option<&str>
+    )
+    -> bool
+    {
+        set_command(&mut self.binds, key, command)
+    }
+
+    /// The aliases then the binds of the user as console commands, aliases first so binds can use them.
+    pub fn commands
+    (
+        &self
+    )
+    -> Vec<String>
+    {
+        let aliases = self.aliases.iter().map(|(name, commands)| format!("alias {} {}", name, ConsoleCommandParameter::from(commands.as_str())));
+        let binds = self.binds.iter().map(|(key, command)| format!("bind {} {}", key, ConsoleCommandParameter::from(command.as_str())));
+        aliases.chain(binds).collect()
+    }
+
+    /// Overrides of cvars that are not registered are written back as read.
+    pub(crate) fn write
+    (
+        &self
+    )
+    -> io::Result<()>
+    {
+        let user_path = match &self.user_path
+        {
+            Some(user_path) => user_path,
+            None => return Ok(())
+        };
+
+        let mut text = String::from("# Overrides the settings of the project, written when a cvar, alias or bind changes.\n");
+        for (name, value) in &self.user
+        {
+            text.push_str(&format!("{} = {}\n", name, value));
+        }
+        for command in self.commands()
+        {
+            text.push_str(&command);
+            text.push('\n');
+        }
+
+        write_atomic(user_path, text.as_bytes())
+    }
+
+    fn read
+    (
+        path: &Path,
+        aliases: &mut BTreeMap<String, String>,
+        binds: &mut BTreeMap<String, String>
+    )
+    -> BTreeMap<String, ConsoleCommandParameter>
+    {
+        let mut values = BTreeMap::new();
+
+        let text = match fs::read_to_string(path)
+        {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return values,
+            Err(err) =>
+            {
+                error!("settings", "Settings not read from {}: {}", path.to_string_lossy(), err);
+                return values;
+            }
+        };
+
+        for (number, line) in text.lines().enumerate()
+        {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+
+            if line.starts_with("alias ") || line.starts_with("bind ")
+            {
+                let command = ConsoleCommand::parse(line).map_err(|err| err.to_string());
+                match command.as_ref().map(|command| (command.keyword(), command.get::<String>(0), command.get::<String>(1)))
+                {
+                    Ok(("alias", Some(name), Some(commands))) => { aliases.insert(name, commands); }
+                    Ok(("bind", Some(key), Some(command))) => { binds.insert(key, command); }
+                    Ok(_) => warn!("settings", "{}:{}: Expected '{} name \"commands\"'.", path.to_string_lossy(), number + 1, line.split(' ').next().unwrap_or_default()),
+                    Err(err) => warn!("settings", "{}:{}: {}", path.to_string_lossy(), number + 1, err)
+                }
+                continue;
+            }
+
+            let parsed = match line.split_once('=')
+            {
+                Some((name, value)) => ConsoleCommandParameter::from_text(value.trim()).map(|value| (name.trim().to_string(), value)).map_err(|err| err.to_string()),
+                None => Err(String::from("Expected 'name = value'."))
+            };
+
+            match parsed
+            {
+                Ok((name, value)) => { values.insert(name, value); }
+                Err(err) => warn!("settings", "{}:{}: {}", path.to_string_lossy(), number + 1, err)
+            }
+        }
+
+        values
+    }
+}
+
+/// Shared by aliases and binds, false if nothing changed.
+fn set_command
+(
+    commands: &mut BTreeMap<String, String>,
+    name: &str,
+    command: Option<&str>
+)
+-> bool
+{
+    match command
+    {
+        Some(command) if commands.get(name).map(String::as_str) != Some(command) =>
+        {
+            commands.insert(name.to_string(), command.to_string());
+            true
+        }
+        Some(_) => false,
+        None => commands.remove(name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::cvars::{Cvar, Cvars};
+
+    fn files
+    (
+        name: &str,
+        project: &str,
+        user: &str
+    )
+    -> (PathBuf, PathBuf)
+    {
+        let directory = crate::test_directory(name);
+        let project_path = directory.join("project.cfg");
+        let user_path = directory.join("user").join(SETTINGS_FILE);
+        fs::write(&project_path, project).unwrap();
+        fs::create_dir_all(user_path.parent().unwrap()).unwrap();
+        fs::write(&user_path, user).unwrap();
+        (project_path, user_path)
+    }
+
+    #[test]
+    fn load
+    ()
+    {
+        let (project_path, user_path) = files
+        (
+            "settings-load",
+            "# Project.\ncount = 3\nspeed = 1.5\nname = \"level one\"\nalias quit \"echo no\"\nnot a setting\n",
+            "speed = 2.0\n\nalias jump \"move up; wait; move down\"\nbind space jump\n"
+        );
+
+        let settings = Settings::load(&project_path, Some(user_path.clone()));
+        assert_eq!(settings.project_value("count"), Some(&ConsoleCommandParameter::Integer(3)));
+        assert_eq!(settings.user_value("speed"), Some(&ConsoleCommandParameter::Float(2.0)));
+        assert_eq!(settings.user_value("count"), None);
+        assert_eq!(settings.commands(), ["alias jump \"move up; wait; move down\"", "bind space \"jump\""]);
+
+        // The user overrides the project, which overrides the default.
+        let mut cvars = Cvars::new(settings);
+        cvars.register(Cvar::new("count", 0));
+        cvars.register(Cvar::new("speed", 1.0f32));
+        cvars.register(Cvar::new("name", ""));
+        cvars.register(Cvar::new("other", 7));
+        assert_eq!(cvars.get::<i64>("count"), Some(3));
+        assert_eq!(cvars.get::<f32>("speed"), Some(2.0));
+        assert_eq!(cvars.get::<String>("name").as_deref(), Some("level one"));
+        assert_eq!(cvars.get::<i64>("other"), Some(7));
+
+        // Missing files are empty.
+        let settings = Settings::load(&project_path.with_file_name("missing.cfg"), None);
+        assert!(settings.project_value("count").is_none());
+        assert!(settings.commands().is_empty());
+    }
+
+    #[test]
+    fn write
+    ()
+    {
+        let (project_path, user_path) = files("settings-write", "count = 3\n", "speed = 2.0\nunknown = 5\n");
+
+        let mut cvars = Cvars::new(Settings::load(&project_path, Some(user_path.clone())));
+        cvars.register(Cvar::new("count", 0));
+        cvars.register(Cvar::new("speed", 1.0f32));
+        cvars.register(Cvar::new("size", 10));
+
+        // Back to the value of the project, the override is dropped.
+        assert!(cvars.set("speed", 1.0f32).is_ok());
+        assert!(cvars.set("size", 12).is_ok());
+        assert!(cvars.set_transient("count", 4).is_ok());
+        assert!(cvars.settings_mut().set_alias("jump", Some("move up")));
+        cvars.save_settings();
+
+        let settings = Settings::load(&project_path, Some(user_path.clone()));
+        assert_eq!(settings.user_value("speed"), None);
+        assert_eq!(settings.user_value("size"), Some(&ConsoleCommandParameter::Integer(12)));
+        assert_eq!(settings.user_value("count"), None);
+        assert_eq!(settings.user_value("unknown"), Some(&ConsoleCommandParameter::Integer(5)));
+        assert_eq!(settings.commands(), ["alias jump \"move up\""]);
+
+        // Nothing is written without a file of the user.
+        let mut cvars = Cvars::new(Settings::load(&project_path, None));
+        cvars.register(Cvar::new("count", 0));
+        assert!(cvars.set("count", 9).is_ok());
+        cvars.save_settings();
+        assert_eq!(fs::read_to_string(&project_path).unwrap(), "count = 3\n");
+    }
+}
@@ -0,0 +1,360 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::archive::{self, Archive, ArchiveError, ARCHIVE_EXTENSION};
+
+/// Folder of a project holding its settings, scripts and [IMPORT_FOLDER].
+pub const PROJECT_FOLDER: &str = "@main";
+
+/// The assets inside [PROJECT_FOLDER].
+pub const IMPORT_FOLDER: &str = "import";
+
+/// Next to the executable, every folder or archive in it is mounted as a mod in name order.
+pub const MODS_FOLDER: &str = "mods";
+
+/// Where assets are looked up, in an order where later mounts override earlier ones.
+/// Assets are named by relative paths like `meshes/tile.obj`, the same in every mount.
+pub struct Vfs
+{
+    mounts: Vec<Mount>
+}
+
+pub struct Mount
+{
+    /// Shown by the `mounts` command, `archive <file>`, `install`, `source`, `argument` or `mod <name>` for the standard ones.
+    pub name: String,
+    pub source: MountSource
+}
+
+pub enum MountSource
+{
+    Directory(PathBuf),
+    Archive(Archive)
+}
+
+#[derive(Debug)]
+pub enum VfsError
+{
+    /// No mount has the asset, or its path leaves the mounts.
+    NotFound(PathBuf),
+    Io(PathBuf, io::Error),
+    Archive(ArchiveError)
+}
+
+impl fmt::Display for VfsError
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            VfsError::NotFound(asset) => write!(f, "Asset {} is in no mount.", asset.to_string_lossy()),
+            VfsError::Io(path, err) => write!(f, "{}: {}", path.to_string_lossy(), err),
+            VfsError::Archive(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl fmt::Display for MountSource
+{
+    fn fmt
+    (
+        &self,
+        f: &mut fmt::Formatter
+    )
+    -> fmt::Result
+    {
+        match self
+        {
+            MountSource::Directory(directory) => write!(f, "{}", directory.to_string_lossy()),
+            MountSource::Archive(archive) => write!(f, "{} ({} assets)", archive.path().to_string_lossy(), archive.len())
+        }
+    }
+}
+
+impl Default for Vfs
+{
+    fn default
+    ()
+    -> Vfs
+    {
+        Vfs::new()
+    }
+}
+
+impl Vfs
+{
+    pub fn new
+    ()
+    -> Vfs
+    {
+        Vfs
+        {
+            mounts: Vec::new()
+        }
+    }
+
+    /// Mounts in the order of the standard layout, lowest priority first:
+    /// * Archives in the project folder next to the executable, in name order.
+    /// * `install`, the project folder next to the executable.
+    /// * `source`, the project folder in a parent directory of the executable, as when built from source.
+    /// * `argument`, the directory given by `--asset-path`.
+    /// * Every folder or archive in [MODS_FOLDER] next to the executable, then the ones given by `--mod`.
+    ///
+    /// Directories that do not exist are left out, archives that can not be opened are reported and left out.
+    pub fn standard
+    (
+        exe_directory: &Path,
+        asset_path: Option<&Path>,
+        mods: &[PathBuf]
+    )
+    -> Vfs
+    {
+        let mut vfs = Vfs::new();
+        for archive in sorted_entries(&exe_directory.join(PROJECT_FOLDER)).into_iter().filter(|path| is_archive(path))
+        {
+            let name = format!("archive {}", file_name(&archive));
+            vfs.mount_path(&name, archive);
+        }
+
+        vfs.mount_path("install", exe_directory.join(PROJECT_FOLDER).join(IMPORT_FOLDER));
+        if let Some(source) = source_project(exe_directory)
+        {
+            vfs.mount_path("source", source.join(IMPORT_FOLDER));
+        }
+        if let Some(asset_path) = asset_path
+        {
+            vfs.mount_path("argument", asset_path.to_path_buf());
+        }
+
+        for path in sorted_entries(&exe_directory.join(MODS_FOLDER)).into_iter().chain(mods.iter().cloned())
+        {
+            let name = format!("mod {}", file_name(&path));
+            vfs.mount_path(&name, path);
+        }
+
+        vfs
+    }
+
+    /// Mounted with the highest priority so far.
+    pub fn mount
+    (
+        &mut self,
+        name: &str,
+        source: MountSource
+    )
+    {
+        self.mounts.push(Mount { name: name.to_string(), source });
+    }
+
+    /// A directory or an archive, left out if it is neither.
+    fn mount_path
+    (
+        &mut self,
+        name: &str,
+        path: PathBuf
+    )
+    {
+        if path.is_dir()
+        {
+            self.mount(name, MountSource::Directory(path));
+        }
+        else if is_archive(&path)
+        {
+            match Archive::open(&path)
+            {
+                Ok(archive) => self.mount(name, MountSource::Archive(archive)),
+                Err(err) => warn!("vfs", "Archive not mounted: {}", err)
+            }
+        }
+    }
+
+    /// False if there was no mount of that name.
+    pub fn unmount
+    (
+        &mut self,
+        name: &str
+    )
+    -> bool
+    {
+        let count = self.mounts.len();
+        self.mounts.retain(|mount| mount.name != name);
+        self.mounts.len() != count
+    }
+
+    /// Lowest priority first.
+    pub fn mounts
+    (
+        &self
+    )
+    -> &[Mount]
+    {
+        &self.mounts
+    }
+
+    /// The mount with the highest priority having the asset.
+    /// Paths leaving the mounts, absolute ones or with `..`, are never found.
+    pub fn resolve
+    (
+        &self,
+        asset: &Path
+    )
+    -> Option<&Mount>
+    {
+        archive::asset_name(asset)?;
+        self.mounts.iter().rev().find(|mount| match &mount.source
+        {
+            MountSource::Directory(directory) => directory.join(asset).is_file(),
+            MountSource::Archive(archive) => archive.contains(asset)
+        })
+    }
+
+    pub fn exists
+    (
+        &self,
+        asset: &Path
+    )
+    -> bool
+    {
+        self.resolve(asset).is_some()
+    }
+
+    pub fn read
+    (
+        &self,
+        asset: &Path
+    )
+    -> Result<Vec<u8>, VfsError>
+    {
+        match self.resolve(asset).map(|mount| &mount.source)
+        {
+            Some(MountSource::Directory(directory)) =>
+            {
+                let path = directory.join(asset);
+                fs::read(&path).map_err(|err| VfsError::Io(path, err))
+            }
+            Some(MountSource::Archive(archive)) => archive.read(asset).map_err(VfsError::Archive),
+            None => Err(VfsError::NotFound(asset.to_path_buf()))
+        }
+    }
+}
+
+/// Holds the settings and scripts: the parent of `--asset-path` if given, else the source project or the installed one.
+pub fn project_directory
+(
+    exe_directory: &Path,
+    asset_path: Option<&Path>
+)
+-> PathBuf
+{
+    asset_path.and_then(Path::parent).map(Path::to_path_buf)
+        .or_else(|| source_project(exe_directory))
+        .unwrap_or_else(|| exe_directory.join(PROJECT_FOLDER))
+}
+
+/// The nearest [PROJECT_FOLDER] with assets above the executable, like from `target/debug`.
+fn source_project
+(
+    exe_directory: &Path
+)
+-> Option<PathBuf>
+{
+    exe_directory.ancestors().skip(1).map(|directory| directory.join(PROJECT_FOLDER)).find(|project| project.join(IMPORT_FOLDER).is_dir())
+}
+
+fn sorted_entries
+(
+    directory: &Path
+)
+-> Vec<PathBuf>
+{
+    let mut paths: Vec<PathBuf> = match fs::read_dir(directory)
+    {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        Err(_) => Vec::new()
+    };
+    paths.sort();
+    paths
+}
+
+fn is_archive
+(
+    path: &Path
+)
+-> bool
+{
+    path.is_file() && path.extension().is_some_and(|extension| extension == ARCHIVE_EXTENSION)
+}
+
+fn file_name
+(
+    path: &Path
+)
+-> String
+{
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn override_order
+    ()
+    {
+        let directory = crate::test_directory("vfs");
+        let install = directory.join("install");
+        let mod_directory = directory.join("mod");
+        fs::create_dir_all(install.join("meshes")).unwrap();
+        fs::create_dir_all(mod_directory.join("meshes")).unwrap();
+        fs::write(install.join("meshes").join("tile.obj"), "install").unwrap();
+        fs::write(install.join("only.txt"), "install").unwrap();
+        fs::write(mod_directory.join("meshes").join("tile.obj"), "mod").unwrap();
+        fs::write(directory.join("outside.txt"), "outside").unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_path("install", install);
+        vfs.mount_path("mod", mod_directory);
+        vfs.mount_path("missing", directory.join("missing"));
+        assert_eq!(vfs.mounts().len(), 2);
+
+        let tile = Path::new("meshes/tile.obj");
+        assert_eq!(vfs.resolve(tile).unwrap().name, "mod");
+        assert_eq!(vfs.read(tile).unwrap(), b"mod");
+        assert_eq!(vfs.read(Path::new("only.txt")).unwrap(), b"install");
+        assert!(matches!(vfs.read(Path::new("../outside.txt")), Err(VfsError::NotFound(_))));
+
+        assert!(vfs.unmount("mod"));
+        assert!(!vfs.unmount("mod"));
+        assert_eq!(vfs.read(tile).unwrap(), b"install");
+    }
+
+    #[test]
+    fn archives
+    ()
+    {
+        let directory = crate::test_directory("vfs-archive");
+        let import = directory.join("import");
+        let over = directory.join("over");
+        fs::create_dir_all(&import).unwrap();
+        fs::create_dir_all(&over).unwrap();
+        fs::write(import.join("a.txt"), "archive").unwrap();
+        fs::write(import.join("b.txt"), "archive").unwrap();
+        fs::write(over.join("a.txt"), "directory").unwrap();
+        let archive = directory.join("import.nka");
+        Archive::pack(&import, &archive).unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_path("archive", archive);
+        vfs.mount_path("over", over);
+        assert_eq!(vfs.read(Path::new("a.txt")).unwrap(), b"directory");
+        assert_eq!(vfs.read(Path::new("b.txt")).unwrap(), b"archive");
+    }
+}
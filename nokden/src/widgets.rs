@@ -13,10 +13,11 @@ pub enum ConsoleState
 pub struct ConsoleWidget
 {
     state: ConsoleState,
-    typing: String,
     input_open_console: Handle<Mapping>,    
     input_submit: Handle<Mapping>,
-    input_info: Handle<Mapping>
+    input_complete: Handle<Mapping>,
+    input_history_previous: Handle<Mapping>,
+    input_history_next: Handle<Mapping>
 }
 
 impl ConsoleWidget
@@ -28,16 +29,19 @@ impl ConsoleWidget
     -> ConsoleWidget
     {
         let input_open_console = input.add_mapping(Mapping::new("(GUI) Toggle Console", MethodKM::F1));
-        let input_info = input.add_mapping(Mapping::new("(GUI) Print All Console Commands", MethodKM::TAB));
+        let input_complete = input.add_mapping(Mapping::new("(GUI) Complete Console Command", MethodKM::TAB));
+        let input_history_previous = input.add_mapping(Mapping::new("(GUI) Previous Console Command", MethodKM::ArrowUp));
+        let input_history_next = input.add_mapping(Mapping::new("(GUI) Next Console Command", MethodKM::ArrowDown));
         let input_submit = input.add_mapping(Mapping::new("(GUI) Console Submit", MethodKM::Enter)); // TODO Perhaps needs to go to a more global place.
 
         ConsoleWidget
         {
             state: ConsoleState::Closed,
-            typing: String::new(),
             input_submit,
             input_open_console,
-            input_info
+            input_complete,
+            input_history_previous,
+            input_history_next
         }
     }    
 
@@ -63,27 +67,44 @@ impl ConsoleWidget
             }
             ConsoleState::Opened =>
             {
-                input.check_typing(&mut self.typing);   
-                framework.window().set_title(&self.typing);       
+                input.check_typing(framework.console().typing_mut());
 
                 if input.check_once(&self.input_submit)
                 {
-                    framework.command(&self.typing);
-                    self.typing.clear();
+                    if let Some(line) = framework.console().submit()
+                    {
+                        framework.command(&line);
+                    }
                 }  
 
-                if input.check_once(&self.input_info)
+                if input.check_once(&self.input_complete)
                 {
-                    println!();
-                    println!("Console commands:");
-                    framework.command("info");                    
-                    //self.typing.clear();
+                    framework.console_complete();
                 }  
 
+                if input.check_once(&self.input_history_previous)
+                {
+                    framework.console().history_previous();
+                }
+
+                if input.check_once(&self.input_history_next)
+                {
+                    framework.console().history_next();
+                }
+
+                let typing = framework.console().typing().to_string();
+                if let Some(window) = framework.window()
+                {
+                    window.set_title(&typing);
+                }
+
                 if input.check_once(&self.input_open_console)
                 {       
                     input.mode(Mode::Normal);
-                    framework.window().set_title("TITLE NEEDS FIXING YOU LAZY BUM!");
+                    if let Some(window) = framework.window()
+                    {
+                        window.set_title("TITLE NEEDS FIXING YOU LAZY BUM!");
+                    }
                     self.state = ConsoleState::Closed;
                 }        
 